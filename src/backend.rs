@@ -0,0 +1,119 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use response::Attachment;
+use types::{Channel, Name, User};
+use NiancatHandler;
+
+/// A message received from a chat service, after it has been validated by the backend.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct IncomingMessage {
+    pub channel: Channel,
+    pub user: User,
+    pub text: String,
+}
+
+/// The chat service that niancat is connected to. The backend delivers each `IncomingMessage` to
+/// `NiancatHandler::handle_message`, and the handler uses the backend to look up users and send
+/// responses.
+pub trait ChatBackend {
+    /// Look up the name of a user, given the user id.
+    fn user_name(&self, user: &User) -> Option<Name>;
+    /// Check if a user is a member of a channel.
+    fn is_member(&self, user: &User, channel: &Channel) -> bool;
+    /// Look up the id of a user, given the name.
+    fn find_user(&self, name: &Name) -> Option<User>;
+    /// The user id of niancat itself, if the chat service has one, so that it never answers itself.
+    fn own_user(&self) -> Option<User>;
+    /// The channel for private messages with a user, opened if there is none yet.
+    fn private_channel(&mut self, user: &User) -> Option<Channel>;
+    fn send_message(&mut self, channel: &Channel, text: &str) -> Result<(), String>;
+    /// Send a message formatted as an attachment, with its plain text as the fallback. Backends that
+    /// can't show attachments send the plain text.
+    fn send_attachment(&mut self, channel: &Channel, text: &str, _attachment: &Attachment) -> Result<(), String> {
+        self.send_message(channel, text)
+    }
+}
+
+/// A backend that plays a script of messages, and records all messages sent by niancat. This is
+/// used to test complete conversations with the bot.
+pub struct ScriptedBackend {
+    users: HashMap<User, Name>,
+    members: HashSet<(Channel, User)>,
+    script: VecDeque<IncomingMessage>,
+    own_user: Option<User>,
+    pub sent: Vec<(Channel, String)>,
+}
+
+impl ScriptedBackend {
+    pub fn new() -> ScriptedBackend {
+        ScriptedBackend {
+            users: HashMap::new(),
+            members: HashSet::new(),
+            script: VecDeque::new(),
+            own_user: None,
+            sent: vec![],
+        }
+    }
+
+    pub fn add_user(&mut self, user: &User, name: &Name) {
+        self.users.insert(user.clone(), name.clone());
+    }
+
+    /// Make a user niancat itself.
+    pub fn set_own_user(&mut self, user: &User) {
+        self.own_user = Some(user.clone());
+    }
+
+    pub fn add_member(&mut self, channel: &Channel, user: &User) {
+        self.members.insert((channel.clone(), user.clone()));
+    }
+
+    /// Add a message to the end of the script.
+    pub fn push_message(&mut self, channel: &Channel, user: &User, text: &str) {
+        self.script.push_back(IncomingMessage {
+            channel: channel.clone(),
+            user: user.clone(),
+            text: text.to_string(),
+        });
+    }
+
+    /// Deliver all scripted messages to the handler, in order.
+    pub fn run(&mut self, handler: &mut NiancatHandler) {
+        while let Some(message) = self.script.pop_front() {
+            handler.handle_message(self, &message);
+        }
+    }
+
+    /// Take all messages sent so far, so the next part of a conversation can be checked on its own.
+    pub fn take_sent(&mut self) -> Vec<(Channel, String)> {
+        self.sent.drain(..).collect()
+    }
+}
+
+impl ChatBackend for ScriptedBackend {
+    fn user_name(&self, user: &User) -> Option<Name> {
+        self.users.get(user).cloned()
+    }
+
+    fn is_member(&self, user: &User, channel: &Channel) -> bool {
+        self.members.contains(&(channel.clone(), user.clone()))
+    }
+
+    fn find_user(&self, name: &Name) -> Option<User> {
+        self.users.iter().find(|&(_, n)| n == name).map(|(user, _)| user.clone())
+    }
+
+    fn own_user(&self) -> Option<User> {
+        self.own_user.clone()
+    }
+
+    /// Private channels are named like Slack's, with a D before the user id.
+    fn private_channel(&mut self, &User(ref user): &User) -> Option<Channel> {
+        Some(Channel(format!("D{}", user)))
+    }
+
+    fn send_message(&mut self, channel: &Channel, text: &str) -> Result<(), String> {
+        self.sent.push((channel.clone(), text.to_string()));
+        Ok(())
+    }
+}
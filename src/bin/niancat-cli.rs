@@ -0,0 +1,164 @@
+extern crate niancat;
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+use niancat::NiancatHandler;
+use niancat::backend::{ChatBackend, IncomingMessage};
+use niancat::dictionary::Dictionary;
+use niancat::store::{State, MemoryStateStore};
+use niancat::types::{Channel, Name, User, DEFAULT_PUZZLE_LENGTH};
+
+/// Prints all responses to stdout. Users are simply named by their user id, and are members of the
+/// channels they have written in.
+struct TerminalBackend {
+    members: HashSet<(Channel, User)>,
+}
+
+impl ChatBackend for TerminalBackend {
+    fn user_name(&self, &User(ref user): &User) -> Option<Name> {
+        Some(Name(user.clone()))
+    }
+
+    fn is_member(&self, user: &User, channel: &Channel) -> bool {
+        self.members.contains(&(channel.clone(), user.clone()))
+    }
+
+    fn find_user(&self, &Name(ref name): &Name) -> Option<User> {
+        Some(User(name.clone()))
+    }
+
+    /// Only users write in the terminal.
+    fn own_user(&self) -> Option<User> {
+        None
+    }
+
+    fn private_channel(&mut self, &User(ref user): &User) -> Option<Channel> {
+        Some(private_channel(user))
+    }
+
+    fn send_message(&mut self, channel: &Channel, text: &str) -> Result<(), String> {
+        println!("[{}] {}", channel_display_name(channel), text.trim());
+        Ok(())
+    }
+}
+
+// Public channels are simulated as Slack channel ids "C<name>", and private channels as "D<user>".
+fn public_channel(name: &str) -> Channel {
+    Channel(format!("C{}", name))
+}
+
+fn private_channel(user: &str) -> Channel {
+    Channel(format!("D{}", user))
+}
+
+fn channel_display_name(&Channel(ref c): &Channel) -> String {
+    if c.starts_with("D") {
+        format!("@{}", &c[1..])
+    } else {
+        format!("#{}", &c[1..])
+    }
+}
+
+/// Parse a line on the form `@alice> text`, `#general> text` or `alice#general> text`. A message in
+/// a public channel without a user is sent by the last user seen.
+fn parse_line(line: &str, last_user: &str) -> Option<IncomingMessage> {
+    let split = match line.find('>') {
+        None => return None,
+        Some(i) => i,
+    };
+    let (prefix, text) = (line[..split].trim(), line[split + 1..].trim());
+
+    let (user, channel) = if prefix.starts_with('@') {
+        let user = &prefix[1..];
+        (user, private_channel(user))
+    } else if let Some(i) = prefix.find('#') {
+        let user = if i == 0 { last_user } else { &prefix[..i] };
+        (user, public_channel(&prefix[i + 1..]))
+    } else {
+        return None;
+    };
+
+    if user.is_empty() || text.is_empty() {
+        return None;
+    }
+
+    Some(IncomingMessage {
+        channel: channel,
+        user: User(user.to_string()),
+        text: text.to_string(),
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() < 2 || args.len() > 4 {
+        println!("Usage: niancat-cli <dictionary> [<channel>[,<channel>...] [<puzzle length>]]");
+        std::process::exit(1);
+    }
+    let channel_names: Vec<String> = args.get(2).cloned().unwrap_or("general".to_string())
+        .split(',').map(|x| x.trim().to_string()).collect();
+    let puzzle_length = match args.get(3).map(|x| x.parse::<usize>()) {
+        None => DEFAULT_PUZZLE_LENGTH,
+        Some(Ok(n)) if n >= 2 => n,
+        Some(Ok(n)) => {
+            println!("The puzzle length must be at least 2, but is {}", n);
+            std::process::exit(1);
+        },
+        Some(Err(e)) => {
+            println!("Invalid puzzle length, reason: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let dictionary = match Dictionary::from_file(&args[1]) {
+        Ok(d) => d,
+        Err(e) => {
+            println!("Could not load dictionary, reason: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut handler = NiancatHandler::new(&dictionary);
+    for name in &channel_names {
+        handler.add_game(name, public_channel(name), Box::new(MemoryStateStore::default()), &State::default());
+    }
+    handler.set_puzzle_length(puzzle_length);
+    let mut backend = TerminalBackend { members: HashSet::new() };
+
+    println!("Write messages as `@user> text` for private messages, and `#channel> text` or");
+    println!("`user#channel> text` for public messages. Games are played in #{}.", channel_names.join(", #"));
+
+    let mut last_user = "spelare".to_string();
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {},
+            Err(e) => {
+                println!("Could not read from stdin, reason: {}", e);
+                break;
+            }
+        }
+
+        match parse_line(&line, &last_user) {
+            None => println!("Could not understand the line. Expected `@user> text` or `#channel> text`."),
+            Some(message) => {
+                last_user = message.user.0.clone();
+                if !message.channel.is_private() {
+                    backend.members.insert((message.channel.clone(), message.user.clone()));
+                }
+                handler.handle_message(&mut backend, &message);
+            }
+        }
+
+        // Like the bot does between messages, so that replaced solutions, guesses and summaries
+        // are taken care of when they are due.
+        handler.poll(&mut backend);
+    }
+}
@@ -0,0 +1,65 @@
+extern crate niancat;
+
+use std::fs;
+use std::io::{Read, Write};
+
+use niancat::dictionary::split_lines;
+use niancat::index;
+use niancat::types::Word;
+
+/// Read the normalized words of a word list. Lines that are not valid UTF-8 are reported, with their
+/// line numbers.
+fn read_word_list(path: &str) -> Result<Vec<Word>, Vec<String>> {
+    let mut contents = vec![];
+    let read = fs::File::open(path).and_then(|mut f| f.read_to_end(&mut contents));
+    if let Err(e) = read {
+        return Err(vec![format!("{}: {}", path, e)]);
+    }
+
+    let (lines, bad_lines) = split_lines(&contents);
+    if !bad_lines.is_empty() {
+        return Err(bad_lines.iter().map(|n| format!("{}:{}: the line is not valid UTF-8", path, n)).collect());
+    }
+
+    Ok(lines.into_iter()
+       .map(|x| Word(x).normalize())
+       .filter(|&Word(ref x)| !x.is_empty())
+       .collect())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() < 3 {
+        println!("Usage: niancat-index <index> <word list> [<word list>...]");
+        println!("Compiles the word lists into an index, which can be used as a dictionary instead.");
+        std::process::exit(1);
+    }
+
+    let mut words = vec![];
+    let mut errors = vec![];
+    for path in &args[2..] {
+        match read_word_list(path) {
+            Ok(w) => words.extend(w),
+            Err(e) => errors.extend(e),
+        }
+    }
+
+    if !errors.is_empty() {
+        for e in &errors {
+            println!("{}", e);
+        }
+        println!("No index was written.");
+        std::process::exit(1);
+    }
+
+    let contents = index::encode(&words);
+    let written = fs::File::create(&args[1]).and_then(|mut f| f.write_all(&contents));
+    match written {
+        Ok(()) => println!("Wrote an index of {} words to {}", words.len(), args[1]),
+        Err(e) => {
+            println!("Could not write {}, reason: {}", args[1], e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use toml;
+
+use dictionary::DictionaryFiles;
+use generator::GeneratorOptions;
+use ratelimit::GuessLimit;
+use messages::{Language, LANGUAGE_CODES};
+use scheduler::Schedule;
+use parser;
+use roles::{Role, Roles, ROLE_NAMES};
+use types::{Name, DEFAULT_PUZZLE_LENGTH};
+
+/// Settings from a config file or from command line flags. Settings that are not given are `None`.
+/// Flags override the config file with `merge`, and `validate` checks the result.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct PartialConfig {
+    pub token: Option<String>,
+    /// The name of an environment variable with the token, so it doesn't have to be in the file.
+    pub token_env: Option<String>,
+    pub channels: Option<Vec<String>>,
+    pub dictionaries: Option<Vec<String>>,
+    pub allow_file: Option<String>,
+    pub deny_file: Option<String>,
+    pub state_file: Option<String>,
+    pub language: Option<String>,
+    /// Languages of single games, like "general=en".
+    pub channel_languages: Option<Vec<String>>,
+    pub reconnect_delay: Option<i64>,
+    pub puzzle_length: Option<i64>,
+    pub daily_puzzle_time: Option<String>,
+    pub reminder_times: Option<Vec<String>>,
+    pub publish_solutions_time: Option<String>,
+    pub prefer_unique_puzzles: Option<bool>,
+    pub avoid_recent_puzzles: Option<i64>,
+    pub commands: Option<Vec<String>>,
+    pub admins: Option<Vec<String>>,
+    pub setters: Option<Vec<String>>,
+    pub default_role: Option<String>,
+    pub report_spoilers: Option<bool>,
+    pub guess_stats: Option<bool>,
+    pub guess_burst: Option<i64>,
+    pub guess_interval: Option<i64>,
+}
+
+/// A validated configuration.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Config {
+    pub token: String,
+    /// The channel names of all games. The first is the default game.
+    pub channels: Vec<String>,
+    pub dictionaries: Vec<String>,
+    /// The files with the words added with `!tillåt` and removed with `!förbjud`.
+    pub allow_file: Option<String>,
+    pub deny_file: Option<String>,
+    pub state_file: String,
+    /// The language of all games, unless given in `channel_languages`.
+    pub language: Language,
+    pub channel_languages: Vec<(String, Language)>,
+    /// Seconds to wait before reconnecting to Slack.
+    pub reconnect_delay: u64,
+    pub puzzle_length: usize,
+    /// When a new puzzle is generated every day, in seconds after midnight UTC, if it is.
+    pub daily_puzzle_time: Option<i64>,
+    /// When to remind the channels of puzzles nobody has solved, in seconds after midnight UTC.
+    pub reminder_times: Vec<i64>,
+    /// When to publish the solutions and end the puzzles, in seconds after midnight UTC, if ever.
+    pub publish_solutions_time: Option<i64>,
+    /// Prefer generated puzzles with a single solution.
+    pub prefer_unique_puzzles: bool,
+    /// Don't generate puzzles used in this many days.
+    pub avoid_recent_puzzles: u32,
+    /// The enabled commands, or `None` if all commands are enabled.
+    pub commands: Option<Vec<String>>,
+    /// The names of the users that may run admin commands.
+    pub admins: Vec<String>,
+    /// The names of the users that may set puzzles.
+    pub setters: Vec<String>,
+    /// The role of everyone else.
+    pub default_role: Role,
+    /// Ask the admins to delete spoilers posted in public channels.
+    pub report_spoilers: bool,
+    /// Include stats of the guesses in the summary when a puzzle ends.
+    pub guess_stats: bool,
+    /// How many guesses a user may make at once, if guesses are limited.
+    pub guess_burst: Option<u32>,
+    /// Seconds until a user may make one more guess.
+    pub guess_interval: i64,
+}
+
+fn string_setting(table: &BTreeMap<String, toml::Value>, key: &str) -> Result<Option<String>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(v) => v.as_str()
+            .map(|x| Some(x.to_string()))
+            .ok_or(format!("The setting {} must be a string, but is a {}", key, v.type_str())),
+    }
+}
+
+fn integer_setting(table: &BTreeMap<String, toml::Value>, key: &str) -> Result<Option<i64>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(v) => v.as_integer()
+            .map(Some)
+            .ok_or(format!("The setting {} must be an integer, but is a {}", key, v.type_str())),
+    }
+}
+
+fn bool_setting(table: &BTreeMap<String, toml::Value>, key: &str) -> Result<Option<bool>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(v) => v.as_bool()
+            .map(Some)
+            .ok_or(format!("The setting {} must be true or false, but is a {}", key, v.type_str())),
+    }
+}
+
+/// Parse a time of day on the form HH:MM, into seconds after midnight.
+fn parse_time_of_day(s: &str) -> Option<i64> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 2 || parts.iter().any(|p| p.len() != 2) {
+        return None;
+    }
+
+    match (parts[0].parse::<i64>(), parts[1].parse::<i64>()) {
+        (Ok(h), Ok(m)) if h >= 0 && h < 24 && m >= 0 && m < 60 => Some(h * 60 * 60 + m * 60),
+        _ => None,
+    }
+}
+
+fn time_of_day(setting: &str, time: &str) -> Result<i64, String> {
+    parse_time_of_day(time).ok_or(format!("The {} must be on the form HH:MM, in UTC, but is {}", setting, time))
+}
+
+fn language_setting(code: &str) -> Result<Language, String> {
+    Language::from_code(code)
+        .ok_or(format!("Unsupported language {}. Supported languages: {}", code, LANGUAGE_CODES.join(", ")))
+}
+
+fn string_list_setting(table: &BTreeMap<String, toml::Value>, key: &str) -> Result<Option<Vec<String>>, String> {
+    let values = match table.get(key) {
+        None => return Ok(None),
+        Some(v) => try!(v.as_slice()
+            .ok_or(format!("The setting {} must be a list of strings, but is a {}", key, v.type_str()))),
+    };
+
+    values.iter()
+        .map(|v| v.as_str().map(|x| x.to_string())
+             .ok_or(format!("The setting {} must be a list of strings, but contains a {}", key, v.type_str())))
+        .collect::<Result<Vec<String>, String>>()
+        .map(Some)
+}
+
+/// Generated puzzles avoid the puzzles of at most this many days, ten years.
+const MAX_AVOID_RECENT_PUZZLES: i64 = 3650;
+
+/// A user may make at most this many guesses at once, more than anyone types by hand.
+const MAX_GUESS_BURST: i64 = 1000;
+
+const SETTINGS: &'static [&'static str] = &[
+    "token", "token_env", "channels", "dictionaries", "allow_file", "deny_file", "state_file", "language",
+    "channel_languages", "reconnect_delay", "puzzle_length", "daily_puzzle_time", "reminder_times", "publish_solutions_time",
+    "prefer_unique_puzzles", "avoid_recent_puzzles", "commands", "admins", "setters", "default_role", "report_spoilers",
+    "guess_stats", "guess_burst", "guess_interval",
+];
+
+impl PartialConfig {
+    pub fn from_toml(s: &str) -> Result<PartialConfig, String> {
+        let mut toml_parser = toml::Parser::new(s);
+        let table = match toml_parser.parse() {
+            Some(table) => table,
+            None => {
+                let errors: Vec<String> = toml_parser.errors.iter()
+                    .map(|e| {
+                        let (line, col) = toml_parser.to_linecol(e.lo);
+                        format!("line {}, column {}: {}", line + 1, col + 1, e.desc)
+                    })
+                    .collect();
+                return Err(format!("Invalid TOML: {}", errors.join("; ")));
+            }
+        };
+
+        // Misspelled settings would otherwise be silently ignored.
+        if let Some(key) = table.keys().find(|k| !SETTINGS.contains(&k.as_str())) {
+            return Err(format!("Unknown setting {}", key));
+        }
+
+        Ok(PartialConfig {
+            token: try!(string_setting(&table, "token")),
+            token_env: try!(string_setting(&table, "token_env")),
+            channels: try!(string_list_setting(&table, "channels")),
+            dictionaries: try!(string_list_setting(&table, "dictionaries")),
+            allow_file: try!(string_setting(&table, "allow_file")),
+            deny_file: try!(string_setting(&table, "deny_file")),
+            state_file: try!(string_setting(&table, "state_file")),
+            language: try!(string_setting(&table, "language")),
+            channel_languages: try!(string_list_setting(&table, "channel_languages")),
+            reconnect_delay: try!(integer_setting(&table, "reconnect_delay")),
+            puzzle_length: try!(integer_setting(&table, "puzzle_length")),
+            daily_puzzle_time: try!(string_setting(&table, "daily_puzzle_time")),
+            reminder_times: try!(string_list_setting(&table, "reminder_times")),
+            publish_solutions_time: try!(string_setting(&table, "publish_solutions_time")),
+            prefer_unique_puzzles: try!(bool_setting(&table, "prefer_unique_puzzles")),
+            avoid_recent_puzzles: try!(integer_setting(&table, "avoid_recent_puzzles")),
+            commands: try!(string_list_setting(&table, "commands")),
+            admins: try!(string_list_setting(&table, "admins")),
+            setters: try!(string_list_setting(&table, "setters")),
+            default_role: try!(string_setting(&table, "default_role")),
+            report_spoilers: try!(bool_setting(&table, "report_spoilers")),
+            guess_stats: try!(bool_setting(&table, "guess_stats")),
+            guess_burst: try!(integer_setting(&table, "guess_burst")),
+            guess_interval: try!(integer_setting(&table, "guess_interval")),
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path> + ?Sized>(path: &P) -> Result<PartialConfig, String> {
+        let mut contents = String::new();
+        let mut f = try!(fs::File::open(path)
+                         .map_err(|e| format!("Could not open {}: {}", path.as_ref().display(), e)));
+        try!(f.read_to_string(&mut contents)
+             .map_err(|e| format!("Could not read {}: {}", path.as_ref().display(), e)));
+
+        PartialConfig::from_toml(&contents).map_err(|e| format!("{}: {}", path.as_ref().display(), e))
+    }
+
+    /// Take all settings given in `overrides`, and the rest from `self`.
+    pub fn merge(self, overrides: PartialConfig) -> PartialConfig {
+        PartialConfig {
+            token: overrides.token.or(self.token),
+            token_env: overrides.token_env.or(self.token_env),
+            channels: overrides.channels.or(self.channels),
+            dictionaries: overrides.dictionaries.or(self.dictionaries),
+            allow_file: overrides.allow_file.or(self.allow_file),
+            deny_file: overrides.deny_file.or(self.deny_file),
+            state_file: overrides.state_file.or(self.state_file),
+            language: overrides.language.or(self.language),
+            channel_languages: overrides.channel_languages.or(self.channel_languages),
+            reconnect_delay: overrides.reconnect_delay.or(self.reconnect_delay),
+            puzzle_length: overrides.puzzle_length.or(self.puzzle_length),
+            daily_puzzle_time: overrides.daily_puzzle_time.or(self.daily_puzzle_time),
+            reminder_times: overrides.reminder_times.or(self.reminder_times),
+            publish_solutions_time: overrides.publish_solutions_time.or(self.publish_solutions_time),
+            prefer_unique_puzzles: overrides.prefer_unique_puzzles.or(self.prefer_unique_puzzles),
+            avoid_recent_puzzles: overrides.avoid_recent_puzzles.or(self.avoid_recent_puzzles),
+            commands: overrides.commands.or(self.commands),
+            admins: overrides.admins.or(self.admins),
+            setters: overrides.setters.or(self.setters),
+            default_role: overrides.default_role.or(self.default_role),
+            report_spoilers: overrides.report_spoilers.or(self.report_spoilers),
+            guess_stats: overrides.guess_stats.or(self.guess_stats),
+            guess_burst: overrides.guess_burst.or(self.guess_burst),
+            guess_interval: overrides.guess_interval.or(self.guess_interval),
+        }
+    }
+
+    /// Check that all settings are valid, and fill in defaults. Environment variables are looked up
+    /// with `env`.
+    pub fn validate<F>(self, env: F) -> Result<Config, String>
+        where F: Fn(&str) -> Option<String> {
+        let token = match (self.token, self.token_env) {
+            (Some(token), _) => token,
+            (None, Some(var)) => try!(env(&var).ok_or(format!("The environment variable {} is not set", var))),
+            (None, None) => return Err("No token given. Set token, or token_env to the name of an \
+                                        environment variable with the token".to_string()),
+        };
+        if token.is_empty() {
+            return Err("The token is empty".to_string());
+        }
+
+        let channels = self.channels.unwrap_or(vec![]);
+        if channels.is_empty() {
+            return Err("No channels given. Set channels to the channels to play in".to_string());
+        }
+
+        let dictionaries = self.dictionaries.unwrap_or(vec![]);
+        if dictionaries.is_empty() {
+            return Err("No dictionaries given. Set dictionaries to one or more word lists".to_string());
+        }
+        if let Some(missing) = dictionaries.iter().find(|d| !Path::new(d).exists()) {
+            return Err(format!("The dictionary {} does not exist", missing));
+        }
+
+        let language = try!(language_setting(&self.language.unwrap_or("sv".to_string())));
+        let mut channel_languages = vec![];
+        for setting in self.channel_languages.unwrap_or(vec![]) {
+            let (channel, code) = match setting.find('=') {
+                Some(i) => (&setting[..i], &setting[i + 1..]),
+                None => return Err(format!("A channel language must be on the form channel=language, but is {}",
+                                           setting)),
+            };
+            channel_languages.push((channel.to_string(), try!(language_setting(code))));
+        }
+
+        let reconnect_delay = self.reconnect_delay.unwrap_or(60);
+        if reconnect_delay < 0 {
+            return Err(format!("The reconnect delay must not be negative, but is {}", reconnect_delay));
+        }
+
+        let puzzle_length = self.puzzle_length.unwrap_or(DEFAULT_PUZZLE_LENGTH as i64);
+        if puzzle_length < 2 {
+            return Err(format!("The puzzle length must be at least 2, but is {}", puzzle_length));
+        }
+
+        let daily_puzzle_time = match self.daily_puzzle_time {
+            None => None,
+            Some(ref time) => Some(try!(time_of_day("daily puzzle time", time))),
+        };
+        let reminder_times = try!(self.reminder_times.unwrap_or(vec![]).iter()
+                                  .map(|time| time_of_day("reminder time", time))
+                                  .collect::<Result<Vec<i64>, String>>());
+        let publish_solutions_time = match self.publish_solutions_time {
+            None => None,
+            Some(ref time) => Some(try!(time_of_day("publish solutions time", time))),
+        };
+
+        let avoid_recent_puzzles = self.avoid_recent_puzzles.unwrap_or(0);
+        if avoid_recent_puzzles < 0 {
+            return Err(format!("The days to avoid recent puzzles must not be negative, but is {}",
+                               avoid_recent_puzzles));
+        }
+        if avoid_recent_puzzles > MAX_AVOID_RECENT_PUZZLES {
+            return Err(format!("The days to avoid recent puzzles must be at most {}, but is {}",
+                               MAX_AVOID_RECENT_PUZZLES, avoid_recent_puzzles));
+        }
+
+        if let Some(ref commands) = self.commands {
+            let known = parser::command_names();
+            if let Some(unknown) = commands.iter().find(|c| !known.contains(&c.as_str())) {
+                return Err(format!("Unknown command {}. Known commands: {}", unknown, known.join(", ")));
+            }
+        }
+
+        if let Some(guess_burst) = self.guess_burst {
+            if guess_burst < 1 {
+                return Err(format!("The guess burst must be at least 1, but is {}", guess_burst));
+            }
+            if guess_burst > MAX_GUESS_BURST {
+                return Err(format!("The guess burst must be at most {}, but is {}", MAX_GUESS_BURST, guess_burst));
+            }
+        }
+        let guess_interval = self.guess_interval.unwrap_or(10);
+        if guess_interval < 1 {
+            return Err(format!("The guess interval must be at least 1 second, but is {}", guess_interval));
+        }
+
+        // Anyone may set puzzles by default, as before there were roles.
+        let default_role = match self.default_role {
+            None => Role::Setter,
+            Some(ref role) => try!(Role::from_name(role).ok_or(
+                format!("Unknown default role {}. Roles: {}", role, ROLE_NAMES.join(", ")))),
+        };
+
+        Ok(Config {
+            token: token,
+            channels: channels,
+            dictionaries: dictionaries,
+            allow_file: self.allow_file,
+            deny_file: self.deny_file,
+            state_file: self.state_file.unwrap_or("niancat_state.json".to_string()),
+            language: language,
+            channel_languages: channel_languages,
+            reconnect_delay: reconnect_delay as u64,
+            puzzle_length: puzzle_length as usize,
+            daily_puzzle_time: daily_puzzle_time,
+            reminder_times: reminder_times,
+            publish_solutions_time: publish_solutions_time,
+            prefer_unique_puzzles: self.prefer_unique_puzzles.unwrap_or(false),
+            avoid_recent_puzzles: avoid_recent_puzzles as u32,
+            commands: self.commands,
+            admins: self.admins.unwrap_or(vec![]),
+            setters: self.setters.unwrap_or(vec![]),
+            default_role: default_role,
+            report_spoilers: self.report_spoilers.unwrap_or(false),
+            guess_stats: self.guess_stats.unwrap_or(false),
+            guess_burst: self.guess_burst.map(|burst| burst as u32),
+            guess_interval: guess_interval,
+        })
+    }
+}
+
+impl Config {
+    pub fn dictionary_files(&self) -> DictionaryFiles {
+        DictionaryFiles {
+            base: self.dictionaries.clone(),
+            allow: self.allow_file.clone(),
+            deny: self.deny_file.clone(),
+        }
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        Schedule {
+            daily_puzzle: self.daily_puzzle_time,
+            reminders: self.reminder_times.clone(),
+            publish_solutions: self.publish_solutions_time,
+        }
+    }
+
+    /// The configured roles. Users that are both admins and setters are admins.
+    pub fn roles(&self) -> Roles {
+        let mut roles = Roles::new(self.default_role);
+        for name in &self.setters {
+            roles.set(Name(name.clone()), Role::Setter);
+        }
+        for name in &self.admins {
+            roles.set(Name(name.clone()), Role::Admin);
+        }
+        roles
+    }
+
+    /// The language of the game in a channel.
+    pub fn language_for(&self, channel: &str) -> Language {
+        self.channel_languages.iter()
+            .find(|&&(ref c, _)| c == channel)
+            .map(|&(_, language)| language)
+            .unwrap_or(self.language)
+    }
+
+    pub fn generator_options(&self) -> GeneratorOptions {
+        GeneratorOptions {
+            prefer_unique: self.prefer_unique_puzzles,
+            avoid_recent_days: self.avoid_recent_puzzles,
+        }
+    }
+
+    /// How often each user may guess, or `None` if there is no limit.
+    pub fn guess_limit(&self) -> Option<GuessLimit> {
+        self.guess_burst.map(|burst| GuessLimit { burst: burst, interval: self.guess_interval })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &'static str = r#"
+        token_env = "NIANCAT_TOKEN"
+        channels = ["konsulatet", "general"]
+        dictionaries = ["tests/test_dictionary.txt"]
+        deny_file = "/var/lib/niancat/denied.txt"
+        state_file = "/var/lib/niancat/state.json"
+        reconnect_delay = 10
+        daily_puzzle_time = "05:30"
+        reminder_times = ["12:00", "18:00"]
+        prefer_unique_puzzles = true
+        commands = ["!nian", "!setnian"]
+        admins = ["erike"]
+        setters = ["johaper", "erike"]
+        default_role = "player"
+        channel_languages = ["general=en"]
+        report_spoilers = true
+        guess_burst = 5
+    "#;
+
+    fn env(var: &str) -> Option<String> {
+        if var == "NIANCAT_TOKEN" { Some("xoxb-17".to_string()) } else { None }
+    }
+
+    fn example() -> PartialConfig {
+        PartialConfig::from_toml(EXAMPLE).unwrap()
+    }
+
+    #[test]
+    fn validate_test() {
+        assert_eq!(example().validate(env), Ok(Config {
+            token: "xoxb-17".into(),
+            channels: vec!["konsulatet".into(), "general".into()],
+            dictionaries: vec!["tests/test_dictionary.txt".into()],
+            allow_file: None,
+            deny_file: Some("/var/lib/niancat/denied.txt".into()),
+            state_file: "/var/lib/niancat/state.json".into(),
+            language: Language::Swedish,
+            channel_languages: vec![("general".into(), Language::English)],
+            reconnect_delay: 10,
+            puzzle_length: 9,
+            daily_puzzle_time: Some(5 * 60 * 60 + 30 * 60),
+            reminder_times: vec![12 * 60 * 60, 18 * 60 * 60],
+            publish_solutions_time: None,
+            prefer_unique_puzzles: true,
+            avoid_recent_puzzles: 0,
+            commands: Some(vec!["!nian".into(), "!setnian".into()]),
+            admins: vec!["erike".into()],
+            setters: vec!["johaper".into(), "erike".into()],
+            default_role: Role::Player,
+            report_spoilers: true,
+            guess_stats: false,
+            guess_burst: Some(5),
+            guess_interval: 10,
+        }));
+    }
+
+    #[test]
+    fn roles_test() {
+        let roles = example().validate(env).unwrap().roles();
+        assert_eq!(roles.role(&Name("erike".into())), Role::Admin);
+        assert_eq!(roles.role(&Name("johaper".into())), Role::Setter);
+        assert_eq!(roles.role(&Name("cadaker".into())), Role::Player);
+
+        let config = PartialConfig { default_role: None, ..example() }.validate(env).unwrap();
+        assert_eq!(config.default_role, Role::Setter);
+    }
+
+    #[test]
+    fn language_test() {
+        let config = example().validate(env).unwrap();
+        assert_eq!(config.language_for("general"), Language::English);
+        assert_eq!(config.language_for("konsulatet"), Language::Swedish);
+    }
+
+    #[test]
+    fn merge_test() {
+        let flags = PartialConfig {
+            token: Some("xoxb-42".into()),
+            channels: Some(vec!["tian".into()]),
+            puzzle_length: Some(10),
+            ..PartialConfig::default()
+        };
+
+        let config = example().merge(flags).validate(env).unwrap();
+        assert_eq!(config.token, "xoxb-42");
+        assert_eq!(config.channels, vec!["tian".to_string()]);
+        assert_eq!(config.puzzle_length, 10);
+        assert_eq!(config.reconnect_delay, 10);
+    }
+
+    #[test]
+    fn guess_limit_test() {
+        let config = example().validate(env).unwrap();
+        assert_eq!(config.guess_limit(), Some(GuessLimit { burst: 5, interval: 10 }));
+
+        let config = PartialConfig { guess_burst: None, ..example() }.validate(env).unwrap();
+        assert_eq!(config.guess_limit(), None);
+    }
+
+    const INVALID_TESTS: &'static [(&'static str, &'static str)] = &[
+        ("channels = [", "Invalid TOML"),
+        ("chanels = [\"general\"]", "Unknown setting chanels"),
+        ("channels = \"general\"", "channels must be a list of strings"),
+        ("channels = [17]", "channels must be a list of strings"),
+        ("reconnect_delay = \"soon\"", "reconnect_delay must be an integer"),
+        ("allow_file = [\"allowed.txt\"]", "allow_file must be a string"),
+        ("channels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]",
+         "No token given"),
+        ("token_env = \"NO_SUCH_VARIABLE\"", "NO_SUCH_VARIABLE is not set"),
+        ("token = \"t\"\ndictionaries = [\"tests/test_dictionary.txt\"]", "No channels given"),
+        ("token = \"t\"\nchannels = [\"general\"]", "No dictionaries given"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"no/such/file.txt\"]",
+         "no/such/file.txt does not exist"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nlanguage = \"fi\"",
+         "Unsupported language fi"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nchannel_languages = [\"general\"]",
+         "must be on the form channel=language"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nchannel_languages = [\"general=fi\"]",
+         "Unsupported language fi"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nreconnect_delay = -1",
+         "must not be negative"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\npuzzle_length = 1",
+         "at least 2"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\ncommands = [\"!nosuchcommand\"]",
+         "Unknown command !nosuchcommand"),
+        ("prefer_unique_puzzles = \"yes\"", "must be true or false"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\ndaily_puzzle_time = \"6:00\"",
+         "must be on the form HH:MM"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\ndaily_puzzle_time = \"24:00\"",
+         "must be on the form HH:MM"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nreminder_times = [\"12:00\", \"noon\"]",
+         "reminder time must be on the form HH:MM"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\navoid_recent_puzzles = -7",
+         "must not be negative"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\navoid_recent_puzzles = 4294967296",
+         "must be at most 3650"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\ndefault_role = \"king\"",
+         "Unknown default role king"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nguess_burst = 0",
+         "guess burst must be at least 1"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nguess_burst = 4294967296",
+         "guess burst must be at most 1000"),
+        ("token = \"t\"\nchannels = [\"general\"]\ndictionaries = [\"tests/test_dictionary.txt\"]\nguess_interval = 0",
+         "guess interval must be at least 1 second"),
+    ];
+
+    #[test]
+    fn invalid_config_test() {
+        for &(toml, expected) in INVALID_TESTS {
+            let result = PartialConfig::from_toml(toml).and_then(|c| c.validate(env));
+            match result {
+                Ok(config) => assert!(false, "{:?} should be invalid, but was {:?}", toml, config),
+                Err(e) => assert!(e.contains(expected), "{:?}: expected {:?} in {:?}", toml, expected, e),
+            }
+        }
+    }
+}
@@ -1,25 +1,46 @@
 use std::collections::HashSet;
-use std::iter::FromIterator;
-use multimap::MultiMap;
+use multimap::{Entry, MultiMap};
 use std::path::Path;
-use std::io::{BufReader, BufRead, Result};
+use std::io::{self, Read, Result, Write};
+use std::str;
 use std::fs;
+use std::sync::{Arc, RwLock};
 
 use super::types::*;
+use index;
 
 pub trait CheckWord {
     fn is_solution(&self, w: &Word) -> bool;
     fn no_of_solutions(&self, p: &Puzzle) -> usize;
     fn find_solutions(&self, p: &Puzzle) -> Option<Vec<Word>>;
     fn has_solution(&self, p: &Puzzle) -> bool;
+    fn near_misses(&self, w: &Word) -> Vec<Word>;
+    /// All puzzles of the given length that have solutions, with their letters sorted.
+    fn puzzles(&self, length: usize) -> Vec<Puzzle>;
 }
 
+/// Words at most this many edits away from a guess are suggested as near misses.
+const MAX_EDIT_DISTANCE: usize = 2;
+
+/// The words from the base word lists, with the words in the allow overlay added and the words in
+/// the deny overlay removed. The words are only kept grouped by their sorted letters.
 pub struct Dictionary {
-    words:  HashSet<Word>,
     solutions: MultiMap<Puzzle, Word>,
+    allowed: HashSet<Word>,
+    denied: HashSet<Word>,
+}
+
+/// The files a dictionary is read from. The overlays are edited with `!tillåt` and `!förbjud`, and
+/// are optional. An overlay file that doesn't exist yet is empty.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct DictionaryFiles {
+    pub base: Vec<String>,
+    pub allow: Option<String>,
+    pub deny: Option<String>,
 }
 
-fn sort_word(x: &String) -> String {
+/// The letters of a word, sorted. All anagrams of a word have the same sorted letters.
+pub fn sort_word(x: &String) -> String {
     let mut cs: Vec<char> = x.chars().collect();
     cs.sort();
     cs.into_iter().collect()
@@ -29,34 +50,261 @@ fn sort_puzzle(&Puzzle(ref p): &Puzzle) -> Puzzle {
     Puzzle(sort_word(&p))
 }
 
+/// The Levenshtein distance between two words, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(substitution.min(insertion).min(deletion));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn sorted(words: &HashSet<Word>) -> Vec<Word> {
+    let mut words: Vec<Word> = words.iter().cloned().collect();
+    words.sort_by(|a, b| a.0.cmp(&b.0));
+    words
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Split a word list into lines. Lines that are not valid UTF-8 are left out, and their line
+/// numbers, counted from 1, are returned instead.
+pub fn split_lines(contents: &[u8]) -> (Vec<String>, Vec<usize>) {
+    let mut lines = vec![];
+    let mut bad_lines = vec![];
+    for (i, line) in contents.split(|&b| b == b'\n').enumerate() {
+        let line = if line.ends_with(b"\r") { &line[..line.len() - 1] } else { line };
+        match str::from_utf8(line) {
+            Ok(line) => lines.push(line.to_string()),
+            Err(_) => bad_lines.push(i + 1),
+        }
+    }
+    (lines, bad_lines)
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let mut contents = vec![];
+    try!(try!(fs::File::open(path)).read_to_end(&mut contents));
+    Ok(contents)
+}
+
+fn read_words(path: &Path) -> Result<Vec<String>> {
+    words_from_bytes(path, &try!(read_file(path)))
+}
+
+fn words_from_bytes(path: &Path, contents: &[u8]) -> Result<Vec<String>> {
+    let (lines, bad_lines) = split_lines(contents);
+    match bad_lines.first() {
+        Some(line) => Err(invalid_data(format!("{}: line {} is not valid UTF-8", path.display(), line))),
+        None => Ok(lines),
+    }
+}
+
+fn read_overlay(path: &Option<String>) -> Result<Vec<String>> {
+    match *path {
+        Some(ref path) if Path::new(path).exists() => read_words(Path::new(path)),
+        _ => Ok(vec![]),
+    }
+}
+
+fn write_words(path: &str, words: &[Word]) -> Result<()> {
+    // Write to a temporary file first, like the state files, so the overlay is never half written.
+    let tmp_path = format!("{}.tmp", path);
+    {
+        let mut words: Vec<&Word> = words.iter().collect();
+        words.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut f = try!(fs::File::create(&tmp_path));
+        for &&Word(ref w) in &words {
+            try!(writeln!(f, "{}", w));
+        }
+    }
+    fs::rename(&tmp_path, path)
+}
+
 impl Dictionary {
     pub fn new<I>(it: I) -> Dictionary
         where I: Iterator<Item=String> {
+        Dictionary::with_overlays(it, vec![].into_iter(), vec![].into_iter())
+    }
 
-        let word_it = it.map(|x| Word(x).normalize()).filter(|&Word(ref x)| x.chars().count() == 9);
-        let words = HashSet::from_iter(word_it);
+    /// Create a dictionary from base words, and overlays of words to add and to remove. A word in
+    /// both overlays is removed.
+    pub fn with_overlays<I, A, D>(base: I, allowed: A, denied: D) -> Dictionary
+        where I: Iterator<Item=String>, A: Iterator<Item=String>, D: Iterator<Item=String> {
 
-        let mut solutions = MultiMap::new();
-        for &Word(ref w) in &words {
-            let p = sort_word(w);
-            solutions.insert(Puzzle(p), Word(w.clone()));
-        }
+        let mut dictionary = Dictionary::empty();
+        dictionary.add_base_words(base);
+        dictionary.add_overlays(allowed, denied);
+        dictionary
+    }
 
-        Dictionary { words: words, solutions: solutions }
+    fn empty() -> Dictionary {
+        Dictionary {
+            solutions: MultiMap::new(),
+            allowed: HashSet::new(),
+            denied: HashSet::new(),
+        }
     }
 
+    /// Read a dictionary from a word list, or from an index written by `niancat-index`.
     pub fn from_file<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Dictionary> {
-        let f = try!(fs::File::open(path));
-        let f = BufReader::new(f);
+        let mut dictionary = Dictionary::empty();
+        try!(dictionary.add_base_file(path.as_ref()));
+        Ok(dictionary)
+    }
 
-        Ok(Dictionary::new(f.lines().map(|x| x.unwrap())))
+    /// Read a dictionary with the words from all files.
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Dictionary> {
+        let mut dictionary = Dictionary::empty();
+        for path in paths {
+            try!(dictionary.add_base_file(path.as_ref()));
+        }
+        Ok(dictionary)
+    }
+
+    fn add_base_words<I>(&mut self, it: I)
+        where I: Iterator<Item=String> {
+        for w in it.map(|x| Word(x).normalize()).filter(|&Word(ref x)| !x.is_empty()) {
+            self.insert(w);
+        }
+    }
+
+    /// Add the words in a word list or an index. The words in an index are already normalized, and
+    /// grouped by their sorted letters, so each group is added as it is.
+    fn add_base_file(&mut self, path: &Path) -> Result<()> {
+        let contents = try!(read_file(path));
+        if !index::is_index(&contents) {
+            let words = try!(words_from_bytes(path, &contents));
+            self.add_base_words(words.into_iter());
+            return Ok(());
+        }
+
+        let entries = try!(index::decode(&contents)
+                           .map_err(|e| invalid_data(format!("{}: {}", path.display(), e))));
+        for (puzzle, words) in entries {
+            match self.solutions.entry(puzzle) {
+                Entry::Vacant(entry) => {
+                    entry.insert_vec(words);
+                },
+                // Another file already had words with these letters.
+                Entry::Occupied(mut entry) => {
+                    let solutions = entry.get_vec_mut();
+                    for w in words {
+                        if !solutions.contains(&w) {
+                            solutions.push(w);
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn add_overlays<A, D>(&mut self, allowed: A, denied: D)
+        where A: Iterator<Item=String>, D: Iterator<Item=String> {
+        for w in allowed.map(Word).filter(|w| !w.normalize().0.is_empty()) {
+            self.allow(&w);
+        }
+        for w in denied.map(Word).filter(|w| !w.normalize().0.is_empty()) {
+            self.deny(&w);
+        }
+    }
+
+    fn insert(&mut self, w: Word) {
+        let solutions = self.solutions.entry(Puzzle(sort_word(&w.0))).or_insert_vec(vec![]);
+        if !solutions.contains(&w) {
+            solutions.push(w);
+        }
+    }
+
+    fn remove(&mut self, w: &Word) {
+        let puzzle = Puzzle(sort_word(&w.0));
+        let now_empty = match self.solutions.get_vec_mut(&puzzle) {
+            Some(solutions) => {
+                solutions.retain(|x| x != w);
+                solutions.is_empty()
+            },
+            None => false,
+        };
+        if now_empty {
+            self.solutions.remove(&puzzle);
+        }
+    }
+
+    /// Add a word to the allow overlay, and remove it from the deny overlay.
+    pub fn allow(&mut self, w: &Word) {
+        let w = w.normalize();
+        self.denied.remove(&w);
+        self.allowed.insert(w.clone());
+        self.insert(w);
+    }
+
+    /// Add a word to the deny overlay, and remove it from the allow overlay.
+    pub fn deny(&mut self, w: &Word) {
+        let w = w.normalize();
+        self.allowed.remove(&w);
+        self.denied.insert(w.clone());
+        self.remove(&w);
+    }
+
+    /// The words in the allow overlay, sorted.
+    pub fn allowed_words(&self) -> Vec<Word> {
+        sorted(&self.allowed)
+    }
+
+    /// The words in the deny overlay, sorted.
+    pub fn denied_words(&self) -> Vec<Word> {
+        sorted(&self.denied)
+    }
+}
+
+impl DictionaryFiles {
+    pub fn new(base: Vec<String>) -> DictionaryFiles {
+        DictionaryFiles { base: base, allow: None, deny: None }
+    }
+
+    /// Read the base word lists and the overlays.
+    pub fn load(&self) -> Result<Dictionary> {
+        let mut dictionary = try!(Dictionary::from_files(&self.base));
+        let allowed = try!(read_overlay(&self.allow));
+        let denied = try!(read_overlay(&self.deny));
+        dictionary.add_overlays(allowed.into_iter(), denied.into_iter());
+        Ok(dictionary)
+    }
+
+    /// Write the overlays to their files. An overlay without a file must be empty.
+    pub fn save_overlays(&self, allowed: &[Word], denied: &[Word]) -> Result<()> {
+        for &(path, words) in &[(&self.allow, allowed), (&self.deny, denied)] {
+            match *path {
+                Some(ref path) => try!(write_words(path, words)),
+                None if words.is_empty() => {},
+                None => return Err(io::Error::new(io::ErrorKind::NotFound, "No file for the overlay")),
+            }
+        }
+        Ok(())
     }
 }
 
 impl CheckWord for Dictionary {
     /// Check if a word is in the dictionary.
     fn is_solution(&self, w: &Word) -> bool {
-        self.words.contains(&w.normalize())
+        let w = w.normalize();
+        match self.solutions.get_vec(&Puzzle(sort_word(&w.0))) {
+            Some(solutions) => solutions.contains(&w),
+            None => false,
+        }
     }
 
     /// Check how many solutions a given puzzle has in the dictionary.
@@ -77,6 +325,90 @@ impl CheckWord for Dictionary {
     fn has_solution(&self, p: &Puzzle) -> bool {
         self.solutions.contains_key(&sort_puzzle(p))
     }
+
+    /// Find all words in the dictionary that are close to, but not the same as, the given word.
+    /// The words are sorted by distance, and then alphabetically.
+    fn near_misses(&self, w: &Word) -> Vec<Word> {
+        let Word(ref w) = w.normalize();
+        let length = w.chars().count();
+        // The distance is at least the difference in length, which is much cheaper to find. All
+        // words with the same sorted letters have the same length.
+        let mut misses: Vec<(usize, &Word)> = self.solutions.iter_all()
+            .filter(|&(p, _)| {
+                let other = p.length();
+                other + MAX_EDIT_DISTANCE >= length && other <= length + MAX_EDIT_DISTANCE
+            })
+            .flat_map(|(_, words)| words.iter())
+            .map(|x| (edit_distance(w, &x.0), x))
+            .filter(|&(d, _)| d > 0 && d <= MAX_EDIT_DISTANCE)
+            .collect();
+        misses.sort_by(|a, b| a.0.cmp(&b.0).then(a.1 .0.cmp(&b.1 .0)));
+        misses.into_iter().map(|(_, x)| x.clone()).collect()
+    }
+
+    fn puzzles(&self, length: usize) -> Vec<Puzzle> {
+        self.solutions.keys().filter(|p| p.length() == length).cloned().collect()
+    }
+}
+
+/// A dictionary that can be replaced while games are using it, like when the word lists are
+/// reloaded. Clones share the same dictionary.
+#[derive(Clone)]
+pub struct SharedDictionary {
+    dictionary: Arc<RwLock<Dictionary>>,
+}
+
+impl SharedDictionary {
+    pub fn new(dictionary: Dictionary) -> SharedDictionary {
+        SharedDictionary { dictionary: Arc::new(RwLock::new(dictionary)) }
+    }
+
+    /// Replace the dictionary for all clones at once.
+    pub fn replace(&self, dictionary: Dictionary) {
+        *self.dictionary.write().unwrap() = dictionary;
+    }
+
+    pub fn allow(&self, w: &Word) {
+        self.dictionary.write().unwrap().allow(w);
+    }
+
+    pub fn deny(&self, w: &Word) {
+        self.dictionary.write().unwrap().deny(w);
+    }
+
+    pub fn allowed_words(&self) -> Vec<Word> {
+        self.dictionary.read().unwrap().allowed_words()
+    }
+
+    pub fn denied_words(&self) -> Vec<Word> {
+        self.dictionary.read().unwrap().denied_words()
+    }
+}
+
+impl CheckWord for SharedDictionary {
+    fn is_solution(&self, w: &Word) -> bool {
+        self.dictionary.read().unwrap().is_solution(w)
+    }
+
+    fn no_of_solutions(&self, p: &Puzzle) -> usize {
+        self.dictionary.read().unwrap().no_of_solutions(p)
+    }
+
+    fn find_solutions(&self, p: &Puzzle) -> Option<Vec<Word>> {
+        self.dictionary.read().unwrap().find_solutions(p)
+    }
+
+    fn has_solution(&self, p: &Puzzle) -> bool {
+        self.dictionary.read().unwrap().has_solution(p)
+    }
+
+    fn near_misses(&self, w: &Word) -> Vec<Word> {
+        self.dictionary.read().unwrap().near_misses(w)
+    }
+
+    fn puzzles(&self, length: usize) -> Vec<Puzzle> {
+        self.dictionary.read().unwrap().puzzles(length)
+    }
 }
 
 #[cfg(test)]
@@ -101,12 +433,12 @@ mod tests {
     const SOLUTION_TESTS: &'static [&'static str] = &[
         "GALLTJUTA", "DATORSPEL", "SPELDATOR", "ABCDEFGHI", "ABCDEFÅÄÖ",
         "galltjuta", "datorspel", "speldator", "abcdefghi", "abcdefåäö",
-        "gall tjuta", "  galltjuta  ", "gall-tjuta", "-galltjuta -----     "
+        "gall tjuta", "  galltjuta  ", "gall-tjuta", "-galltjuta -----     ",
+        "abc", "abcdefghijkl", "ÅÄÖABC", "abcåäö",
     ];
 
     const NON_SOLUTION_TESTS: &'static [&'static str] = &[
-        "GALLTJUT", "GALLTJUTAA", "åäöabcdef",
-        "abc", "abcdefghijkl", "ÅÄÖABC", "abcåäö",
+        "GALLTJUT", "GALLTJUTAA", "åäöabcdef", "", "---",
     ];
 
     const NO_OF_SOLUTIONS_TESTS: &'static [(&'static str, usize)] = &[
@@ -116,7 +448,10 @@ mod tests {
         ("SPELDATOR", 2),
         ("SPDATOREL", 2),
         ("ÅÄÖABCDEF", 1),
-        ("AAAAAAAAA", 0)
+        ("AAAAAAAAA", 0),
+        ("CBA", 1),
+        ("LKJIHGFEDCBA", 1),
+        ("ÅÄÖCBA", 2),
     ];
 
     #[test]
@@ -159,6 +494,47 @@ mod tests {
         assert!(expected == actual, "Expected {:?}, Actual {:?}", expected, actual);
     }
 
+    const EDIT_DISTANCE_TESTS: &'static [(&'static str, &'static str, usize)] = &[
+        ("DATORSPEL", "DATORSPEL", 0),
+        ("DATORSPEL", "DATORSPEX", 1),
+        ("DATORSPEL", "DATORSPE", 1),
+        ("DATORSPEL", "ADTORSPEL", 2),
+        ("DATORSPEL", "SPELDATOR", 8),
+        ("ÅÄÖABCDEF", "ÅÄOABCDEF", 1),
+        ("", "ABC", 3),
+    ];
+
+    #[test]
+    fn edit_distance_test() {
+        for &(a, b, expected) in EDIT_DISTANCE_TESTS {
+            assert_eq!(edit_distance(a, b), expected, "{} -> {}", a, b);
+            assert_eq!(edit_distance(b, a), expected, "{} -> {}", b, a);
+        }
+    }
+
+    #[test]
+    fn near_misses_test() {
+        let d = Dictionary::new(WORDS.iter().map(|x| x.to_string()));
+
+        assert_eq!(d.near_misses(&Word("DATORSPEX".into())), vec![Word("DATORSPEL".into())]);
+        assert_eq!(d.near_misses(&Word("abcdefgåö".into())),
+                   vec![Word("ABCDEFGHI".into()), Word("ABCDEFÅÄÖ".into())]);
+        // The word itself is not a near miss.
+        assert_eq!(d.near_misses(&Word("DATORSPEL".into())), vec![]);
+        assert_eq!(d.near_misses(&Word("XXXXXXXXX".into())), vec![]);
+    }
+
+    #[test]
+    fn shared_dictionary_test() {
+        let shared = SharedDictionary::new(Dictionary::new(WORDS.iter().map(|x| x.to_string())));
+        let other = shared.clone();
+        assert!(shared.is_solution(&Word("GALLTJUTA".into())));
+
+        other.replace(Dictionary::new(vec!["DATORSPEL".to_string()].into_iter()));
+        assert!(!shared.is_solution(&Word("GALLTJUTA".into())));
+        assert_eq!(shared.no_of_solutions(&Puzzle("SPELDATOR".into())), 1);
+    }
+
     #[test]
     fn has_solution_test() {
         let d = Dictionary::new(WORDS.iter().map(|x| x.to_string()));
@@ -166,4 +542,60 @@ mod tests {
         assert!(d.has_solution(&Puzzle("SPELDATOR".to_string())));
         assert!(!d.has_solution(&Puzzle("NOTAWORDX".to_string())));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn overlays_test() {
+        let base = vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string(), "SPELDATOR".to_string()];
+        let allowed = vec!["tagalltju".to_string(), "SPELDATOR".to_string()];
+        let denied = vec!["speldator".to_string()];
+        let mut d = Dictionary::with_overlays(base.into_iter(), allowed.into_iter(), denied.into_iter());
+
+        assert!(d.is_solution(&Word("TAGALLTJU".into())));
+        assert!(!d.is_solution(&Word("SPELDATOR".into())));
+        assert_eq!(d.no_of_solutions(&Puzzle("GALLTJUTA".into())), 2);
+        assert_eq!(d.no_of_solutions(&Puzzle("DATORSPEL".into())), 1);
+
+        d.deny(&Word("DATORSPEL".into()));
+        assert!(!d.is_solution(&Word("DATORSPEL".into())));
+        assert!(!d.has_solution(&Puzzle("DATORSPEL".into())));
+        assert_eq!(d.find_solutions(&Puzzle("DATORSPEL".into())), None);
+
+        d.allow(&Word("speldator".into()));
+        assert_eq!(d.find_solutions(&Puzzle("DATORSPEL".into())), Some(vec![Word("SPELDATOR".into())]));
+        assert_eq!(d.allowed_words(), vec![Word("SPELDATOR".into()), Word("TAGALLTJU".into())]);
+        assert_eq!(d.denied_words(), vec![Word("DATORSPEL".into())]);
+    }
+
+    #[test]
+    fn split_lines_test() {
+        let (lines, bad_lines) = split_lines(b"GALLTJUTA\r\nDATOR\xffSPEL\n\nSPELDATOR");
+        assert_eq!(lines, vec!["GALLTJUTA".to_string(), "".to_string(), "SPELDATOR".to_string()]);
+        assert_eq!(bad_lines, vec![2]);
+    }
+
+    #[test]
+    fn from_index_file_test() {
+        use index;
+        use std::env;
+
+        let path = env::temp_dir().join("niancat_index_test.idx");
+        let words: Vec<Word> = WORDS.iter().map(|x| Word(x.to_string()).normalize()).collect();
+        fs::File::create(&path).unwrap().write_all(&index::encode(&words)).unwrap();
+
+        let d = Dictionary::from_file(&path).unwrap();
+        for word in SOLUTION_TESTS.iter().map(|x| Word(x.to_string())) {
+            assert!(d.is_solution(&word), "Word should be a solution: {:?}", word);
+        }
+        assert_eq!(d.no_of_solutions(&Puzzle("SPDATOREL".into())), 2);
+
+        // Words in more than one file are only added once.
+        let d = Dictionary::from_files(&[&path, &path]).unwrap();
+        assert_eq!(d.no_of_solutions(&Puzzle("SPDATOREL".into())), 2);
+
+        fs::File::create(&path).unwrap().write_all(b"GALLTJUTA\nDATOR\xffSPEL\n").unwrap();
+        let error = format!("{}", Dictionary::from_file(&path).err().unwrap());
+        assert!(error.contains("line 2"), "{}", error);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use dictionary::CheckWord;
+use logic::{self, Command, Niancat};
+use messages::Language;
+use response::{Respond, Response, InvalidCommand, SlackResponse, new_responder};
+use scheduler::Job;
+use store::{State, StateStore};
+use generator::GeneratorOptions;
+use roles::{Role, Roles};
+use types::{Channel, Name, Timestamp, Word};
+
+/// A game has its own puzzle, solutions and notification channel, and saves its state to its own
+/// store. All games share one dictionary.
+pub struct Game<'a> {
+    pub name: String,
+    pub channel: Channel,
+    state: Niancat<'a>,
+    store: Box<StateStore>,
+    language: Language,
+    responder: Box<Respond>,
+}
+
+impl<'a> Game<'a> {
+    pub fn new(name: &str,
+               dict: &'a CheckWord,
+               channel: Channel,
+               store: Box<StateStore>,
+               initial_state: &State) -> Game<'a> {
+        let mut state = Niancat::new(dict);
+        state.restore(initial_state);
+
+        Game {
+            name: name.to_string(),
+            language: Language::default(),
+            responder: new_responder(&channel, Language::default(), state.puzzle_length()),
+            channel: channel,
+            state: state,
+            store: store,
+        }
+    }
+
+    /// Set the length of new puzzles, which the messages of the game name.
+    pub fn set_puzzle_length(&mut self, length: usize) {
+        self.state.set_puzzle_length(length);
+        self.responder = new_responder(&self.channel, self.language, length);
+    }
+
+    pub fn set_generator_options(&mut self, options: GeneratorOptions) {
+        self.state.set_generator_options(options);
+    }
+
+    pub fn set_show_guess_stats(&mut self, show: bool) {
+        self.state.set_show_guess_stats(show);
+    }
+
+    pub fn set_roles(&mut self, roles: Roles) {
+        self.state.set_roles(roles);
+    }
+
+    pub fn role(&self, name: &Name) -> Role {
+        self.state.role(name)
+    }
+
+    pub fn admins(&self) -> Vec<Name> {
+        self.state.admins()
+    }
+
+    /// Set the language of the game channel, and of private messages to users who have not chosen
+    /// another language.
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+        self.responder = new_responder(&self.channel, language, self.state.puzzle_length());
+    }
+
+    /// Apply a command from a user to this game, and save the state if the command changed it. The
+    /// response is in the language the user has chosen, if any.
+    pub fn apply(&mut self, command: &Command, language: Option<Language>, now: Timestamp) -> Vec<SlackResponse> {
+        let response = logic::apply(command, &mut self.state, now);
+        self.save_state_if_changed();
+        self.serialize(&response, language)
+    }
+
+    /// Check a message in a public channel for a word that gives away the puzzle, and log it.
+    pub fn check_spoiler(&mut self, channel: &Channel, name: &Name, text: &str, now: Timestamp) -> Option<Word> {
+        let spoiler = logic::check_spoiler(&mut self.state, channel, name, text, now);
+        self.save_state_if_changed();
+        spoiler
+    }
+
+    /// Update the solutions of the current puzzle, after the dictionary has been reloaded.
+    pub fn revalidate(&mut self) -> Vec<SlackResponse> {
+        let response = logic::revalidate(&mut self.state);
+        self.save_state_if_changed();
+        response.map(|r| self.serialize(&r, None)).unwrap_or(vec![])
+    }
+
+    /// Run a scheduled job in this game.
+    pub fn run_job(&mut self, job: &Job, now: Timestamp) -> Vec<SlackResponse> {
+        let response = match *job {
+            Job::DailyPuzzle(due) => logic::daily_puzzle(&mut self.state, &self.channel, due, now),
+            Job::Reminder(_) => logic::remind(&self.state),
+            Job::PublishSolutions(due) => logic::publish_solutions(&mut self.state, due),
+        };
+        self.save_state_if_changed();
+        response.map(|r| self.serialize(&r, None)).unwrap_or(vec![])
+    }
+
+    /// Post the solutions of a replaced puzzle, once it can no longer be restored, and the summary
+    /// of the previous week, once it has ended. Wrong guesses are saved here, a while after they
+    /// were made.
+    pub fn poll(&mut self, now: Timestamp) -> Vec<SlackResponse> {
+        logic::save_guesses(&mut self.state, now);
+        let replaced = logic::publish_replaced(&mut self.state, now);
+        let summary = logic::weekly_summary(&mut self.state, now);
+        self.save_state_if_changed();
+        replaced.into_iter().chain(summary).flat_map(|r| self.serialize(&r, None)).collect()
+    }
+
+    /// Serialize a response, with private messages in the language a user has chosen, if any.
+    pub fn serialize(&self, response: &Response, language: Option<Language>) -> Vec<SlackResponse> {
+        self.responder.serialize(response, language)
+    }
+
+    pub fn serialize_invalid_command(&self, invalid_command: &InvalidCommand, language: Option<Language>)
+                                     -> Vec<SlackResponse> {
+        self.responder.serialize_invalid_command(invalid_command, language)
+    }
+
+    fn save_state_if_changed(&mut self) {
+        if self.state.take_changed() {
+            if let Err(e) = self.store.save(&self.state.to_state()) {
+                println!("State for game {} was not saved! Reason: {}", self.name, e);
+            }
+        }
+    }
+}
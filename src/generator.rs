@@ -0,0 +1,132 @@
+use rand::Rng;
+use std::collections::HashSet;
+
+use dictionary::{CheckWord, sort_word};
+use stats::{History, SECONDS_PER_DAY};
+use types::{Puzzle, Timestamp, Word};
+
+/// Shuffling is retried this many times if the letters happen to spell a solution.
+const MAX_SHUFFLES: usize = 10;
+
+/// How puzzles are chosen by `!slumpnian` and the daily puzzle.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    /// Prefer puzzles with exactly one solution, if there are any.
+    pub prefer_unique: bool,
+    /// Don't choose a puzzle that has been used in this many days.
+    pub avoid_recent_days: u32,
+}
+
+/// Choose a puzzle of the given length from the dictionary, with its letters shuffled. Returns
+/// `None` if the dictionary has no puzzles of that length that may be chosen.
+pub fn generate<R: Rng>(dictionary: &CheckWord,
+                        length: usize,
+                        options: &GeneratorOptions,
+                        history: &History,
+                        now: Timestamp,
+                        rng: &mut R) -> Option<Puzzle> {
+    let since = now - options.avoid_recent_days as Timestamp * SECONDS_PER_DAY;
+    let recent: HashSet<String> = history.0.iter()
+        .filter(|r| r.set_time > since)
+        .map(|r| sort_word(&Word(r.puzzle.0.clone()).normalize().0))
+        .collect();
+
+    let mut candidates: Vec<Puzzle> = dictionary.puzzles(length).into_iter()
+        .filter(|p| !recent.contains(&p.0))
+        .collect();
+    if options.prefer_unique && candidates.iter().any(|p| dictionary.no_of_solutions(p) == 1) {
+        candidates.retain(|p| dictionary.no_of_solutions(p) == 1);
+    }
+    if candidates.is_empty() {
+        return None;
+    }
+
+    // The candidates come from a hash map, so they are sorted to make the choice depend only on
+    // the random number generator.
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+    let i = rng.gen_range(0, candidates.len());
+    Some(shuffle(dictionary, &candidates[i], rng))
+}
+
+/// Shuffle the letters of a puzzle, so they don't spell one of the solutions if it can be helped.
+fn shuffle<R: Rng>(dictionary: &CheckWord, &Puzzle(ref p): &Puzzle, rng: &mut R) -> Puzzle {
+    let mut letters: Vec<char> = p.chars().collect();
+    for _ in 0..MAX_SHUFFLES {
+        rng.shuffle(&mut letters);
+        let shuffled: String = letters.iter().cloned().collect();
+        if !dictionary.is_solution(&Word(shuffled.clone())) {
+            return Puzzle(shuffled);
+        }
+    }
+    Puzzle(letters.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dictionary::{CheckWord, Dictionary, sort_word};
+    use rand::{SeedableRng, XorShiftRng};
+    use stats::{History, PuzzleRecord, SECONDS_PER_DAY};
+    use types::*;
+
+    // 2016-09-19 12:00:00 UTC, a monday.
+    const NOW: Timestamp = 1474286400;
+
+    fn dictionary() -> Dictionary {
+        Dictionary::new(vec!["GALLTJUTA", "DATORSPEL", "SPELDATOR", "ABC"].into_iter().map(|x| x.to_string()))
+    }
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([17, 42, 4711, 1337])
+    }
+
+    #[test]
+    fn generate_test() {
+        let d = dictionary();
+        let options = GeneratorOptions::default();
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let puzzle = generate(&d, 9, &options, &History::default(), NOW, &mut rng).unwrap();
+            assert!(d.has_solution(&puzzle), "{:?}", puzzle);
+            assert!(!d.is_solution(&Word(puzzle.0.clone())), "{:?} is not shuffled", puzzle);
+        }
+
+        assert_eq!(generate(&d, 3, &options, &History::default(), NOW, &mut rng).map(|p| sort_word(&p.0)),
+                   Some("ABC".to_string()));
+        assert_eq!(generate(&d, 10, &options, &History::default(), NOW, &mut rng), None);
+    }
+
+    #[test]
+    fn prefer_unique_test() {
+        let d = dictionary();
+        let options = GeneratorOptions { prefer_unique: true, ..GeneratorOptions::default() };
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let puzzle = generate(&d, 9, &options, &History::default(), NOW, &mut rng).unwrap();
+            assert_eq!(d.no_of_solutions(&puzzle), 1);
+        }
+    }
+
+    #[test]
+    fn avoid_recent_test() {
+        let d = dictionary();
+        let options = GeneratorOptions { avoid_recent_days: 7, ..GeneratorOptions::default() };
+        let mut rng = rng();
+
+        let history = History(vec![PuzzleRecord::new(&Puzzle("TAGALLTJU".into()), NOW - 6 * SECONDS_PER_DAY)]);
+        for _ in 0..20 {
+            let puzzle = generate(&d, 9, &options, &history, NOW, &mut rng).unwrap();
+            assert_eq!(d.no_of_solutions(&puzzle), 2);
+        }
+
+        // Puzzles used before that may be chosen again.
+        let history = History(vec![
+            PuzzleRecord::new(&Puzzle("TAGALLTJU".into()), NOW - 8 * SECONDS_PER_DAY),
+            PuzzleRecord::new(&Puzzle("PELDATORS".into()), NOW - SECONDS_PER_DAY),
+        ]);
+        let puzzle = generate(&d, 9, &options, &history, NOW, &mut rng).unwrap();
+        assert_eq!(sort_word(&puzzle.0), "AAGJLLTTU");
+    }
+}
@@ -0,0 +1,200 @@
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use std::collections::BTreeMap;
+use std::io;
+
+use dictionary::sort_word;
+use types::{Puzzle, Word};
+
+// An index file is a header followed by the entries. The header is the magic bytes, the version and
+// a SHA-256 checksum of the entries. The entries are a count, and then for each puzzle key its
+// sorted letters and the words with those letters. All integers are little endian u32, and all
+// strings are a length in bytes followed by UTF-8.
+
+/// Every index file starts with these bytes, so it can be told apart from a word list.
+pub const MAGIC: &'static [u8] = b"NIANIDX\0";
+/// Indexes with another version can't be read, and must be rebuilt with `niancat-index`.
+pub const VERSION: u32 = 1;
+const CHECKSUM_LENGTH: usize = 32;
+const HEADER_LENGTH: usize = 8 + 4 + CHECKSUM_LENGTH;
+
+pub fn is_index(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+fn checksum(bytes: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(bytes);
+    let mut result = vec![0; CHECKSUM_LENGTH];
+    hasher.result(&mut result);
+    result
+}
+
+fn push_u32(bytes: &mut Vec<u8>, x: u32) {
+    for i in 0..4 {
+        bytes.push((x >> (8 * i)) as u8);
+    }
+}
+
+fn push_string(bytes: &mut Vec<u8>, s: &str) {
+    push_u32(bytes, s.len() as u32);
+    bytes.extend(s.as_bytes());
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    bytes.iter().take(4).enumerate().fold(0, |x, (i, &b)| x | (b as u32) << (8 * i))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads the entries of an index, in order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() - self.position < n {
+            return Err(invalid_data("The index is truncated"));
+        }
+        let taken = &self.bytes[self.position..self.position + n];
+        self.position += n;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.take(4).map(read_u32)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let length = try!(self.u32()) as usize;
+        let bytes = try!(self.take(length));
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid_data("The index has a string that is not valid UTF-8"))
+    }
+}
+
+/// Write an index of the words, which must already be normalized.
+pub fn encode(words: &[Word]) -> Vec<u8> {
+    let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for &Word(ref w) in words {
+        entries.entry(sort_word(w)).or_insert(vec![]).push(w.clone());
+    }
+
+    let mut payload = vec![];
+    push_u32(&mut payload, entries.len() as u32);
+    for (key, mut words) in entries {
+        words.sort();
+        words.dedup();
+        push_string(&mut payload, &key);
+        push_u32(&mut payload, words.len() as u32);
+        for w in &words {
+            push_string(&mut payload, w);
+        }
+    }
+
+    let mut index = MAGIC.to_vec();
+    push_u32(&mut index, VERSION);
+    index.extend(checksum(&payload));
+    index.extend(payload);
+    index
+}
+
+/// Read an index written by `encode`. The words are grouped by their sorted letters.
+pub fn decode(contents: &[u8]) -> io::Result<Vec<(Puzzle, Vec<Word>)>> {
+    if !is_index(contents) {
+        return Err(invalid_data("Not a niancat index"));
+    }
+    if contents.len() < HEADER_LENGTH {
+        return Err(invalid_data("The index is truncated"));
+    }
+
+    let version = read_u32(&contents[MAGIC.len()..]);
+    if version != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                  format!("The index has version {}, but only version {} can be read. \
+                                           Rebuild it with niancat-index", version, VERSION)));
+    }
+
+    let payload = &contents[HEADER_LENGTH..];
+    if checksum(payload)[..] != contents[HEADER_LENGTH - CHECKSUM_LENGTH..HEADER_LENGTH] {
+        return Err(invalid_data("The index checksum does not match. The file may be damaged"));
+    }
+
+    let mut reader = Reader { bytes: payload, position: 0 };
+    let no_of_entries = try!(reader.u32());
+    let mut entries = vec![];
+    for _ in 0..no_of_entries {
+        let key = try!(reader.string());
+        let no_of_words = try!(reader.u32());
+        let mut words = vec![];
+        for _ in 0..no_of_words {
+            let word = try!(reader.string());
+            if sort_word(&word) != key {
+                return Err(invalid_data(&format!("The index has the word {} under the letters {}", word, key)));
+            }
+            words.push(Word(word));
+        }
+        entries.push((Puzzle(key), words));
+    }
+
+    if reader.position != payload.len() {
+        return Err(invalid_data("The index has unexpected data after the last entry"));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::*;
+
+    fn example_words() -> Vec<Word> {
+        vec!["DATORSPEL", "GALLTJUTA", "SPELDATOR", "DATORSPEL"].into_iter()
+            .map(|x| Word(x.into()))
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip_test() {
+        let index = encode(&example_words());
+        assert!(is_index(&index));
+
+        let entries = decode(&index).unwrap();
+        assert_eq!(entries, vec![
+            (Puzzle("AAGJLLTTU".into()), vec![Word("GALLTJUTA".into())]),
+            (Puzzle("ADELOPRST".into()), vec![Word("DATORSPEL".into()), Word("SPELDATOR".into())]),
+        ]);
+    }
+
+    #[test]
+    fn invalid_index_test() {
+        let index = encode(&example_words());
+
+        let mut damaged = index.clone();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 1;
+        assert!(format!("{}", decode(&damaged).unwrap_err()).contains("checksum"));
+
+        let mut wrong_version = index.clone();
+        wrong_version[MAGIC.len()] = 17;
+        assert!(format!("{}", decode(&wrong_version).unwrap_err()).contains("version 17"));
+
+        assert!(format!("{}", decode(&index[..20]).unwrap_err()).contains("truncated"));
+        assert!(decode(b"DATORSPEL\nSPELDATOR\n").is_err());
+
+        // Every word must have the letters it is listed under.
+        let mut payload = vec![];
+        push_u32(&mut payload, 1);
+        push_string(&mut payload, "ADELOPRST");
+        push_u32(&mut payload, 1);
+        push_string(&mut payload, "GALLTJUTA");
+        let mut mismatched = MAGIC.to_vec();
+        push_u32(&mut mismatched, VERSION);
+        mismatched.extend(checksum(&payload));
+        mismatched.extend(payload);
+        assert!(format!("{}", decode(&mismatched).unwrap_err()).contains("GALLTJUTA"));
+    }
+}
@@ -4,8 +4,12 @@ extern crate regex;
 extern crate multimap;
 extern crate crypto;
 extern crate hyper;
+extern crate rustc_serialize;
+extern crate toml;
+extern crate libc;
+extern crate rand;
 
-use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use slack::api::channels::ListResponse;
 use slack::api;
@@ -13,139 +17,596 @@ use slack::api;
 
 pub mod types;
 pub mod dictionary;
+pub mod store;
+pub mod stats;
+pub mod backend;
+pub mod slack_backend;
+pub mod config;
+pub mod index;
+pub mod roles;
+mod messages;
 mod logic;
 mod parser;
 mod response;
-
-use response::{Respond, new_responder, SlackResponse};
-use types::{Channel, Name};
+mod game;
+mod reload;
+mod generator;
+mod scheduler;
+mod ratelimit;
+
+use std::collections::HashSet;
+
+use backend::{ChatBackend, IncomingMessage};
+use dictionary::{CheckWord, DictionaryFiles, SharedDictionary};
+use game::Game;
+use logic::Command;
+use ratelimit::{RateLimiter, Verdict};
+use reload::{ReloadRequest, Reloader};
+use roles::{Role, Roles};
+use scheduler::Scheduler;
+use response::{Response, RefusedReason, SlackResponse};
+use store::{SettingsStore, State, StateStore, UserSettings};
+use types::Timestamp;
+
+pub use messages::Language;
+pub use response::{Attachment, Field};
+pub use generator::GeneratorOptions;
+pub use scheduler::Schedule;
+pub use ratelimit::GuessLimit;
+
+pub use slack_backend::{SlackEventHandler, SlackListChannels};
+
+fn now() -> Timestamp {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as Timestamp).unwrap_or(0)
+}
 
 pub struct NiancatHandler<'a> {
-    state: logic::Niancat<'a>,
-    responder: Box<Respond>,
-    users: HashMap<String, slack::User>,
+    dictionary: &'a CheckWord,
+    games: Vec<Game<'a>>,
+    /// What users have chosen for themselves, like the game selected with `!spela` and the language
+    /// chosen with `!språk`.
+    settings: UserSettings,
+    settings_store: Option<Box<SettingsStore>>,
+    /// The commands that are enabled, or `None` if all are.
+    enabled_commands: Option<HashSet<String>>,
+    /// The configured roles, which decide who may run the admin commands that concern all games.
+    roles: Roles,
+    /// Ask the admins to delete spoilers, besides warning the poster.
+    report_spoilers: bool,
+    /// Limits how often each user may guess, if guesses are limited.
+    rate_limiter: Option<RateLimiter>,
+    reloader: Option<Reloader>,
+    scheduler: Option<Scheduler>,
 }
 
 impl<'a> NiancatHandler<'a> {
-    pub fn new(dict: &'a dictionary::Dictionary, main_channel: types::Channel) -> NiancatHandler<'a> {
+    /// Create a handler without any games. Add games with `add_game`.
+    pub fn new(dict: &'a CheckWord) -> NiancatHandler<'a> {
         NiancatHandler {
-            state: logic::Niancat::new(dict),
-            responder: new_responder(&main_channel),
-            users: HashMap::new(),
+            dictionary: dict,
+            games: vec![],
+            settings: UserSettings::default(),
+            settings_store: None,
+            enabled_commands: None,
+            roles: Roles::default(),
+            report_spoilers: false,
+            rate_limiter: None,
+            reloader: None,
+            scheduler: None,
         }
     }
 
-    fn handle_command(&mut self,
-                      client: &mut slack::RtmClient,
-                      channel: &types::Channel,
-                      name: &types::Name,
-                      text: &String) {
-        let command_result = parser::parse_command(channel, name, text);
+    /// Add a game, played in the given channel. The first game added is the default game, for users
+    /// that are not members of any game channel.
+    pub fn add_game(&mut self,
+                    name: &str,
+                    channel: types::Channel,
+                    store: Box<StateStore>,
+                    initial_state: &State) {
+        let game = Game::new(name, self.dictionary, channel, store, initial_state);
+        self.games.push(game);
+    }
 
-        if let Some(result) = command_result {
-            let slack_responses = match result {
-                Ok(command) => {
-                    let response_message = logic::apply(&command, &mut self.state);
-                    self.responder.serialize(&response_message)
-                },
+    /// Save what users choose for themselves, across all games, to a store. The initial settings are
+    /// those saved before the last restart.
+    pub fn set_settings_store(&mut self, store: Box<SettingsStore>, initial_settings: &UserSettings) {
+        self.settings = initial_settings.clone();
+        self.settings_store = Some(store);
+    }
 
-                Err(invalid_command) => {
-                    self.responder.serialize_invalid_command(&invalid_command)
-                },
-            };
+    /// Set the length of new puzzles, in all games. The default is nine letters.
+    pub fn set_puzzle_length(&mut self, length: usize) {
+        for game in &mut self.games {
+            game.set_puzzle_length(length);
+        }
+    }
 
-            for SlackResponse(channel, msg) in slack_responses {
-                let result = client.send_message(channel.0.as_str(), msg.as_str());
-                if let Err(x) = result {
-                    println!("Response was not sent! Reason: {:?}", x);
-                }
+    /// Set how puzzles are chosen by `!slumpnian` and the daily puzzle, in all games.
+    pub fn set_generator_options(&mut self, options: &GeneratorOptions) {
+        for game in &mut self.games {
+            game.set_generator_options(options.clone());
+        }
+    }
+
+    /// Include the number of guesses and the most common wrong guess in the summary when a puzzle
+    /// ends, in all games.
+    pub fn set_show_guess_stats(&mut self, show: bool) {
+        for game in &mut self.games {
+            game.set_show_guess_stats(show);
+        }
+    }
+
+    /// Run the daily puzzle, reminders and publishing of solutions by themselves, in all games.
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.scheduler = Some(Scheduler::new(schedule));
+    }
+
+    /// Set the roles of the users, in all games. Roles given with `!roll` take precedence within a
+    /// game, except that admins can't be given a lesser role. Only admins by these roles may run the
+    /// admin commands that concern all games, like reloading the dictionary.
+    pub fn set_roles(&mut self, roles: &Roles) {
+        self.roles = roles.clone();
+        for game in &mut self.games {
+            game.set_roles(roles.clone());
+        }
+    }
+
+    /// Set the language of a game. Users can choose another language for their private messages.
+    pub fn set_language(&mut self, game_name: &str, language: Language) {
+        for game in self.games.iter_mut().filter(|g| g.name == game_name) {
+            game.set_language(language);
+        }
+    }
+
+    /// Allow the dictionary to be reloaded from the given files, with `!laddaom` or SIGHUP, and its
+    /// overlays to be edited with `!tillåt` and `!förbjud`. The shared dictionary must be the one
+    /// the handler was created with.
+    pub fn enable_reload(&mut self, dictionary: SharedDictionary, files: DictionaryFiles) {
+        self.reloader = Some(Reloader::new(dictionary, files));
+        reload::reload_on_sighup();
+    }
+
+    /// Ask the admins of a game to delete spoilers posted in public channels. The poster is always
+    /// warned.
+    pub fn set_report_spoilers(&mut self, report: bool) {
+        self.report_spoilers = report;
+    }
+
+    /// Limit how often each user may guess, across all games. Users who keep guessing while
+    /// refused are reported to the admins.
+    pub fn set_guess_limit(&mut self, limit: GuessLimit) {
+        self.rate_limiter = Some(RateLimiter::new(limit));
+    }
+
+    /// Only respond to the given commands. Other commands are ignored, as if they were meant for
+    /// another bot.
+    pub fn set_enabled_commands(&mut self, commands: &[String]) {
+        self.enabled_commands = Some(commands.iter().cloned().collect());
+    }
+
+    fn is_enabled(&self, text: &str) -> bool {
+        match (&self.enabled_commands, text.split_whitespace().next()) {
+            (&Some(ref enabled), Some(command)) if command.starts_with('!') => enabled.contains(command),
+            _ => true,
+        }
+    }
+
+    /// Find the game a message belongs to. Messages in a game channel belong to that game. Other
+    /// messages, like private messages, belong to the game the user has selected, or else to the
+    /// first game whose channel the user is a member of, or else to the default game.
+    fn find_game(&self, backend: &ChatBackend, message: &IncomingMessage, name: &types::Name) -> Option<usize> {
+        if let Some(i) = self.games.iter().position(|g| g.channel == message.channel) {
+            return Some(i);
+        }
+
+        if let Some(selected) = self.settings.selected_games.get(&name.0) {
+            if let Some(i) = self.games.iter().position(|g| &g.name == selected) {
+                return Some(i);
             }
         }
+
+        if let Some(i) = self.games.iter().position(|g| backend.is_member(&message.user, &g.channel)) {
+            return Some(i);
+        }
+
+        if self.games.is_empty() { None } else { Some(0) }
     }
 
-    fn handle_message(&mut self, client: &mut slack::RtmClient, message: &slack::Message) {
-        match *message {
-            slack::Message::Standard{ref channel, ref user, ref text, ..} => {
-                if channel.is_none() {
-                    println!("Message with no channel! text: {:?}", text);
-                    return;
-                }
+    fn select_game(&mut self, channel: &types::Channel, name: &types::Name, game_name: &String) -> Response {
+        let game_name = game_name.trim_left_matches('#');
+        if self.games.iter().any(|g| g.name == game_name) {
+            self.settings.selected_games.insert(name.0.clone(), game_name.to_string());
+            self.save_settings();
+            Response::GameSelected(channel.clone(), game_name.to_string())
+        } else {
+            let names = self.games.iter().map(|g| g.name.clone()).collect();
+            Response::UnknownGame(channel.clone(), game_name.to_string(), names)
+        }
+    }
 
-                if user.is_none() {
-                    println!("Message with no user! text: {:?}", user);
-                    return;
-                }
+    /// The language a user has chosen for private messages, if any.
+    fn language(&self, name: &types::Name) -> Option<Language> {
+        self.settings.languages.get(&name.0).cloned()
+    }
 
-                if text.is_none() {
-                    println!("Message with no text!");
-                    return;
+    fn set_user_language(&mut self, channel: &types::Channel, name: &types::Name, code: &String) -> Response {
+        match Language::from_code(code) {
+            Some(language) => {
+                self.settings.languages.insert(name.0.clone(), language);
+                self.save_settings();
+                Response::LanguageSet(channel.clone(), language)
+            },
+            None => Response::UnknownLanguage(channel.clone(), code.clone()),
+        }
+    }
+
+    fn save_settings(&mut self) {
+        if let Some(ref mut store) = self.settings_store {
+            if let Err(e) = store.save(&self.settings) {
+                println!("User settings were not saved! Reason: {}", e);
+            }
+        }
+    }
+
+    /// Warn the poster of a spoiler privately, and ask the admins to delete it if spoilers are
+    /// reported.
+    fn check_spoiler(&mut self,
+                     backend: &mut ChatBackend,
+                     game_index: usize,
+                     message: &IncomingMessage,
+                     name: &types::Name) -> Vec<SlackResponse> {
+        let word = match self.games[game_index].check_spoiler(&message.channel, name, &message.text, now()) {
+            None => return vec![],
+            Some(word) => word,
+        };
+        println!("{} posted the spoiler {} in {:?}", name.0, word.0, message.channel);
+
+        let mut responses = vec![];
+        if let Some(private) = backend.private_channel(&message.user) {
+            let warning = Response::SpoilerWarning(private, message.channel.clone(), word);
+            responses.extend(self.games[game_index].serialize(&warning, self.language(name)));
+        }
+
+        if self.report_spoilers {
+            responses.extend(self.notify_admins(backend, game_index, |private| {
+                Response::SpoilerReport(private, name.clone(), message.channel.clone())
+            }));
+        }
+        responses
+    }
+
+    /// Refuse a guess if the user has guessed too often, and report users who keep guessing while
+    /// refused to the admins. Refused guesses by reported users are not answered. Returns `None` if
+    /// the command may be applied.
+    fn limit_guess(&mut self,
+                   backend: &mut ChatBackend,
+                   game_index: usize,
+                   command: &Command,
+                   name: &types::Name) -> Option<Vec<SlackResponse>> {
+        let channel = match *command {
+            Command::CheckSolution(ref channel, _, _) => channel,
+            _ => return None,
+        };
+        let verdict = match self.rate_limiter {
+            None => return None,
+            Some(ref mut rate_limiter) => rate_limiter.check(name, now()),
+        };
+
+        match verdict {
+            Verdict::Allowed => None,
+            Verdict::Refused { wait, flag } => {
+                let refusal = Response::TooManyGuesses(channel.clone(), wait);
+                let mut responses = self.games[game_index].serialize(&refusal, self.language(name));
+                if flag {
+                    println!("{} keeps guessing while refused", name.0);
+                    responses.extend(self.notify_admins(backend, game_index, |private| {
+                        Response::BruteForceReport(private, name.clone())
+                    }));
                 }
+                Some(responses)
+            },
+            Verdict::Ignored => Some(vec![]),
+        }
+    }
 
-                let user = user.clone().unwrap();
-                let name = match self.users.get(&user) {
-                    None => {
-                        println!("Unknown user {:?}", user);
-                        return;
-                    },
-                    Some(u) => u.name.clone(),
-                };
+    /// Send a response to each admin of a game, in a private message.
+    fn notify_admins<F>(&self, backend: &mut ChatBackend, game_index: usize, response: F) -> Vec<SlackResponse>
+        where F: Fn(types::Channel) -> Response {
+        let game = &self.games[game_index];
+        let mut responses = vec![];
+        for admin in game.admins() {
+            if let Some(private) = backend.find_user(&admin).and_then(|user| backend.private_channel(&user)) {
+                responses.extend(game.serialize(&response(private), self.language(&admin)));
+            }
+        }
+        responses
+    }
 
-                let channel = channel.clone().unwrap();
-                let text = text.clone().unwrap();
+    /// Whether a user is an admin by the configured roles. Roles given with `!roll` only apply
+    /// within a game, so they don't count here.
+    fn is_admin(&self, name: &types::Name) -> bool {
+        self.roles.role(name) == Role::Admin
+    }
 
-                if text.contains("är inte nio tecken") {
-                    return;
+    fn reload(&mut self, game_index: usize, channel: &types::Channel, name: &types::Name) -> Response {
+        if !self.is_admin(name) {
+            return Response::ReloadRefused(channel.clone(), RefusedReason::NotAdmin);
+        }
+
+        match self.reloader {
+            None => Response::ReloadRefused(channel.clone(), RefusedReason::NotConfigured),
+            Some(ref mut reloader) => {
+                let request = ReloadRequest {
+                    channel: channel.clone(),
+                    name: name.clone(),
+                    game_index: game_index,
+                };
+                if reloader.start(Some(request)) {
+                    Response::ReloadStarted(channel.clone())
+                } else {
+                    Response::ReloadRefused(channel.clone(), RefusedReason::AlreadyRunning)
                 }
+            },
+        }
+    }
 
-                self.handle_command(client, &Channel(channel), &Name(name), &text);
+    /// Add a word to the allow overlay, or the deny overlay if `allow` is false, and update the
+    /// solutions of all games.
+    fn edit_dictionary(&mut self,
+                       game_index: usize,
+                       channel: &types::Channel,
+                       name: &types::Name,
+                       word: &types::Word,
+                       allow: bool) -> Vec<SlackResponse> {
+        let is_admin = self.is_admin(name);
+        let result = {
+            let refused = |reason| Err(Response::EditRefused(channel.clone(), reason));
+            match self.reloader {
+                _ if !is_admin => refused(RefusedReason::NotAdmin),
+                None => refused(RefusedReason::NotConfigured),
+                Some(ref reloader) if reloader.is_running() => refused(RefusedReason::AlreadyRunning),
+                Some(ref reloader) if allow && !reloader.can_allow() => refused(RefusedReason::NotConfigured),
+                Some(ref reloader) if !allow && !reloader.can_deny() => refused(RefusedReason::NotConfigured),
+                Some(ref reloader) => {
+                    let edited = if allow { reloader.allow(word) } else { reloader.deny(word) };
+                    edited.map_err(|e| Response::EditFailed(channel.clone(), e))
+                },
+            }
+        };
+
+        match result {
+            Err(response) => self.games[game_index].serialize(&response, self.language(name)),
+            Ok(()) => {
+                let word = word.normalize();
+                println!("{} {} the word {}", name.0, if allow { "allowed" } else { "denied" }, word.0);
+                let mut responses = vec![];
+                for game in &mut self.games {
+                    responses.extend(game.revalidate());
+                }
+                let response = if allow {
+                    Response::WordAllowed(channel.clone(), word)
+                } else {
+                    Response::WordDenied(channel.clone(), word)
+                };
+                responses.extend(self.games[game_index].serialize(&response, self.language(name)));
+                responses
             },
+        }
+    }
+
+    /// Run the jobs that don't wait for a message: scheduled jobs, and reloading the dictionary.
+    /// This is called for each message, and should also be called regularly by the backend.
+    pub fn poll(&mut self, backend: &mut ChatBackend) {
+        self.poll_games(backend, now());
+        self.poll_schedule(backend, now());
+        self.poll_reload(backend);
+    }
 
-            _ => {},
+    /// Run the jobs of each game that are due at `now`, like the weekly summary.
+    pub fn poll_games(&mut self, backend: &mut ChatBackend, now: Timestamp) {
+        let mut responses = vec![];
+        for game in &mut self.games {
+            responses.extend(game.poll(now));
         }
+        send_responses(backend, responses);
     }
 
-    fn handle_event(&mut self, client: &mut slack::RtmClient, event: &slack::Event) {
-        match *event {
-            slack::Event::Message(ref m) => self.handle_message(client, m),
-            slack::Event::UserChange {ref user} => self.update_user(user),
-            slack::Event::TeamJoin{ref user} => self.update_user(user),
-            _ => {},
+    /// Run the scheduled jobs that are due at `now`.
+    pub fn poll_schedule(&mut self, backend: &mut ChatBackend, now: Timestamp) {
+        let jobs = match self.scheduler {
+            None => return,
+            Some(ref mut scheduler) => scheduler.poll(now),
+        };
+
+        let mut responses = vec![];
+        for job in &jobs {
+            for game in &mut self.games {
+                responses.extend(game.run_job(job, now));
+            }
         }
+        send_responses(backend, responses);
     }
 
-    pub fn update_user(&mut self, user: &slack::User) {
-        println!("Updating user {:?}", user);
-        self.users.entry(user.id.clone()).or_insert(user.clone());
+    /// Start a reload on SIGHUP, and finish a reload when the dictionary has been loaded.
+    fn poll_reload(&mut self, backend: &mut ChatBackend) {
+        let result = match self.reloader {
+            None => return,
+            Some(ref mut reloader) => {
+                if reload::take_sighup() && reloader.start(None) {
+                    println!("Reloading the dictionary, after SIGHUP");
+                }
+                reloader.poll()
+            },
+        };
+
+        let (requested_by, result) = match result {
+            None => return,
+            Some(x) => x,
+        };
+
+        let mut responses = vec![];
+        match result {
+            Ok(()) => {
+                println!("The dictionary was reloaded");
+                // The solutions of the current puzzles may have changed.
+                for game in &mut self.games {
+                    responses.extend(game.revalidate());
+                }
+                if let Some(request) = requested_by {
+                    let response = Response::Reloaded(request.channel);
+                    responses.extend(self.games[request.game_index].serialize(&response, self.language(&request.name)));
+                }
+            },
+
+            Err(e) => {
+                println!("The dictionary could not be reloaded. Reason: {}", e);
+                if let Some(request) = requested_by {
+                    let response = Response::ReloadFailed(request.channel, e);
+                    responses.extend(self.games[request.game_index].serialize(&response, self.language(&request.name)));
+                }
+            },
+        }
+
+        send_responses(backend, responses);
     }
-}
 
-//pub fn parse_command(chan: &Channel, name: &Name, text: &String) -> CommandResult {
-
-impl<'a> slack::EventHandler for NiancatHandler<'a> {
-    fn on_event(&mut self,
-                client: &mut slack::RtmClient,
-                event: Result<&slack::Event, slack::Error>,
-                raw_json: &str) {
-        match event {
-            Ok(ok_event) => {
-                println!("on_event(event: {:?}, raw_json: {:?}", ok_event, raw_json);
-                self.handle_event(client, ok_event);
+    fn handle_command(&mut self,
+                      backend: &mut ChatBackend,
+                      message: &IncomingMessage,
+                      name: &types::Name) {
+        let command_result = parser::parse_command(&message.channel, name, &message.text)
+            .map(|result| result.map(|command| resolve_mentions(&*backend, command)))
+            .map(|result| result.map(|command| private_unsolutions(backend, &message.user, command)));
+
+        // Only messages in a game channel can give away the puzzle of the game. Other public channels
+        // have nothing to do with niancat.
+        if let Some(Ok(Command::CheckSpoiler(..))) = command_result {
+            if !self.games.iter().any(|g| g.channel == message.channel) {
+                return;
+            }
+        }
+
+        if let Some(result) = command_result {
+            let game_index = match self.find_game(&*backend, message, name) {
+                None => {
+                    println!("No game for message in channel {:?}", message.channel);
+                    return;
+                },
+                Some(i) => i,
+            };
+
+            let slack_responses = match result {
+                Ok(Command::SelectGame(ref channel, ref name, ref game_name)) => {
+                    let response = self.select_game(channel, name, game_name);
+                    self.games[game_index].serialize(&response, self.language(name))
+                },
+
+                Ok(Command::ReloadDictionary(ref channel, ref name)) => {
+                    let response = self.reload(game_index, channel, name);
+                    self.games[game_index].serialize(&response, self.language(name))
+                },
+
+                Ok(Command::SetLanguage(ref channel, ref name, ref code)) => {
+                    let response = self.set_user_language(channel, name, code);
+                    self.games[game_index].serialize(&response, self.language(name))
+                },
+
+                Ok(Command::CheckSpoiler(..)) => self.check_spoiler(backend, game_index, message, name),
+
+                Ok(Command::AllowWord(ref channel, ref name, ref word)) =>
+                    self.edit_dictionary(game_index, channel, name, word, true),
+
+                Ok(Command::DenyWord(ref channel, ref name, ref word)) =>
+                    self.edit_dictionary(game_index, channel, name, word, false),
+
+                Ok(command) => {
+                    let required = logic::required_role(&command);
+                    if self.games[game_index].role(name) < required {
+                        let response = Response::PermissionDenied(message.channel.clone(), required);
+                        self.games[game_index].serialize(&response, self.language(name))
+                    } else if let Some(refused) = self.limit_guess(backend, game_index, &command, name) {
+                        refused
+                    } else {
+                        let language = self.language(name);
+                        self.games[game_index].apply(&command, language, now())
+                    }
+                },
+
+                Err(invalid_command) => {
+                    self.games[game_index].serialize_invalid_command(&invalid_command, self.language(name))
+                },
+            };
+
+            send_responses(backend, slack_responses);
+        }
+    }
+
+    /// Handle a message received by a chat backend, and send any responses through the same
+    /// backend.
+    pub fn handle_message(&mut self, backend: &mut ChatBackend, message: &IncomingMessage) {
+        self.poll(backend);
+
+        if backend.own_user().as_ref() == Some(&message.user) {
+            return;
+        }
+
+        let name = match backend.user_name(&message.user) {
+            None => {
+                println!("Unknown user {:?}", message.user);
+                return;
             },
-            Err(bad_event) => println!("on_event(bad event: {:?}, raw_json: {:?}", bad_event, raw_json)
+            Some(name) => name,
+        };
+
+        if !self.is_enabled(&message.text) {
+            return;
         }
 
+        self.handle_command(backend, message, &name);
     }
+}
+
+/// Slack sends a mention of a user as the user id, like `<@U123>` or `<@U123|alice>`. Commands that
+/// name a user get the user name instead.
+fn resolve_mentions(backend: &ChatBackend, command: Command) -> Command {
+    let resolve = |name: types::Name| {
+        if !name.0.starts_with("<@") || !name.0.ends_with('>') {
+            return name;
+        }
+        let user = name.0[2..name.0.len() - 1].split('|').next().unwrap_or("").to_string();
+        backend.user_name(&types::User(user)).unwrap_or(name)
+    };
 
-    fn on_ping(&mut self, _client: &mut slack::RtmClient) {
-        println!("on_ping");
+    match command {
+        Command::GetUserStats(channel, name) => Command::GetUserStats(channel, resolve(name)),
+        Command::SetRole(channel, name, role) => Command::SetRole(channel, resolve(name), role),
+        command => command,
     }
+}
 
-    fn on_close(&mut self, _client: &mut slack::RtmClient) {
-        println!("on_close");
+/// Unsolutions may give away the puzzle, so when a user asks for them in a public channel, they are
+/// listed in a private message to the user instead.
+fn private_unsolutions(backend: &mut ChatBackend, user: &types::User, command: Command) -> Command {
+    match command {
+        Command::GetUnsolutions(ref channel, ref name) if !channel.is_private() => {
+            if let Some(private) = backend.private_channel(user) {
+                return Command::GetUnsolutions(private, name.clone());
+            }
+        },
+        _ => {},
     }
+    command
+}
 
-    fn on_connect(&mut self, _client: &mut slack::RtmClient) {
-        println!("Connected!");
+fn send_responses(backend: &mut ChatBackend, responses: Vec<SlackResponse>) {
+    for SlackResponse(channel, msg, attachment) in responses {
+        let result = match attachment {
+            Some(ref attachment) => backend.send_attachment(&channel, msg.as_str(), attachment),
+            None => backend.send_message(&channel, msg.as_str()),
+        };
+        if let Err(x) = result {
+            println!("Response was not sent! Reason: {}", x);
+        }
     }
 }
 
@@ -153,8 +614,24 @@ pub trait ListChannels {
     fn list_channels(&self) -> Result<ListResponse, api::Error>;
 }
 
-pub fn initialize<T: ListChannels>(c: &T, dictionary_path: &String, channel_name: &String) ->
-    Result<(dictionary::Dictionary, types::Channel), String> {
+pub fn initialize<T: ListChannels>(c: &T,
+                                   dictionary_path: &String,
+                                   channel_name: &String,
+                                   store: &StateStore) ->
+    Result<(dictionary::Dictionary, types::Channel, State), String> {
+
+    let files = DictionaryFiles::new(vec![dictionary_path.clone()]);
+    let (dictionary, mut games) = try!(initialize_games(c, &files, &[(channel_name.clone(), store)]));
+    let (channel_id, state) = games.remove(0);
+    Ok((dictionary, channel_id, state))
+}
+
+/// Load the dictionary and its overlays, and find the channel and saved state for each game. The
+/// games are given as channel names and the stores for their states.
+pub fn initialize_games<T: ListChannels>(c: &T,
+                                         dictionary_files: &DictionaryFiles,
+                                         games: &[(String, &StateStore)]) ->
+    Result<(dictionary::Dictionary, Vec<(types::Channel, State)>), String> {
 
     // List all channels and handle the response.
     let list_response = c.list_channels();
@@ -163,41 +640,30 @@ pub fn initialize<T: ListChannels>(c: &T, dictionary_path: &String, channel_name
         Ok(x) => x,
     };
 
-    // Find the channel with the given name, if present.
-    let channel = list_response.channels.iter().find(|&x| &x.name == channel_name);
-    let channel_id: types::Channel = match channel {
-        None => return Err(format!("No channel named {} found", channel_name)),
-        Some(ref c) => types::Channel(c.id.clone()),
-    };
-
-    // Load the dictionary from a file.
-    let dictionary = dictionary::Dictionary::from_file(dictionary_path);
+    // Load the dictionary from the files.
+    let dictionary = dictionary_files.load();
     let dictionary = match dictionary {
         Ok(d) => d,
         Err(e) => return Err(format!("Could not load dictionary, reason: {}", e)),
     };
 
-    Ok((dictionary, channel_id))
-}
-
-//
-//
-//
-
-pub struct SlackListChannels {
-    pub token: String,
-}
-
-impl ListChannels for SlackListChannels {
-    fn list_channels(&self) -> Result<ListResponse, api::Error> {
-        let client = hyper::Client::new();
-        api::channels::list(&client, &self.token, Some(true))
+    let mut initialized = vec![];
+    for &(ref channel_name, store) in games {
+        // Find the channel with the given name, if present.
+        let channel = list_response.channels.iter().find(|&x| &x.name == channel_name);
+        let channel_id: types::Channel = match channel {
+            None => return Err(format!("No channel named {} found", channel_name)),
+            Some(ref c) => types::Channel(c.id.clone()),
+        };
+
+        // Restore the state saved before the last restart, if any.
+        let state = match store.load() {
+            Ok(state) => state.unwrap_or(State::default()),
+            Err(e) => return Err(format!("Could not load the saved state for {}, reason: {}", channel_name, e)),
+        };
+
+        initialized.push((channel_id, state));
     }
-}
 
-impl SlackListChannels {
-    pub fn list_users(&self) -> Result<api::users::ListResponse, api::Error> {
-        let client = hyper::Client::new();
-        api::users::list(&client, &self.token, Some(false))
-    }
-}
\ No newline at end of file
+    Ok((dictionary, initialized))
+}
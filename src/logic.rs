@@ -2,47 +2,304 @@ use crypto::digest::Digest;
 use crypto::sha2::Sha256;
 use std::collections::{HashMap, HashSet};
 use std::iter::{FromIterator, repeat};
+use rand::{self, Rng};
 
 use types::*;
 use dictionary::*;
 use response::*;
+use stats::*;
+use store::{ReplacedState, State};
+use roles::{Role, Roles};
+use generator::{self, GeneratorOptions};
+
+/// The most number of near misses suggested when a guess is not in the dictionary.
+const MAX_NEAR_MISSES: usize = 5;
+/// A puzzle set less than this many seconds ago is only replaced after confirmation.
+const RECENT_PUZZLE_SECONDS: Timestamp = 60 * 60;
+/// The seconds a replacement waits for a confirmation.
+const CONFIRM_SECONDS: Timestamp = 5 * 60;
+/// The seconds a replaced puzzle can be restored with `!ångra`. Its solutions, and the salt of its
+/// solution hashes, are published after that.
+const UNDO_SECONDS: Timestamp = 15 * 60;
+/// The most seconds wrong guesses go unsaved. They are not saved for each guess, since that would
+/// write the whole state for every message.
+const SAVE_GUESSES_SECONDS: Timestamp = 60;
+/// The number of random bytes in the salt of each puzzle.
+const SALT_BYTES: usize = 16;
+
+/// What a setter replaces the current puzzle with.
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Replacement {
+    /// A puzzle set with `!setnian`.
+    Puzzle(Puzzle),
+    /// A puzzle chosen from the dictionary with `!slumpnian`.
+    Generated,
+    /// The next puzzle in the queue, with `!nästanian`.
+    Next,
+}
+
+/// A puzzle that has been replaced, with what is needed to restore it.
+struct ReplacedPuzzle {
+    puzzle: Puzzle,
+    solutions: SolutionsMap,
+    unsolutions: UnsolutionsMap,
+    hints: HintCounts,
+    guesses: HashMap<Name, Vec<Guess>>,
+    /// The length of the history before the new puzzle was added to it.
+    history_length: usize,
+    replaced_at: Timestamp,
+}
 
 pub struct Niancat<'a> {
     puzzle: Option<Puzzle>,
     solutions: SolutionsMap,
+    unsolutions: UnsolutionsMap,
+    history: History,
+    hints: HintCounts,
+    /// Every guess on the current puzzle, by each user.
+    guesses: HashMap<Name, Vec<Guess>>,
+    /// Puzzles to set next, first in line first.
+    queue: Vec<Puzzle>,
+    /// The configured roles.
+    roles: Roles,
+    /// The roles given with `!roll`.
+    assigned_roles: HashMap<Name, Role>,
+    /// The spoilers of the current puzzle.
+    spoilers: Vec<Spoiler>,
+    /// The last week whose summary has been posted.
+    summarized_week: Option<i64>,
+    /// Whether the state has changed since it was last saved.
+    changed: bool,
+    /// When the first wrong guess since the state was last saved was made, if any.
+    unsaved_guesses_since: Option<Timestamp>,
+    /// The replacement each setter must confirm, and when it was asked for.
+    pending_puzzles: HashMap<Name, (Replacement, Timestamp)>,
+    /// The puzzle replaced last, until its solutions are published.
+    replaced: Option<ReplacedPuzzle>,
+    puzzle_length: usize,
+    generator: GeneratorOptions,
+    /// Include stats of the guesses in the summary when a puzzle ends.
+    show_guess_stats: bool,
     dictionary: &'a CheckWord,
 }
 
 impl<'a> Niancat<'a> {
-    pub fn new<T: CheckWord>(dictionary: &'a T) -> Niancat<'a> {
+    pub fn new(dictionary: &'a CheckWord) -> Niancat<'a> {
         Niancat { puzzle: None,
                   solutions: SolutionsMap(HashMap::new()),
+                  unsolutions: UnsolutionsMap(HashMap::new()),
+                  history: History::default(),
+                  hints: HashMap::new(),
+                  guesses: HashMap::new(),
+                  queue: vec![],
+                  roles: Roles::default(),
+                  assigned_roles: HashMap::new(),
+                  spoilers: vec![],
+                  summarized_week: None,
+                  changed: false,
+                  unsaved_guesses_since: None,
+                  pending_puzzles: HashMap::new(),
+                  replaced: None,
+                  puzzle_length: DEFAULT_PUZZLE_LENGTH,
+                  generator: GeneratorOptions::default(),
+                  show_guess_stats: false,
                   dictionary: dictionary,
                 }
     }
 
     #[allow(dead_code)]
-    pub fn new_with_puzzle<T: CheckWord>(dictionary: &'a T, puzzle: Puzzle) -> Niancat<'a> {
+    pub fn new_with_puzzle(dictionary: &'a CheckWord, puzzle: Puzzle) -> Niancat<'a> {
         Niancat { puzzle: Some(puzzle),
                   solutions: SolutionsMap(HashMap::new()),
+                  unsolutions: UnsolutionsMap(HashMap::new()),
+                  history: History::default(),
+                  hints: HashMap::new(),
+                  guesses: HashMap::new(),
+                  queue: vec![],
+                  roles: Roles::default(),
+                  assigned_roles: HashMap::new(),
+                  spoilers: vec![],
+                  summarized_week: None,
+                  changed: false,
+                  unsaved_guesses_since: None,
+                  pending_puzzles: HashMap::new(),
+                  replaced: None,
+                  puzzle_length: DEFAULT_PUZZLE_LENGTH,
+                  generator: GeneratorOptions::default(),
+                  show_guess_stats: false,
                   dictionary: dictionary,
                 }
     }
+
+    /// Set the length of new puzzles. Guesses are always checked against the length of the current
+    /// puzzle.
+    pub fn set_puzzle_length(&mut self, length: usize) {
+        self.puzzle_length = length;
+    }
+
+    pub fn puzzle_length(&self) -> usize {
+        self.puzzle_length
+    }
+
+    pub fn set_generator_options(&mut self, options: GeneratorOptions) {
+        self.generator = options;
+    }
+
+    pub fn set_show_guess_stats(&mut self, show: bool) {
+        self.show_guess_stats = show;
+    }
+
+    pub fn set_roles(&mut self, roles: Roles) {
+        self.roles = roles;
+    }
+
+    /// The role of a user. A role given with `!roll` takes precedence over the configured role.
+    pub fn role(&self, name: &Name) -> Role {
+        self.assigned_roles.get(name).cloned().unwrap_or(self.roles.role(name))
+    }
+
+    /// The users that are admins, by the config or by `!roll`, in name order.
+    pub fn admins(&self) -> Vec<Name> {
+        let mut names: Vec<Name> = self.roles.users.keys().chain(self.assigned_roles.keys())
+            .filter(|name| self.role(name) == Role::Admin)
+            .cloned()
+            .collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        names.dedup();
+        names
+    }
+
+    /// Whether the state has changed since this was last called, and needs to be saved.
+    pub fn take_changed(&mut self) -> bool {
+        let changed = self.changed;
+        self.changed = false;
+        if changed {
+            self.unsaved_guesses_since = None;
+        }
+        changed
+    }
+
+    /// Take a snapshot of the state, so it can be saved in a `StateStore`.
+    pub fn to_state(&self) -> State {
+        State {
+            puzzle: self.puzzle.as_ref().map(|p| p.0.clone()),
+            solutions: self.solutions.0.iter().map(|(w, names)| (w.0.clone(), names.clone())).collect(),
+            unsolutions: self.unsolutions.0.iter().map(|(n, texts)| (n.0.clone(), texts.clone())).collect(),
+            history: self.history.clone(),
+            hints: self.hints.iter().map(|(n, &count)| (n.0.clone(), count)).collect(),
+            guesses: self.guesses.iter().map(|(n, guesses)| (n.0.clone(), guesses.clone())).collect(),
+            queue: self.queue.iter().map(|p| p.0.clone()).collect(),
+            roles: self.assigned_roles.iter().map(|(n, &role)| (n.0.clone(), role)).collect(),
+            spoilers: self.spoilers.clone(),
+            summarized_week: self.summarized_week,
+            replaced: self.replaced.as_ref().map(|r| ReplacedState {
+                puzzle: r.puzzle.0.clone(),
+                solutions: r.solutions.0.iter().map(|(w, names)| (w.0.clone(), names.clone())).collect(),
+                unsolutions: r.unsolutions.0.iter().map(|(n, texts)| (n.0.clone(), texts.clone())).collect(),
+                hints: r.hints.iter().map(|(n, &count)| (n.0.clone(), count)).collect(),
+                guesses: r.guesses.iter().map(|(n, guesses)| (n.0.clone(), guesses.clone())).collect(),
+                history_length: r.history_length,
+                replaced_at: r.replaced_at,
+            }),
+        }
+    }
+
+    /// Restore a snapshot taken by `to_state`.
+    pub fn restore(&mut self, state: &State) {
+        self.puzzle = state.puzzle.as_ref().map(|p| Puzzle(p.clone()));
+        self.solutions = SolutionsMap(state.solutions.iter().map(|(w, names)| (Word(w.clone()), names.clone())).collect());
+        self.unsolutions = UnsolutionsMap(state.unsolutions.iter().map(|(n, texts)| (Name(n.clone()), texts.clone())).collect());
+        self.history = state.history.clone();
+        self.hints = state.hints.iter().map(|(n, &count)| (Name(n.clone()), count)).collect();
+        self.guesses = state.guesses.iter().map(|(n, guesses)| (Name(n.clone()), guesses.clone())).collect();
+        self.queue = state.queue.iter().map(|p| Puzzle(p.clone())).collect();
+        self.assigned_roles = state.roles.iter().map(|(n, &role)| (Name(n.clone()), role)).collect();
+        self.spoilers = state.spoilers.clone();
+        self.summarized_week = state.summarized_week;
+        self.replaced = state.replaced.as_ref().map(|r| ReplacedPuzzle {
+            puzzle: Puzzle(r.puzzle.clone()),
+            solutions: SolutionsMap(r.solutions.iter().map(|(w, names)| (Word(w.clone()), names.clone())).collect()),
+            unsolutions: UnsolutionsMap(r.unsolutions.iter().map(|(n, texts)| (Name(n.clone()), texts.clone())).collect()),
+            hints: r.hints.iter().map(|(n, &count)| (Name(n.clone()), count)).collect(),
+            guesses: r.guesses.iter().map(|(n, guesses)| (Name(n.clone()), guesses.clone())).collect(),
+            history_length: r.history_length,
+            replaced_at: r.replaced_at,
+        });
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum Command {
     GetPuzzle(Channel),
-    SetPuzzle(Channel, Puzzle),
+    SetPuzzle(Channel, Name, Puzzle),
+    GeneratePuzzle(Channel, Name),
+    NextPuzzle(Channel, Name),
+    QueuePuzzle(Channel, Name, Puzzle),
+    GetQueue(Channel, Name),
+    RemoveFromQueue(Channel, Name, String),
     CheckSolution(Channel, Name, Word),
+    /// A message in a public channel, which must not give away the puzzle.
+    CheckSpoiler(Channel, Name, String),
+    SetUnsolution(Channel, Name, String),
+    GetUnsolutions(Channel, Name),
+    /// List the user's guesses on the current puzzle.
+    GetGuesses(Channel, Name),
+    GetLeaderboard(Channel),
+    GetUserStats(Channel, Name),
+    GetHint(Channel, Name),
+    SelectGame(Channel, Name, String),
+    ReloadDictionary(Channel, Name),
+    AllowWord(Channel, Name, Word),
+    DenyWord(Channel, Name, Word),
+    /// Restore the puzzle that was replaced.
+    Undo(Channel),
+    /// Give a user a role, by the name of the role.
+    SetRole(Channel, Name, String),
+    GetRoles(Channel),
+    VerifyHash(Channel, String),
+    /// Choose the language of private messages, by its code.
+    SetLanguage(Channel, Name, String),
     Help(Channel),
 }
 
-pub fn apply(command: &Command, state: &mut Niancat) -> Response {
+/// The role needed to run a command. The admin commands that are run by the `NiancatHandler` are
+/// checked there.
+pub fn required_role(command: &Command) -> Role {
+    match *command {
+        Command::SetPuzzle(..) | Command::GeneratePuzzle(..) | Command::NextPuzzle(..) |
+        Command::Undo(..) => Role::Setter,
+        Command::QueuePuzzle(..) | Command::GetQueue(..) | Command::RemoveFromQueue(..) |
+        Command::SetRole(..) => Role::Admin,
+        _ => Role::Player,
+    }
+}
+
+/// Apply a command to the state, at the time `now`.
+pub fn apply(command: &Command, state: &mut Niancat, now: Timestamp) -> Response {
     match command {
         &Command::GetPuzzle(ref c) => get_puzzle(state, &c),
-        &Command::SetPuzzle(ref channel, ref puzzle) => set_puzzle(state, &channel, &puzzle),
-        &Command::CheckSolution(ref chan, ref name, ref word) => check_solution(state, &chan, &name, &word),
+        &Command::SetPuzzle(ref channel, ref name, ref puzzle) => set_puzzle_command(state, &channel, &name, &puzzle, now),
+        &Command::GeneratePuzzle(ref channel, ref name) => generate_puzzle(state, &channel, &name, now),
+        &Command::NextPuzzle(ref channel, ref name) => next_puzzle(state, &channel, &name, now),
+        &Command::Undo(ref channel) => undo(state, &channel, now),
+        &Command::QueuePuzzle(ref chan, _, ref puzzle) => queue_puzzle(state, &chan, &puzzle),
+        &Command::GetQueue(ref chan, _) => Response::Queue(chan.clone(), state.queue.clone()),
+        &Command::RemoveFromQueue(ref chan, _, ref entry) => remove_from_queue(state, &chan, &entry),
+        &Command::CheckSolution(ref chan, ref name, ref word) => check_solution(state, &chan, &name, &word, now),
+        &Command::SetUnsolution(ref chan, ref name, ref text) => set_unsolution(state, &chan, &name, &text),
+        &Command::GetUnsolutions(ref chan, ref name) => get_unsolutions(state, &chan, &name),
+        &Command::GetGuesses(ref chan, ref name) => get_guesses(state, &chan, &name),
+        &Command::GetLeaderboard(ref chan) => get_leaderboard(state, &chan, now),
+        &Command::GetUserStats(ref chan, ref name) => get_user_stats(state, &chan, &name),
+        &Command::GetHint(ref chan, ref name) => get_hint(state, &chan, &name),
+        // These commands concern more than a single game, or need the backend, so the
+        // `NiancatHandler` runs them itself. Applied to a game, they do nothing and get no response.
+        &Command::CheckSpoiler(..) | &Command::SelectGame(..) | &Command::ReloadDictionary(..) |
+        &Command::AllowWord(..) | &Command::DenyWord(..) | &Command::SetLanguage(..) =>
+            Response::Multiple(vec![]),
+        &Command::SetRole(ref chan, ref name, ref role) => set_role(state, &chan, &name, &role),
+        &Command::GetRoles(ref chan) => get_roles(state, &chan),
+        &Command::VerifyHash(ref chan, ref hash) => verify_hash(state, &chan, &hash),
         &Command::Help(ref chan) => help_command(&chan),
     }
 }
@@ -55,69 +312,558 @@ fn get_puzzle(state: &mut Niancat, channel: &Channel) -> Response {
     }
 }
 
-fn set_puzzle(state: &mut Niancat, channel: &Channel, puzzle: &Puzzle) -> Response {
-    if !is_right_length(&puzzle.0) {
-        return Response::InvalidPuzzle(channel.clone(), puzzle.clone(), InvalidPuzzleReason::NotNineCharacters);
+/// Check that a puzzle may be set: it has the puzzle length, and a solution in the dictionary.
+fn validate_puzzle(state: &Niancat, puzzle: &Puzzle) -> Result<(), InvalidPuzzleReason> {
+    if !is_right_length(&puzzle.0, state.puzzle_length) {
+        Err(InvalidPuzzleReason::WrongLength(state.puzzle_length))
+    } else if !state.dictionary.has_solution(puzzle) {
+        Err(InvalidPuzzleReason::NotInDictionary)
+    } else {
+        Ok(())
     }
-    if state.dictionary.has_solution(&puzzle) {
-        let old_solutions = state.solutions.clone();
+}
 
-        state.puzzle = Some(puzzle.clone());
-        let new_solutions = state.dictionary.find_solutions(puzzle).unwrap();
+/// Why the current puzzle should not be replaced without confirmation, if it shouldn't.
+fn confirm_reason(state: &Niancat, now: Timestamp) -> Option<ConfirmReason> {
+    if state.puzzle.is_none() {
+        return None;
+    }
+    if let Some(record) = state.history.current() {
+        if now - record.set_time < RECENT_PUZZLE_SECONDS {
+            return Some(ConfirmReason::SetRecently(now - record.set_time));
+        }
+    }
+    if state.solutions.0.values().all(|solvers| solvers.is_empty()) {
+        return Some(ConfirmReason::NoSolvers);
+    }
+    None
+}
 
-        state.solutions = SolutionsMap(HashMap::from_iter(new_solutions.into_iter().zip(repeat(vec![]))));
+/// Ask a setter to confirm replacing a current puzzle that was set recently, or that nobody has
+/// solved, by giving the same command again. Returns the response asking for the confirmation, or
+/// `None` if the puzzle may be replaced.
+fn confirm_replacement(state: &mut Niancat, channel: &Channel, name: &Name, replacement: Replacement,
+                       now: Timestamp) -> Option<Response> {
+    let confirmed = match state.pending_puzzles.remove(name) {
+        Some((ref pending, time)) => *pending == replacement && now - time <= CONFIRM_SECONDS,
+        None => false,
+    };
+    if confirmed {
+        return None;
+    }
 
-        let set_response = Response::SetPuzzle(channel.clone(), puzzle.clone(), state.dictionary.no_of_solutions(&puzzle));
+    let reason = match confirm_reason(state, now) {
+        Some(reason) => reason,
+        None => return None,
+    };
+    let response = match replacement {
+        Replacement::Puzzle(ref puzzle) => Response::ConfirmSetPuzzle(channel.clone(), puzzle.clone(), reason),
+        Replacement::Generated => Response::ConfirmGeneratePuzzle(channel.clone(), reason),
+        Replacement::Next => Response::ConfirmNextPuzzle(channel.clone(), reason),
+    };
+    state.pending_puzzles.insert(name.clone(), (replacement, now));
+    Some(response)
+}
 
-        if !old_solutions.0.is_empty() {
-            let notification_response = Response::SolutionsNotification(old_solutions.clone());
-            return Response::Dual(Box::new(set_response), Box::new(notification_response));
-        } else {
-            return set_response;
-        }
-    } else {
-        Response::InvalidPuzzle(channel.clone(), puzzle.clone(), InvalidPuzzleReason::NotInDictionary)
+/// Set a puzzle with `!setnian`, after confirmation if needed.
+fn set_puzzle_command(state: &mut Niancat, channel: &Channel, name: &Name, puzzle: &Puzzle, now: Timestamp) -> Response {
+    // Invalid puzzles are refused right away, instead of after the confirmation.
+    if validate_puzzle(state, puzzle).is_err() {
+        state.pending_puzzles.remove(name);
+    } else if let Some(response) = confirm_replacement(state, channel, name, Replacement::Puzzle(puzzle.clone()), now) {
+        return response;
     }
+    set_puzzle(state, channel, puzzle, now)
 }
 
-fn check_solution(state: &mut Niancat, channel: &Channel, name: &Name, word: &Word) -> Response {
-    let normalized_word = word.normalize();
-    if let Some(ref puzzle) = state.puzzle {
-        if !is_right_length(&normalized_word.0) {
-            return Response::IncorrectSolution(channel.clone(), word.clone(),
-                Reason::NotNineCharacters);
-        }
+fn set_puzzle(state: &mut Niancat, channel: &Channel, puzzle: &Puzzle, now: Timestamp) -> Response {
+    if let Err(reason) = validate_puzzle(state, puzzle) {
+        return Response::InvalidPuzzle(channel.clone(), puzzle.clone(), reason);
+    }
+
+    let set_response = Response::SetPuzzle(channel.clone(), puzzle.clone(), state.dictionary.no_of_solutions(&puzzle));
+    let mut responses = vec![set_response];
+
+    // A puzzle replaced before can no longer be restored, so its solutions are published now. The
+    // solutions of the current puzzle are published once it can no longer be restored either.
+    responses.extend(take_replaced(state));
+    state.changed = true;
+    state.replaced = state.puzzle.take().map(|old_puzzle| ReplacedPuzzle {
+        puzzle: old_puzzle,
+        solutions: state.solutions.clone(),
+        unsolutions: state.unsolutions.clone(),
+        hints: state.hints.clone(),
+        guesses: state.guesses.clone(),
+        history_length: state.history.0.len(),
+        replaced_at: now,
+    });
+    state.pending_puzzles.clear();
+
+    state.puzzle = Some(puzzle.clone());
+    let new_solutions = state.dictionary.find_solutions(puzzle).unwrap();
+
+    state.solutions = SolutionsMap(HashMap::from_iter(new_solutions.into_iter().zip(repeat(vec![]))));
+    state.unsolutions = UnsolutionsMap(HashMap::new());
+    state.hints = HashMap::new();
+    state.guesses = HashMap::new();
+    state.spoilers = vec![];
+
+    let mut record = PuzzleRecord::new(puzzle, now);
+    record.salt = new_salt();
+    state.history.0.push(record);
+
+    Response::combine(responses)
+}
+
+/// Set a puzzle chosen from the dictionary, as if it had been set with `!setnian`.
+fn generate_puzzle(state: &mut Niancat, channel: &Channel, name: &Name, now: Timestamp) -> Response {
+    if let Some(response) = confirm_replacement(state, channel, name, Replacement::Generated, now) {
+        return response;
+    }
+
+    let puzzle = generator::generate(state.dictionary, state.puzzle_length, &state.generator, &state.history,
+                                     now, &mut rand::thread_rng());
+    match puzzle {
+        Some(puzzle) => set_puzzle(state, channel, &puzzle, now),
+        None => Response::NoPuzzleToGenerate(channel.clone(), state.puzzle_length),
+    }
+}
+
+/// Restore the puzzle that was replaced, with its solvers, if it was replaced recently. The new
+/// puzzle is removed from the history, as if it was never set.
+fn undo(state: &mut Niancat, channel: &Channel, now: Timestamp) -> Response {
+    // A puzzle replaced too long ago is left for `publish_replaced`, to publish its solutions.
+    let replaced = match state.replaced.take() {
+        Some(ref replaced) if now - replaced.replaced_at > UNDO_SECONDS => None,
+        replaced => replaced,
+    };
+    let replaced = match replaced {
+        Some(replaced) => replaced,
+        None => return Response::NothingToUndo(channel.clone()),
+    };
+
+    state.changed = true;
+    state.history.0.truncate(replaced.history_length);
+    state.solutions = replaced.solutions;
+    state.unsolutions = replaced.unsolutions;
+    state.hints = replaced.hints;
+    state.guesses = replaced.guesses;
+    state.spoilers = vec![];
+    state.pending_puzzles.clear();
+    let n = state.dictionary.no_of_solutions(&replaced.puzzle);
+    state.puzzle = Some(replaced.puzzle.clone());
+    Response::PuzzleRestored(channel.clone(), replaced.puzzle, n)
+}
+
+/// Set the next puzzle in the queue, after confirmation if needed.
+fn next_puzzle(state: &mut Niancat, channel: &Channel, name: &Name, now: Timestamp) -> Response {
+    if state.queue.is_empty() {
+        return Response::QueueEmpty(channel.clone());
+    }
+    if let Some(response) = confirm_replacement(state, channel, name, Replacement::Next, now) {
+        return response;
+    }
+
+    match pop_queue(state) {
+        Some(puzzle) => set_puzzle(state, channel, &puzzle, now),
+        None => Response::QueueEmpty(channel.clone()),
+    }
+}
 
-        if let Some((too_few, too_many)) = non_match(&puzzle, &normalized_word) {
-            let reason = Reason::NonMatchingWord(puzzle.clone(), too_many, too_few);
-            return Response::IncorrectSolution(channel.clone(), word.clone(),
-                reason)
+/// Take the first puzzle in the queue that may still be set. Puzzles that were valid when they were
+/// queued may not be, if the dictionary or the puzzle length has changed since, and are dropped.
+fn pop_queue(state: &mut Niancat) -> Option<Puzzle> {
+    while !state.queue.is_empty() {
+        let puzzle = state.queue.remove(0);
+        state.changed = true;
+        if validate_puzzle(state, &puzzle).is_ok() {
+            return Some(puzzle);
         }
+    }
+    None
+}
+
+/// Add a puzzle last in the queue, if it may be set.
+fn queue_puzzle(state: &mut Niancat, channel: &Channel, puzzle: &Puzzle) -> Response {
+    if let Err(reason) = validate_puzzle(state, puzzle) {
+        return Response::InvalidPuzzle(channel.clone(), puzzle.clone(), reason);
+    }
+    state.queue.push(puzzle.clone());
+    state.changed = true;
+    Response::PuzzleQueued(channel.clone(), puzzle.clone(), state.queue.len())
+}
+
+/// Remove a puzzle from the queue, by its position as shown by `!kö`, starting at 1.
+fn remove_from_queue(state: &mut Niancat, channel: &Channel, entry: &String) -> Response {
+    match entry.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= state.queue.len() => {
+            state.changed = true;
+            Response::RemovedFromQueue(channel.clone(), state.queue.remove(n - 1))
+        },
+        _ => Response::NoSuchQueueEntry(channel.clone(), entry.clone()),
+    }
+}
+
+/// Set the daily puzzle that was due at `due`, unless a puzzle has been set since then. The next
+/// puzzle in the queue is used, or a generated one if the queue is empty. The puzzle is announced in
+/// the given channel.
+pub fn daily_puzzle(state: &mut Niancat, channel: &Channel, due: Timestamp, now: Timestamp) -> Option<Response> {
+    if state.history.current().map(|r| r.set_time >= due).unwrap_or(false) {
+        return None;
+    }
+
+    // If no puzzle can be generated, this is tried again the next day, instead of complaining.
+    let puzzle = match pop_queue(state) {
+        Some(puzzle) => Some(puzzle),
+        None => generator::generate(state.dictionary, state.puzzle_length, &state.generator, &state.history,
+                                    now, &mut rand::thread_rng()),
+    };
+    puzzle.map(|p| set_puzzle(state, channel, &p, now))
+}
+
+/// Remind the channel of the current puzzle, if nobody has solved it yet.
+pub fn remind(state: &Niancat) -> Option<Response> {
+    match state.puzzle {
+        Some(ref puzzle) if state.solutions.0.values().all(|solvers| solvers.is_empty()) =>
+            Some(Response::Reminder(puzzle.clone(), state.solutions.0.len())),
+        _ => None,
+    }
+}
+
+/// Summarize the previous week, once it has ended. Each week is only summarized once. When nothing
+/// has been summarized yet, the bot has just been installed, and summarizing starts with the next
+/// week that ends.
+pub fn weekly_summary(state: &mut Niancat, now: Timestamp) -> Option<Response> {
+    let previous_week = week(now) - 1;
+    let summarized = state.summarized_week.map(|w| w >= previous_week);
+    if summarized == Some(true) {
+        return None;
+    }
+
+    state.summarized_week = Some(previous_week);
+    state.changed = true;
+    match summarized {
+        None => None,
+        Some(_) => state.history.weekly_summary(previous_week).map(Response::WeeklySummary),
+    }
+}
+
+/// Publish the solutions and unsolutions of a puzzle set before `due`, and end it. As the puzzle has
+/// no solutions left, they are not published again when the next puzzle is set.
+pub fn publish_solutions(state: &mut Niancat, due: Timestamp) -> Option<Response> {
+    if state.puzzle.is_none() || state.history.current().map(|r| r.set_time >= due).unwrap_or(false) {
+        return None;
+    }
 
-        if state.dictionary.is_solution(&word) {
+    let mut responses = take_replaced(state);
+    let solve_times = state.history.current().map(solve_times).unwrap_or(HashMap::new());
+    responses.push(Response::SolutionsNotification(state.solutions.clone(), solve_times, state.hints.clone(),
+                                                   current_salt(state), guess_stats(state, &state.guesses)));
+    if !state.unsolutions.0.is_empty() {
+        responses.push(Response::UnsolutionsNotification(state.unsolutions.clone()));
+    }
+
+    state.changed = true;
+    state.puzzle = None;
+    state.solutions = SolutionsMap(HashMap::new());
+    state.unsolutions = UnsolutionsMap(HashMap::new());
+    state.hints = HashMap::new();
+    state.guesses = HashMap::new();
+    state.spoilers = vec![];
+    Some(Response::combine(responses))
+}
+
+/// Publish the solutions and unsolutions of the replaced puzzle, once it can no longer be restored.
+pub fn publish_replaced(state: &mut Niancat, now: Timestamp) -> Option<Response> {
+    match state.replaced {
+        Some(ref replaced) if now - replaced.replaced_at > UNDO_SECONDS => {},
+        _ => return None,
+    }
+
+    let responses = take_replaced(state);
+    if responses.is_empty() { None } else { Some(Response::combine(responses)) }
+}
+
+/// Forget the replaced puzzle, and return the responses that publish its solutions and unsolutions.
+/// They are only published if there are any. When the first puzzle after startup is replaced, there
+/// are no solutions.
+fn take_replaced(state: &mut Niancat) -> Vec<Response> {
+    let replaced = match state.replaced.take() {
+        Some(replaced) => replaced,
+        None => return vec![],
+    };
+    state.changed = true;
+
+    let record = if replaced.history_length > 0 { state.history.0.get(replaced.history_length - 1) } else { None };
+    let mut responses = vec![];
+    if !replaced.solutions.0.is_empty() {
+        let solve_times = record.map(solve_times).unwrap_or(HashMap::new());
+        let salt = record.map(|r| r.salt.clone()).unwrap_or(String::new());
+        responses.push(Response::SolutionsNotification(replaced.solutions, solve_times, replaced.hints, salt,
+                                                       guess_stats(state, &replaced.guesses)));
+    }
+    if !replaced.unsolutions.0.is_empty() {
+        responses.push(Response::UnsolutionsNotification(replaced.unsolutions));
+    }
+    responses
+}
+
+/// Mark the state as changed if wrong guesses have gone unsaved for `SAVE_GUESSES_SECONDS`.
+pub fn save_guesses(state: &mut Niancat, now: Timestamp) {
+    if state.unsaved_guesses_since.map(|since| now - since >= SAVE_GUESSES_SECONDS).unwrap_or(false) {
+        state.changed = true;
+    }
+}
+
+/// Check a guess, and remember it with the reason it was wrong, if it was.
+fn check_solution(state: &mut Niancat, channel: &Channel, name: &Name, word: &Word, now: Timestamp) -> Response {
+    let normalized_word = word.normalize();
+    let puzzle = match state.puzzle {
+        Some(ref puzzle) => puzzle.clone(),
+        None => return Response::NoPuzzleSet(channel.clone()),
+    };
+
+    let reason = if !is_right_length(&normalized_word.0, puzzle.length()) {
+        Some(Reason::WrongLength(puzzle.length()))
+    } else if let Some((too_few, too_many)) = non_match(&puzzle, &normalized_word) {
+        Some(Reason::NonMatchingWord(puzzle.clone(), too_many, too_few))
+    } else if state.dictionary.is_solution(&word) {
+        None
+    } else {
+        Some(not_in_dictionary_reason(state, &puzzle, &normalized_word))
+    };
+
+    state.guesses.entry(name.clone()).or_insert(vec![])
+        .push(Guess { word: normalized_word.clone(), reason: reason.clone(), time: now });
+
+    match reason {
+        Some(reason) => {
+            // Saved by `save_guesses` within a while, or with the next change.
+            state.unsaved_guesses_since = state.unsaved_guesses_since.or(Some(now));
+            Response::IncorrectSolution(channel.clone(), word.clone(), reason)
+        },
+        None => {
+            state.changed = true;
             let mut word_entry = state.solutions.0.entry(normalized_word.clone()).or_insert(vec![]);
             (*word_entry).push(name.0.clone());
 
-            let hash = solution_hash(&normalized_word, &name);
+            let mut solve_time = None;
+            if let Some(record) = state.history.current_mut() {
+                record.solves.push(Solve { name: name.clone(), word: normalized_word.clone(), time: now });
+                solve_time = Some(now - record.set_time);
+            }
+
+            let hash = solution_hash(&current_salt(state), &normalized_word, &name);
             let correct_solution = Response::CorrectSolution(channel.clone(),
                 word.clone());
-            let notification = Response::Notification(name.clone(), hash);
-            return Response::Dual(Box::new(correct_solution), Box::new(notification));
-        } else {
-            return Response::IncorrectSolution(channel.clone(), word.clone(),
-                Reason::NotInDictionary);
-        }
+            let hints = state.hints.get(name).cloned().unwrap_or(0);
+            let notification = Response::Notification(name.clone(), hash, solve_time, hints);
+            Response::Dual(Box::new(correct_solution), Box::new(notification))
+        },
+    }
+}
+
+fn get_guesses(state: &Niancat, channel: &Channel, name: &Name) -> Response {
+    if state.puzzle.is_none() {
+        return Response::NoPuzzleSet(channel.clone());
+    }
+    Response::Guesses(channel.clone(), state.guesses.get(name).cloned().unwrap_or(vec![]))
+}
+
+/// The stats of the guesses on a puzzle, if they are shown in the summary.
+fn guess_stats(state: &Niancat, guesses: &HashMap<Name, Vec<Guess>>) -> Option<GuessStats> {
+    if state.show_guess_stats { Some(GuessStats::new(guesses)) } else { None }
+}
+
+/// Find a word in a public message that gives away the current puzzle: a solution, or another word
+/// with the letters of the puzzle. Spoilers are logged in the state until the puzzle changes.
+pub fn check_spoiler(state: &mut Niancat, channel: &Channel, name: &Name, text: &str, now: Timestamp) -> Option<Word> {
+    let spoiler = match state.puzzle {
+        None => return None,
+        Some(ref puzzle) => text.split_whitespace()
+            .map(|part| Word(part.to_string()).normalize())
+            .find(|word| {
+                word.0 != puzzle.0 && is_right_length(&word.0, puzzle.length()) &&
+                    (non_match(puzzle, word).is_none() || state.solutions.0.contains_key(word))
+            }),
+    };
+
+    if let Some(ref word) = spoiler {
+        state.spoilers.push(Spoiler { name: name.clone(), channel: channel.clone(), word: word.clone(), time: now });
+        state.changed = true;
+    }
+    spoiler
+}
+
+/// Update the solutions of the current puzzle after the dictionary has changed. The solvers of words
+/// that are still solutions are kept. Returns a response only if the solutions changed.
+pub fn revalidate(state: &mut Niancat) -> Option<Response> {
+    let puzzle = match state.puzzle {
+        None => return None,
+        Some(ref puzzle) => puzzle.clone(),
+    };
+
+    let words = state.dictionary.find_solutions(&puzzle).unwrap_or(vec![]);
+    let unchanged = words.len() == state.solutions.0.len() &&
+        words.iter().all(|w| state.solutions.0.contains_key(w));
+    if unchanged {
+        return None;
+    }
+
+    let solutions = words.into_iter()
+        .map(|w| {
+            let solvers = state.solutions.0.get(&w).cloned().unwrap_or(vec![]);
+            (w, solvers)
+        })
+        .collect();
+    state.solutions = SolutionsMap(solutions);
+    state.changed = true;
+    Some(Response::PuzzleRevalidated(puzzle, state.solutions.0.len()))
+}
+
+/// Suggest words close to the guess, but never a solution to the puzzle, as that would spoil it.
+fn not_in_dictionary_reason(state: &Niancat, puzzle: &Puzzle, word: &Word) -> Reason {
+    let solutions = state.dictionary.find_solutions(puzzle).unwrap_or(vec![]);
+    let near_misses: Vec<Word> = state.dictionary.near_misses(word).into_iter()
+        .filter(|w| !solutions.contains(w))
+        .take(MAX_NEAR_MISSES)
+        .collect();
+
+    if near_misses.is_empty() {
+        Reason::NotInDictionary
     } else {
-        Response::NoPuzzleSet(channel.clone())
+        Reason::NearMisses(near_misses)
+    }
+}
+
+fn set_unsolution(state: &mut Niancat, channel: &Channel, name: &Name, text: &String) -> Response {
+    state.changed = true;
+    let unsolutions = state.unsolutions.0.entry(name.clone()).or_insert(vec![]);
+    unsolutions.push(text.clone());
+    Response::SetUnsolution(channel.clone(), text.clone())
+}
+
+/// The unsolutions of a user, which are only shown in private, so they don't spoil the puzzle. The
+/// handler sends the unsolutions asked for in a public channel to the user's private channel.
+fn get_unsolutions(state: &mut Niancat, channel: &Channel, name: &Name) -> Response {
+    if !channel.is_private() {
+        return Response::UnsolutionsPrivateOnly(channel.clone());
+    }
+    let unsolutions = state.unsolutions.0.get(name).cloned().unwrap_or(vec![]);
+    Response::GetUnsolutions(channel.clone(), unsolutions)
+}
+
+fn get_leaderboard(state: &mut Niancat, channel: &Channel, now: Timestamp) -> Response {
+    let this_month = month(now);
+    let all_time = state.history.leaderboard(|_| true);
+    let monthly = state.history.leaderboard(|r| month(r.set_time) == this_month);
+    Response::Leaderboard(channel.clone(), all_time, monthly)
+}
+
+fn get_user_stats(state: &mut Niancat, channel: &Channel, name: &Name) -> Response {
+    Response::UserStats(channel.clone(), name.clone(), state.history.user_stats(name))
+}
+
+/// Reveal one more letter of a solution to the puzzle. The same solution is used for all hints, so
+/// the letters add up.
+fn get_hint(state: &mut Niancat, channel: &Channel, name: &Name) -> Response {
+    let puzzle = match state.puzzle {
+        Some(ref puzzle) => puzzle.clone(),
+        None => return Response::NoPuzzleSet(channel.clone()),
+    };
+
+    // Hints after solving would only be counted against the solver in the summary.
+    if state.solutions.0.values().any(|solvers| solvers.contains(&name.0)) {
+        return Response::AlreadySolved(channel.clone());
+    }
+
+    let mut solutions = state.dictionary.find_solutions(&puzzle).unwrap_or(vec![]);
+    solutions.sort_by(|a, b| a.0.cmp(&b.0));
+    let solution = match solutions.first() {
+        Some(solution) => solution.clone(),
+        None => return Response::NoMoreHints(channel.clone()),
+    };
+
+    // Revealing every letter would just give away the solution.
+    let hints_used = state.hints.get(name).cloned().unwrap_or(0);
+    if hints_used + 1 >= solution.0.chars().count() {
+        return Response::NoMoreHints(channel.clone());
+    }
+
+    let hints_used = hints_used + 1;
+    state.hints.insert(name.clone(), hints_used);
+    state.changed = true;
+    let revealed = solution.0.chars().take(hints_used).collect();
+    Response::Hint(channel.clone(), revealed, hints_used)
+}
+
+fn set_role(state: &mut Niancat, channel: &Channel, name: &Name, role: &String) -> Response {
+    match Role::from_name(role) {
+        Some(role) if role < Role::Admin && state.roles.role(name) == Role::Admin =>
+            Response::ConfiguredAdmin(channel.clone(), name.clone()),
+        Some(role) => {
+            state.assigned_roles.insert(name.clone(), role);
+            state.changed = true;
+            Response::RoleSet(channel.clone(), name.clone(), role)
+        },
+        None => Response::UnknownRole(channel.clone(), role.clone()),
     }
 }
 
+/// List the users with another role than the default role, admins first.
+fn get_roles(state: &Niancat, channel: &Channel) -> Response {
+    let names: HashSet<&Name> = state.roles.users.keys().chain(state.assigned_roles.keys()).collect();
+    let mut roles: Vec<(Name, Role)> = names.into_iter()
+        .map(|name| (name.clone(), state.role(name)))
+        .filter(|&(_, role)| role != state.roles.default_role)
+        .collect();
+    roles.sort_by(|a, b| b.1.cmp(&a.1).then(a.0 .0.cmp(&b.0 .0)));
+    Response::Roles(channel.clone(), state.roles.default_role, roles)
+}
+
+fn solve_times(record: &PuzzleRecord) -> SolveTimes {
+    record.first_solves().iter().map(|s| (s.name.clone(), s.time - record.set_time)).collect()
+}
+
 fn help_command(channel: &Channel) -> Response {
     Response::Help(channel.clone())
 }
 
-pub fn solution_hash(&Word(ref s): &Word, &Name(ref nick): &Name) -> String {
+fn new_salt() -> String {
+    let mut rng = rand::thread_rng();
+    (0..SALT_BYTES).map(|_| format!("{:02x}", rng.gen::<u8>())).collect()
+}
+
+/// The salt of the current puzzle. It is kept secret until the solutions are published.
+fn current_salt(state: &Niancat) -> String {
+    state.history.current().map(|r| r.salt.clone()).unwrap_or(String::new())
+}
+
+/// Find the solve a published solution hash was made from. Hashes can only be verified once the
+/// salt of their puzzle is published with its solutions: when the puzzle is no longer current, and
+/// can no longer be restored.
+fn verify_hash(state: &Niancat, channel: &Channel, hash: &String) -> Response {
+    let hash = hash.to_lowercase();
+    let records = &state.history.0;
+    for (i, record) in records.iter().enumerate() {
+        let solve = record.solves.iter().find(|s| solution_hash(&record.salt, &s.word, &s.name) == hash);
+        if let Some(solve) = solve {
+            let is_current = i + 1 == records.len() && state.puzzle.is_some();
+            let is_replaced = state.replaced.as_ref().map(|r| i + 1 == r.history_length).unwrap_or(false);
+            if is_current || is_replaced {
+                return Response::HashNotYetVerifiable(channel.clone());
+            }
+            return Response::HashVerified(channel.clone(), record.puzzle.clone(), solve.name.clone(), solve.word.clone());
+        }
+    }
+    Response::UnknownHash(channel.clone(), hash)
+}
+
+/// The hash published when a user solves a puzzle. The salt keeps others from finding the solution
+/// by trying all words with the letters of the puzzle, until it is published.
+pub fn solution_hash(salt: &str, &Word(ref s): &Word, &Name(ref nick): &Name) -> String {
     let mut hasher = Sha256::new();
+    hasher.input_str(salt);
     hasher.input_str(s.as_str());
     hasher.input_str(nick.as_str());
     hasher.result_str()
@@ -176,6 +922,15 @@ mod tests {
     use std::collections::HashMap;
     use std::iter::FromIterator;
 
+    // 2016-09-19 12:00:00 UTC, a monday.
+    const NOW: Timestamp = 1474286400;
+    const DAY: Timestamp = 24 * 60 * 60;
+
+    /// The user who sets the puzzles.
+    fn setter() -> Name {
+        Name("setter".into())
+    }
+
     const HASH_TESTS: &'static [(&'static str, &'static str, &'static str)] = &[
         ("GALLTJUTA", "f00ale",   "f72e9a9523bbc72bf7366a58a04046408d2d88ea811afdc9a459d24e077fa71d"),
         ("GALLTJUTA", "erike",    "d8e7363cdad6303dd4c41cb2ad3e2c35759257ca8ac509107e4e9e9ff5741933"),
@@ -210,6 +965,7 @@ mod tests {
         no_of_solutions_v: usize,
         find_solutions_v: Option<Vec<Word>>,
         has_solution_v: bool,
+        near_misses_v: Option<Vec<Word>>,
     }
 
     impl CheckWord for FakeCheckWord {
@@ -217,24 +973,28 @@ mod tests {
         fn no_of_solutions(&self, _: &Puzzle) -> usize { self.no_of_solutions_v }
         fn find_solutions(&self, _: &Puzzle) -> Option<Vec<Word>> { self.find_solutions_v.clone() }
         fn has_solution(&self, _: &Puzzle) -> bool { self.has_solution_v }
+        fn near_misses(&self, _: &Word) -> Vec<Word> { self.near_misses_v.clone().unwrap_or(vec![]) }
+        fn puzzles(&self, _: usize) -> Vec<Puzzle> { vec![] }
     }
 
     static DEFAULT_CHECKWORD: FakeCheckWord = FakeCheckWord {
         is_solution_v: true,
         no_of_solutions_v: 1,
         find_solutions_v: None,
-        has_solution_v: true };
+        has_solution_v: true,
+        near_misses_v: None };
 
     static NOT_SOLUTION_CHECKWORD: FakeCheckWord = FakeCheckWord {
         is_solution_v: false,
         no_of_solutions_v: 0,
         find_solutions_v: None,
-        has_solution_v: false };
+        has_solution_v: false,
+        near_misses_v: None };
 
     #[test]
     fn solution_hash_test() {
         for &(word, nick, expected) in HASH_TESTS {
-            let actual = solution_hash(&Word(word.to_string()), &Name(nick.to_string()));
+            let actual = solution_hash("", &Word(word.to_string()), &Name(nick.to_string()));
             assert!(actual == expected, "Actual hash: {}, expected {}", actual, expected);
         }
     }
@@ -267,7 +1027,7 @@ mod tests {
         let p = Puzzle("ATORSPELD".into());
         let mut check_word = DEFAULT_CHECKWORD.clone();
         check_word.find_solutions_v = Some(vec![Word("ABCDEFGHI".into())]);
-        let mut state = Niancat::new(&check_word);
+        let mut state = Niancat::new_with_puzzle(&check_word, Puzzle("PELDATORS".into()));
         let expected_solutions = SolutionsMap(
             HashMap::from_iter(vec![
                         (Word("DATORSPEL".into()), vec!["foo".to_string(), "bar".to_string()]),
@@ -275,8 +1035,8 @@ mod tests {
                         ].into_iter()));
         state.solutions = expected_solutions.clone();
 
-        let set_command = Command::SetPuzzle(channel.clone(), p.clone());
-        let response = apply(&set_command, &mut state);
+        let set_command = Command::SetPuzzle(channel.clone(), setter(), p.clone());
+        let response = apply(&set_command, &mut state, NOW);
 
         let expected_solutions = SolutionsMap(
             HashMap::from_iter(vec![
@@ -284,13 +1044,16 @@ mod tests {
                         (Word("SPELDATOR".into()), vec![]),
                         ].into_iter()));
         let set_response = Response::SetPuzzle(channel.clone(), p.clone(), 1);
-        let notification_response = Response::SolutionsNotification(expected_solutions);
+        let notification_response = Response::SolutionsNotification(expected_solutions, HashMap::new(), HashMap::new(), "".into(), None);
 
-        assert_eq!(response, Response::Dual(
-                                Box::new(set_response),
-                                Box::new(notification_response)));
+        assert_eq!(response, set_response);
         assert_eq!(state.puzzle, Some(p));
 
+        // The solutions are published once the replaced puzzle can no longer be restored.
+        assert_eq!(publish_replaced(&mut state, NOW + UNDO_SECONDS), None);
+        assert_eq!(publish_replaced(&mut state, NOW + UNDO_SECONDS + 1), Some(notification_response));
+        assert_eq!(publish_replaced(&mut state, NOW + UNDO_SECONDS + 2), None);
+
         assert_eq!(state.solutions,
                    SolutionsMap(HashMap::from_iter(vec![
                         (Word("ABCDEFGHI".into()), vec![]),
@@ -310,7 +1073,7 @@ mod tests {
         state.solutions = expected_solutions.clone();
 
         let cmd = Command::CheckSolution(channel.clone(), Name("foo".into()), Word("DATORSPEL".into()));
-        let response = apply(&cmd, &mut state);
+        let response = apply(&cmd, &mut state, NOW);
         match response {
             Response::Dual(_, _) => {},
             r => assert!(false, "Expected a successful Dual response, but got {:?}", r),
@@ -324,10 +1087,10 @@ mod tests {
 
         // Users "foo" and "bar" both solve "SPELDATOR".
         let cmd = Command::CheckSolution(channel.clone(), Name("foo".into()), Word("SPELDATOR".into()));
-        apply(&cmd, &mut state);
+        apply(&cmd, &mut state, NOW);
 
         let cmd = Command::CheckSolution(channel.clone(), Name("bar".into()), Word("SPELDATOR".into()));
-        apply(&cmd, &mut state);
+        apply(&cmd, &mut state, NOW);
 
         assert_eq!(state.solutions.clone(),
                    SolutionsMap(HashMap::from_iter(vec![
@@ -341,39 +1104,805 @@ mod tests {
         let channel = Channel("channel".into());
         let p = Puzzle("ABCDEF".to_string());
         let mut state = Niancat::new(&NOT_SOLUTION_CHECKWORD);
-        let set_command = Command::SetPuzzle(channel.clone(), p.clone());
-        let response = apply(&set_command, &mut state);
+        let set_command = Command::SetPuzzle(channel.clone(), setter(), p.clone());
+        let response = apply(&set_command, &mut state, NOW);
 
-        assert!(response == Response::InvalidPuzzle(channel.clone(), p.clone(), InvalidPuzzleReason::NotNineCharacters),
+        assert!(response == Response::InvalidPuzzle(channel.clone(), p.clone(), InvalidPuzzleReason::WrongLength(9)),
                 "Actual response: {:?}", response);
         assert!(state.puzzle == None);
 
         let p = Puzzle("IHGFEDCBA".into());
-        let set_command = Command::SetPuzzle(channel.clone(), p.clone());
-        let response = apply(&set_command, &mut state);
+        let set_command = Command::SetPuzzle(channel.clone(), setter(), p.clone());
+        let response = apply(&set_command, &mut state, NOW);
 
         assert!(response == Response::InvalidPuzzle(channel.clone(), p.clone(), InvalidPuzzleReason::NotInDictionary));
         assert!(state.puzzle == None);
     }
 
+    #[test]
+    fn puzzle_length_test() {
+        let channel = Channel("channel".into());
+        let name = Name("erike".into());
+        let check_word = FakeCheckWord {
+            find_solutions_v: Some(vec![Word("ABCDEFGHIJ".into())]),
+            ..DEFAULT_CHECKWORD.clone()
+        };
+        let mut state = Niancat::new(&check_word);
+        state.set_puzzle_length(10);
+
+        let p = Puzzle("ABCDEFGHI".into());
+        let response = apply(&Command::SetPuzzle(channel.clone(), setter(), p.clone()), &mut state, NOW);
+        assert_eq!(response, Response::InvalidPuzzle(channel.clone(), p.clone(), InvalidPuzzleReason::WrongLength(10)));
+
+        let p = Puzzle("ABCDEFGHIJ".into());
+        apply(&Command::SetPuzzle(channel.clone(), setter(), p.clone()), &mut state, NOW);
+        assert_eq!(state.puzzle, Some(p));
+
+        let word = Word("ABCDEFGHI".into());
+        let response = apply(&Command::CheckSolution(channel.clone(), name.clone(), word.clone()), &mut state, NOW);
+        assert_eq!(response, Response::IncorrectSolution(channel.clone(), word.clone(), Reason::WrongLength(10)));
+
+        // A restored puzzle keeps its own length, even if new puzzles have another length.
+        state.set_puzzle_length(9);
+        let word = Word("JIHGFEDCBA".into());
+        let response = apply(&Command::CheckSolution(channel.clone(), name.clone(), word.clone()), &mut state, NOW);
+        assert!(response != Response::IncorrectSolution(channel.clone(), word.clone(), Reason::WrongLength(9)));
+    }
+
+    #[test]
+    fn generate_puzzle_test() {
+        let channel = Channel("channel".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+
+        match apply(&Command::GeneratePuzzle(channel.clone(), setter()), &mut state, NOW) {
+            Response::SetPuzzle(c, p, 1) => {
+                assert_eq!(c, channel);
+                assert!(dictionary.has_solution(&p));
+            },
+            response => assert!(false, "Unexpected response {:?}", response),
+        }
+
+        // The puzzle that was just set is only replaced after confirmation.
+        state.set_puzzle_length(10);
+        let response = apply(&Command::GeneratePuzzle(channel.clone(), setter()), &mut state, NOW + 60);
+        assert_eq!(response, Response::ConfirmGeneratePuzzle(channel.clone(), ConfirmReason::SetRecently(60)));
+        let response = apply(&Command::GeneratePuzzle(channel.clone(), setter()), &mut state, NOW + 60);
+        assert_eq!(response, Response::NoPuzzleToGenerate(channel.clone(), 10));
+    }
+
+    #[test]
+    fn daily_puzzle_test() {
+        let channel = Channel("channel".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+
+        assert!(daily_puzzle(&mut state, &channel, NOW - 60, NOW).is_some());
+        assert!(state.puzzle.is_some());
+
+        // Not again for the same day, but for the next day.
+        assert_eq!(daily_puzzle(&mut state, &channel, NOW - 60, NOW + 60), None);
+        assert!(daily_puzzle(&mut state, &channel, NOW + SECONDS_PER_DAY, NOW + SECONDS_PER_DAY).is_some());
+    }
+
+    #[test]
+    fn remind_test() {
+        let channel = Channel("channel".into());
+        let name = Name("erike".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        assert_eq!(remind(&state), None);
+
+        apply(&Command::SetPuzzle(channel.clone(), setter(), Puzzle("TAGALLTJU".into())), &mut state, NOW);
+        assert_eq!(remind(&state), Some(Response::Reminder(Puzzle("TAGALLTJU".into()), 1)));
+
+        apply(&Command::CheckSolution(channel.clone(), name.clone(), Word("GALLTJUTA".into())), &mut state, NOW);
+        assert_eq!(remind(&state), None);
+    }
+
+    #[test]
+    fn publish_solutions_test() {
+        let channel = Channel("channel".into());
+        let name = Name("erike".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        assert_eq!(publish_solutions(&mut state, NOW), None);
+
+        apply(&Command::SetPuzzle(channel.clone(), setter(), Puzzle("TAGALLTJU".into())), &mut state, NOW);
+        apply(&Command::CheckSolution(channel.clone(), name.clone(), Word("GALLTJUTA".into())), &mut state, NOW + 60);
+
+        // A puzzle set after the time is not published.
+        assert_eq!(publish_solutions(&mut state, NOW - 60), None);
+
+        match publish_solutions(&mut state, NOW + SECONDS_PER_DAY) {
+            Some(Response::SolutionsNotification(solutions, times, _, _, _)) => {
+                assert_eq!(solutions.0.get(&Word("GALLTJUTA".into())), Some(&vec!["erike".to_string()]));
+                assert_eq!(times.get(&name), Some(&60));
+            },
+            response => assert!(false, "Unexpected response {:?}", response),
+        }
+        assert_eq!(state.puzzle, None);
+
+        // The solutions are not published again with the next puzzle.
+        let response = apply(&Command::SetPuzzle(channel.clone(), setter(), Puzzle("AGALLTJUT".into())), &mut state, NOW + SECONDS_PER_DAY);
+        assert_eq!(response, Response::SetPuzzle(channel.clone(), Puzzle("AGALLTJUT".into()), 1));
+    }
+
+    #[test]
+    fn queue_test() {
+        let chan = Channel("D0".into());
+        let name = Name("erike".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        let p1 = Puzzle("TAGALLTJU".into());
+        let p2 = Puzzle("PELDATORS".into());
+
+        let response = apply(&Command::NextPuzzle(chan.clone(), setter()), &mut state, NOW);
+        assert_eq!(response, Response::QueueEmpty(chan.clone()));
+
+        // Puzzles are checked like when they are set.
+        let p = Puzzle("ABCDEFGHI".into());
+        let response = apply(&Command::QueuePuzzle(chan.clone(), name.clone(), p.clone()), &mut state, NOW);
+        assert_eq!(response, Response::InvalidPuzzle(chan.clone(), p.clone(), InvalidPuzzleReason::NotInDictionary));
+
+        for (i, p) in vec![p1.clone(), p2.clone(), p1.clone()].into_iter().enumerate() {
+            let response = apply(&Command::QueuePuzzle(chan.clone(), name.clone(), p.clone()), &mut state, NOW);
+            assert_eq!(response, Response::PuzzleQueued(chan.clone(), p, i + 1));
+        }
+
+        let response = apply(&Command::RemoveFromQueue(chan.clone(), name.clone(), "3".into()), &mut state, NOW);
+        assert_eq!(response, Response::RemovedFromQueue(chan.clone(), p1.clone()));
+        for entry in vec!["0", "3", "foo"] {
+            let response = apply(&Command::RemoveFromQueue(chan.clone(), name.clone(), entry.into()), &mut state, NOW);
+            assert_eq!(response, Response::NoSuchQueueEntry(chan.clone(), entry.into()));
+        }
+
+        let response = apply(&Command::GetQueue(chan.clone(), name.clone()), &mut state, NOW);
+        assert_eq!(response, Response::Queue(chan.clone(), vec![p1.clone(), p2.clone()]));
+
+        let response = apply(&Command::NextPuzzle(chan.clone(), setter()), &mut state, NOW);
+        assert_eq!(response, Response::SetPuzzle(chan.clone(), p1.clone(), 1));
+
+        // The puzzle that was just set is only replaced after confirmation.
+        let response = apply(&Command::NextPuzzle(chan.clone(), setter()), &mut state, NOW + 60);
+        assert_eq!(response, Response::ConfirmNextPuzzle(chan.clone(), ConfirmReason::SetRecently(60)));
+        assert_eq!(state.puzzle, Some(p1.clone()));
+
+        // The daily puzzle is taken from the queue.
+        assert!(daily_puzzle(&mut state, &chan, NOW + SECONDS_PER_DAY, NOW + SECONDS_PER_DAY).is_some());
+        assert_eq!(state.puzzle, Some(p2.clone()));
+        assert!(state.queue.is_empty());
+    }
+
+    #[test]
+    fn queue_invalidated_test() {
+        let chan = Channel("D0".into());
+        let name = Name("erike".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        apply(&Command::QueuePuzzle(chan.clone(), name.clone(), Puzzle("TAGALLTJU".into())), &mut state, NOW);
+
+        // A puzzle that is no longer valid is dropped from the queue.
+        state.set_puzzle_length(10);
+        let response = apply(&Command::NextPuzzle(chan.clone(), setter()), &mut state, NOW);
+        assert_eq!(response, Response::QueueEmpty(chan.clone()));
+        assert!(state.queue.is_empty());
+    }
+
+    #[test]
+    fn roles_test() {
+        let chan = Channel("C0".into());
+        let alice = Name("alice".into());
+        let bob = Name("bob".into());
+        let carol = Name("carol".into());
+        let mut roles = Roles::new(Role::Player);
+        roles.set(alice.clone(), Role::Admin);
+        roles.set(carol.clone(), Role::Setter);
+        let mut state = Niancat::new(&DEFAULT_CHECKWORD);
+        state.set_roles(roles);
+
+        assert_eq!(required_role(&Command::SetPuzzle(chan.clone(), setter(), Puzzle("ABCDEFGHI".into()))), Role::Setter);
+        assert_eq!(required_role(&Command::SetRole(chan.clone(), bob.clone(), "admin".into())), Role::Admin);
+        assert_eq!(required_role(&Command::GetPuzzle(chan.clone())), Role::Player);
+
+        let response = apply(&Command::SetRole(chan.clone(), bob.clone(), "king".into()), &mut state, NOW);
+        assert_eq!(response, Response::UnknownRole(chan.clone(), "king".into()));
+
+        let response = apply(&Command::SetRole(chan.clone(), bob.clone(), "setter".into()), &mut state, NOW);
+        assert_eq!(response, Response::RoleSet(chan.clone(), bob.clone(), Role::Setter));
+        assert_eq!(state.role(&bob), Role::Setter);
+
+        // Given roles take precedence over the configured roles, and are saved.
+        apply(&Command::SetRole(chan.clone(), carol.clone(), "player".into()), &mut state, NOW);
+        assert_eq!(state.role(&carol), Role::Player);
+        let response = apply(&Command::GetRoles(chan.clone()), &mut state, NOW);
+        assert_eq!(response, Response::Roles(chan.clone(), Role::Player,
+                                             vec![(alice.clone(), Role::Admin), (bob.clone(), Role::Setter)]));
+
+        // Admins in the config can't be given a lesser role.
+        let response = apply(&Command::SetRole(chan.clone(), alice.clone(), "player".into()), &mut state, NOW);
+        assert_eq!(response, Response::ConfiguredAdmin(chan.clone(), alice.clone()));
+        assert_eq!(state.role(&alice), Role::Admin);
+
+        let mut restored = Niancat::new(&DEFAULT_CHECKWORD);
+        restored.restore(&state.to_state());
+        assert_eq!(restored.role(&bob), Role::Setter);
+    }
+
+    #[test]
+    fn guesses_test() {
+        let chan = Channel("D0".into());
+        let foo = Name("foo".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        state.set_show_guess_stats(true);
+
+        let response = apply(&Command::GetGuesses(chan.clone(), foo.clone()), &mut state, NOW);
+        assert_eq!(response, Response::NoPuzzleSet(chan.clone()));
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), Puzzle("TAGALLTJU".into())), &mut state, NOW);
+        for guess in &["galltjuta", "GALLTJUT", "GALLTJUTE", "GALLTJUTA"] {
+            apply(&Command::CheckSolution(chan.clone(), foo.clone(), Word(guess.to_string())), &mut state, NOW + 1);
+        }
+        apply(&Command::CheckSolution(chan.clone(), Name("bar".into()), Word("GALLTJUTE".into())), &mut state, NOW + 2);
+
+        let guess = |word: &str, reason: Option<Reason>| Guess { word: Word(word.into()), reason: reason, time: NOW + 1 };
+        let response = apply(&Command::GetGuesses(chan.clone(), foo.clone()), &mut state, NOW + 3);
+        assert_eq!(response, Response::Guesses(chan.clone(), vec![
+            guess("GALLTJUTA", None),
+            guess("GALLTJUT", Some(Reason::WrongLength(9))),
+            guess("GALLTJUTE", Some(Reason::NonMatchingWord(Puzzle("TAGALLTJU".into()), "E".into(), "A".into()))),
+            guess("GALLTJUTA", None),
+        ]));
+
+        let mut restored = Niancat::new(&dictionary);
+        restored.restore(&state.to_state());
+        assert_eq!(restored.guesses, state.guesses);
+
+        // The guesses are forgotten when the puzzle is replaced, and restored with it.
+        apply(&Command::SetPuzzle(chan.clone(), setter(), Puzzle("PELDATORS".into())), &mut state, NOW + DAY);
+        let response = apply(&Command::GetGuesses(chan.clone(), foo.clone()), &mut state, NOW + DAY);
+        assert_eq!(response, Response::Guesses(chan.clone(), vec![]));
+        apply(&Command::Undo(chan.clone()), &mut state, NOW + DAY + 60);
+        assert_eq!(state.guesses, restored.guesses);
+
+        // The stats are in the summary when the solutions are published.
+        apply(&Command::SetPuzzle(chan.clone(), setter(), Puzzle("PELDATORS".into())), &mut state, NOW + DAY + 120);
+        match publish_replaced(&mut state, NOW + DAY + 120 + UNDO_SECONDS + 1) {
+            Some(Response::SolutionsNotification(_, _, _, _, ref stats)) => {
+                assert_eq!(stats, &Some(GuessStats {
+                    guesses: 5,
+                    most_common_wrong: Some((Word("GALLTJUTE".into()), 2)),
+                }));
+            },
+            r => panic!("Expected the solutions, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn check_spoiler_test() {
+        let chan = Channel("C0".into());
+        let foo = Name("foo".into());
+        let mut state = Niancat::new(&DEFAULT_CHECKWORD);
+        assert_eq!(check_spoiler(&mut state, &chan, &foo, "DATORSPEL", NOW), None);
+
+        state.puzzle = Some(Puzzle("DATORSPLE".into()));
+        assert_eq!(check_spoiler(&mut state, &chan, &foo, "Dagens nia är DATORSPLE", NOW), None);
+        assert_eq!(check_spoiler(&mut state, &chan, &foo, "Jag tror inte det är DATORSPET", NOW), None);
+        assert_eq!(check_spoiler(&mut state, &chan, &foo, "Är det *datorspel*?", NOW),
+                   Some(Word("DATORSPEL".into())));
+        assert_eq!(check_spoiler(&mut state, &chan, &foo, "SPELDATRO, kanske", NOW + 10),
+                   Some(Word("SPELDATRO".into())));
+
+        let mut restored = Niancat::new(&DEFAULT_CHECKWORD);
+        restored.restore(&state.to_state());
+        assert_eq!(restored.spoilers, vec![
+            Spoiler { name: foo.clone(), channel: chan.clone(), word: Word("DATORSPEL".into()), time: NOW },
+            Spoiler { name: foo.clone(), channel: chan.clone(), word: Word("SPELDATRO".into()), time: NOW + 10 },
+        ]);
+
+        // The spoilers are forgotten when the puzzle ends.
+        publish_solutions(&mut state, NOW + DAY);
+        assert!(state.spoilers.is_empty());
+    }
+
+    #[test]
+    fn handler_commands_test() {
+        let chan = Channel("D0".into());
+        let foo = Name("foo".into());
+        let mut state = Niancat::new_with_puzzle(&DEFAULT_CHECKWORD, Puzzle("DATORSPLE".into()));
+
+        // The commands that the handler runs itself do nothing to a game.
+        let commands = vec![
+            Command::CheckSpoiler(chan.clone(), foo.clone(), "DATORSPEL".into()),
+            Command::SelectGame(chan.clone(), foo.clone(), "general".into()),
+            Command::ReloadDictionary(chan.clone(), foo.clone()),
+            Command::AllowWord(chan.clone(), foo.clone(), Word("DATORSPEL".into())),
+            Command::DenyWord(chan.clone(), foo.clone(), Word("DATORSPEL".into())),
+            Command::SetLanguage(chan.clone(), foo.clone(), "en".into()),
+        ];
+        for command in &commands {
+            assert_eq!(apply(command, &mut state, NOW), Response::Multiple(vec![]), "{:?}", command);
+        }
+        assert!(state.spoilers.is_empty());
+        assert!(!state.take_changed());
+    }
+
+    #[test]
+    fn admins_test() {
+        let mut state = Niancat::new(&DEFAULT_CHECKWORD);
+        let mut roles = Roles::default();
+        roles.set(Name("foo".into()), Role::Admin);
+        roles.set(Name("bar".into()), Role::Admin);
+        state.set_roles(roles);
+        state.assigned_roles.insert(Name("bar".into()), Role::Player);
+        state.assigned_roles.insert(Name("baz".into()), Role::Admin);
+
+        assert_eq!(state.admins(), vec![Name("baz".into()), Name("foo".into())]);
+    }
+
+    #[test]
+    fn confirm_set_puzzle_test() {
+        let chan = Channel("C0".into());
+        let name = Name("foo".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        let p1 = Puzzle("TAGALLTJU".into());
+        let p2 = Puzzle("PELDATORS".into());
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p1.clone()), &mut state, NOW);
+        let response = apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + 60);
+        assert_eq!(response, Response::ConfirmSetPuzzle(chan.clone(), p2.clone(), ConfirmReason::SetRecently(60)));
+        assert_eq!(state.puzzle, Some(p1.clone()));
+
+        // Each setter confirms their own replacement.
+        let response = apply(&Command::SetPuzzle(chan.clone(), name.clone(), p2.clone()), &mut state, NOW + 60);
+        assert_eq!(response, Response::ConfirmSetPuzzle(chan.clone(), p2.clone(), ConfirmReason::SetRecently(60)));
+        assert_eq!(state.puzzle, Some(p1.clone()));
+
+        // Invalid puzzles are refused without a confirmation.
+        let p = Puzzle("ABCDEFGHI".into());
+        let response = apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW + 60);
+        assert_eq!(response, Response::InvalidPuzzle(chan.clone(), p.clone(), InvalidPuzzleReason::NotInDictionary));
+
+        // The confirmation is the same puzzle again, in time.
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + 60);
+        let response = apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + 60 + CONFIRM_SECONDS + 1);
+        assert_eq!(response, Response::ConfirmSetPuzzle(chan.clone(), p2.clone(),
+                                                        ConfirmReason::SetRecently(60 + CONFIRM_SECONDS + 1)));
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + 120 + CONFIRM_SECONDS);
+        assert_eq!(state.puzzle, Some(p2.clone()));
+
+        // A puzzle that is solved, and not set recently, is replaced right away.
+        apply(&Command::CheckSolution(chan.clone(), name.clone(), Word("DATORSPEL".into())), &mut state, NOW + DAY);
+        match apply(&Command::SetPuzzle(chan.clone(), setter(), p1.clone()), &mut state, NOW + DAY) {
+            Response::Dual(ref set, _) => assert_eq!(**set, Response::SetPuzzle(chan.clone(), p1.clone(), 1)),
+            r => assert!(false, "Expected set puzzle and solutions, but got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn undo_test() {
+        let chan = Channel("C0".into());
+        let name = Name("foo".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        let p1 = Puzzle("TAGALLTJU".into());
+        let p2 = Puzzle("PELDATORS".into());
+
+        assert_eq!(apply(&Command::Undo(chan.clone()), &mut state, NOW), Response::NothingToUndo(chan.clone()));
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p1.clone()), &mut state, NOW);
+        apply(&Command::CheckSolution(chan.clone(), name.clone(), Word("GALLTJUTA".into())), &mut state, NOW + 60);
+        apply(&Command::SetUnsolution(chan.clone(), name.clone(), "FOO".into()), &mut state, NOW + 60);
+        let before = state.to_state();
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + DAY);
+        assert_eq!(state.puzzle, Some(p2.clone()));
+
+        // The old puzzle is restored with its solvers, and the new one is gone from the history.
+        let response = apply(&Command::Undo(chan.clone()), &mut state, NOW + DAY + 60);
+        assert_eq!(response, Response::PuzzleRestored(chan.clone(), p1.clone(), 1));
+        assert_eq!(state.to_state(), before);
+        assert_eq!(apply(&Command::Undo(chan.clone()), &mut state, NOW + DAY + 60), Response::NothingToUndo(chan.clone()));
+
+        // Only for a while.
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + DAY);
+        let response = apply(&Command::Undo(chan.clone()), &mut state, NOW + DAY + UNDO_SECONDS + 1);
+        assert_eq!(response, Response::NothingToUndo(chan.clone()));
+        assert_eq!(state.puzzle, Some(p2.clone()));
+    }
+
+    #[test]
+    fn verify_hash_test() {
+        let chan = Channel("C0".into());
+        let name = Name("foo".into());
+        let word = Word("GALLTJUTA".into());
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        let p = Puzzle("TAGALLTJU".into());
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW);
+        let salt = state.history.current().unwrap().salt.clone();
+        let hash = solution_hash(&salt, &word, &name);
+        assert!(hash != solution_hash("", &word, &name));
+        apply(&Command::CheckSolution(chan.clone(), name.clone(), word.clone()), &mut state, NOW + 60);
+
+        // The hash can not be verified while the puzzle is current.
+        let response = apply(&Command::VerifyHash(chan.clone(), hash.clone()), &mut state, NOW + 120);
+        assert_eq!(response, Response::HashNotYetVerifiable(chan.clone()));
+
+        // Nor while the puzzle can be restored, as its salt is not published yet. Restoring it keeps
+        // the salt.
+        let p2 = Puzzle("PELDATORS".into());
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + DAY);
+        assert!(state.history.current().unwrap().salt != salt);
+        let response = apply(&Command::VerifyHash(chan.clone(), hash.clone()), &mut state, NOW + DAY);
+        assert_eq!(response, Response::HashNotYetVerifiable(chan.clone()));
+        apply(&Command::Undo(chan.clone()), &mut state, NOW + DAY + 60);
+        assert_eq!(state.history.current().unwrap().salt, salt);
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p2.clone()), &mut state, NOW + DAY + 120);
+        match publish_replaced(&mut state, NOW + DAY + 120 + UNDO_SECONDS + 1) {
+            Some(Response::SolutionsNotification(_, _, _, ref published_salt, _)) => assert_eq!(published_salt, &salt),
+            r => panic!("Expected the solutions, got {:?}", r),
+        }
+        let response = apply(&Command::VerifyHash(chan.clone(), hash.to_uppercase()), &mut state, NOW + DAY);
+        assert_eq!(response, Response::HashVerified(chan.clone(), p.clone(), name.clone(), word.clone()));
+
+        let response = apply(&Command::VerifyHash(chan.clone(), "abc".into()), &mut state, NOW + DAY);
+        assert_eq!(response, Response::UnknownHash(chan.clone(), "abc".into()));
+    }
+
     #[test]
     fn set_puzzle_multiple_solutions() {
         let check_word: FakeCheckWord = FakeCheckWord {
             is_solution_v: true,
             no_of_solutions_v: 7,
             find_solutions_v: Some(vec![Word("ABCDEFGHI".into())]),
-            has_solution_v: true };
+            has_solution_v: true,
+            near_misses_v: None };
 
         let channel = Channel("channel".into());
         let p = Puzzle("ABCDEFGHI".to_string());
         let mut state = Niancat::new(&check_word);
-        let set_command = Command::SetPuzzle(channel.clone(), p.clone());
-        let response = apply(&set_command, &mut state);
+        let set_command = Command::SetPuzzle(channel.clone(), setter(), p.clone());
+        let response = apply(&set_command, &mut state, NOW);
 
         assert_eq!(response, Response::SetPuzzle(channel.clone(), p.clone(), check_word.no_of_solutions_v));
         assert_eq!(state.puzzle, Some(p));
     }
 
+    #[test]
+    fn unsolutions_test() {
+        let mut check_word = DEFAULT_CHECKWORD.clone();
+        check_word.find_solutions_v = Some(vec![Word("ABCDEFGHI".into())]);
+        let chan = Channel("D0".into());
+        let name = Name("foo".into());
+        let mut state = Niancat::new_with_puzzle(&check_word, Puzzle("ABCDEFGHI".into()));
+
+        let cmd = Command::SetUnsolution(chan.clone(), name.clone(), "FOO BAR".into());
+        assert_eq!(apply(&cmd, &mut state, NOW), Response::SetUnsolution(chan.clone(), "FOO BAR".into()));
+        let cmd = Command::SetUnsolution(chan.clone(), name.clone(), "BAZ".into());
+        apply(&cmd, &mut state, NOW);
+
+        let cmd = Command::GetUnsolutions(chan.clone(), name.clone());
+        assert_eq!(apply(&cmd, &mut state, NOW),
+                   Response::GetUnsolutions(chan.clone(), vec!["FOO BAR".into(), "BAZ".into()]));
+
+        let cmd = Command::GetUnsolutions(chan.clone(), Name("bar".into()));
+        assert_eq!(apply(&cmd, &mut state, NOW), Response::GetUnsolutions(chan.clone(), vec![]));
+
+        let public = Channel("C0".into());
+        let cmd = Command::GetUnsolutions(public.clone(), name.clone());
+        assert_eq!(apply(&cmd, &mut state, NOW), Response::UnsolutionsPrivateOnly(public.clone()));
+
+        // Setting the next puzzle clears the unsolutions, and they are published once the old puzzle
+        // can no longer be restored.
+        let p = Puzzle("IHGFEDCBA".into());
+        let cmd = Command::SetPuzzle(chan.clone(), setter(), p.clone());
+        let expected_unsolutions = UnsolutionsMap(HashMap::from_iter(vec![
+            (name.clone(), vec!["FOO BAR".to_string(), "BAZ".to_string()]),
+        ].into_iter()));
+        // Nobody has solved the puzzle, so replacing it is confirmed by setting it again.
+        assert_eq!(apply(&cmd, &mut state, NOW),
+                   Response::ConfirmSetPuzzle(chan.clone(), p.clone(), ConfirmReason::NoSolvers));
+        assert_eq!(apply(&cmd, &mut state, NOW), Response::SetPuzzle(chan.clone(), p.clone(), 1));
+        assert_eq!(state.unsolutions, UnsolutionsMap(HashMap::new()));
+        assert_eq!(publish_replaced(&mut state, NOW + UNDO_SECONDS + 1),
+                   Some(Response::UnsolutionsNotification(expected_unsolutions)));
+    }
+
+    #[test]
+    fn set_puzzle_with_solutions_and_unsolutions_test() {
+        let mut check_word = DEFAULT_CHECKWORD.clone();
+        check_word.find_solutions_v = Some(vec![Word("ABCDEFGHI".into())]);
+        let chan = Channel("C0".into());
+        let mut state = Niancat::new_with_puzzle(&check_word, Puzzle("ABCDEFGHI".into()));
+        let solutions = SolutionsMap(HashMap::from_iter(vec![
+            (Word("ABCDEFGHI".into()), vec!["foo".to_string()]),
+        ].into_iter()));
+        let unsolutions = UnsolutionsMap(HashMap::from_iter(vec![
+            (Name("foo".into()), vec!["BAZ".to_string()]),
+        ].into_iter()));
+        state.solutions = solutions.clone();
+        state.unsolutions = unsolutions.clone();
+
+        let p = Puzzle("IHGFEDCBA".into());
+        let response = apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW);
+        assert_eq!(response, Response::SetPuzzle(chan.clone(), p.clone(), 1));
+
+        // Replacing the new puzzle too publishes the solutions of the first one right away, as it can
+        // no longer be restored.
+        let set_command = Command::SetPuzzle(chan.clone(), setter(), Puzzle("ABCDEFGHI".into()));
+        apply(&set_command, &mut state, NOW + 60);
+        let response = apply(&set_command, &mut state, NOW + 60);
+        assert_eq!(response, Response::Multiple(vec![
+            Response::SetPuzzle(chan.clone(), Puzzle("ABCDEFGHI".into()), 1),
+            Response::SolutionsNotification(solutions, HashMap::new(), HashMap::new(), "".into(), None),
+            Response::UnsolutionsNotification(unsolutions)]));
+    }
+
+    #[test]
+    fn state_roundtrip_test() {
+        let chan = Channel("D0".into());
+        let mut state = Niancat::new_with_puzzle(&DEFAULT_CHECKWORD, Puzzle("AGALLTJUT".into()));
+        apply(&Command::CheckSolution(chan.clone(), Name("foo".into()), Word("GALLTJUTA".into())), &mut state, NOW);
+        apply(&Command::SetUnsolution(chan.clone(), Name("bar".into()), "FOO BAR".into()), &mut state, NOW);
+        state.hints.insert(Name("bar".into()), 3);
+        state.queue.push(Puzzle("PELDATORS".into()));
+
+        let snapshot = state.to_state();
+        let mut restored = Niancat::new(&DEFAULT_CHECKWORD);
+        restored.restore(&snapshot);
+
+        assert_eq!(restored.puzzle, state.puzzle);
+        assert_eq!(restored.solutions, state.solutions);
+        assert_eq!(restored.unsolutions, state.unsolutions);
+        assert_eq!(restored.hints, state.hints);
+        assert_eq!(restored.queue, state.queue);
+        assert_eq!(restored.to_state(), snapshot);
+    }
+
+    #[test]
+    fn history_test() {
+        let mut check_word = DEFAULT_CHECKWORD.clone();
+        check_word.find_solutions_v = Some(vec![Word("GALLTJUTA".into())]);
+        let chan = Channel("D0".into());
+        let p = Puzzle("AGALLTJUT".into());
+        let mut state = Niancat::new(&check_word);
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW);
+        apply(&Command::CheckSolution(chan.clone(), Name("foo".into()), Word("GALLTJUTA".into())), &mut state, NOW + 10);
+        apply(&Command::CheckSolution(chan.clone(), Name("bar".into()), Word("GALLTJUTA".into())), &mut state, NOW + 20);
+
+        let salt = state.history.0[0].salt.clone();
+        assert_eq!(salt.len(), 32);
+        assert_eq!(state.history, History(vec![PuzzleRecord {
+            puzzle: p.clone(),
+            set_time: NOW,
+            solves: vec![
+                Solve { name: Name("foo".into()), word: Word("GALLTJUTA".into()), time: NOW + 10 },
+                Solve { name: Name("bar".into()), word: Word("GALLTJUTA".into()), time: NOW + 20 },
+            ],
+            salt: salt,
+        }]));
+
+        let response = apply(&Command::GetLeaderboard(chan.clone()), &mut state, NOW + 30);
+        let expected_leaderboard = vec![(Name("bar".into()), 1), (Name("foo".into()), 1)];
+        assert_eq!(response, Response::Leaderboard(chan.clone(), expected_leaderboard.clone(), expected_leaderboard.clone()));
+
+        // A month later, the monthly leaderboard is empty.
+        let response = apply(&Command::GetLeaderboard(chan.clone()), &mut state, NOW + 31 * DAY);
+        assert_eq!(response, Response::Leaderboard(chan.clone(), expected_leaderboard.clone(), vec![]));
+
+        let response = apply(&Command::GetUserStats(chan.clone(), Name("foo".into())), &mut state, NOW + 30);
+        assert_eq!(response, Response::UserStats(chan.clone(), Name("foo".into()), UserStats {
+            solved: 1,
+            average_solve_time: Some(10),
+            current_streak: 1,
+            longest_streak: 1,
+        }));
+    }
+
+    #[test]
+    fn solve_time_test() {
+        let mut check_word = DEFAULT_CHECKWORD.clone();
+        check_word.find_solutions_v = Some(vec![Word("GALLTJUTA".into())]);
+        let chan = Channel("D0".into());
+        let p = Puzzle("AGALLTJUT".into());
+        let mut state = Niancat::new(&check_word);
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW);
+        let response = apply(&Command::CheckSolution(chan.clone(), Name("foo".into()), Word("GALLTJUTA".into())),
+                             &mut state, NOW + 12 * 60);
+        let salt = state.history.current().unwrap().salt.clone();
+        let expected_hash = solution_hash(&salt, &Word("GALLTJUTA".into()), &Name("foo".into()));
+        assert_eq!(response, Response::Dual(
+            Box::new(Response::CorrectSolution(chan.clone(), Word("GALLTJUTA".into()))),
+            Box::new(Response::Notification(Name("foo".into()), expected_hash, Some(12 * 60), 0))));
+
+        apply(&Command::CheckSolution(chan.clone(), Name("bar".into()), Word("GALLTJUTA".into())), &mut state, NOW + 60);
+
+        let response = apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW + DAY);
+        assert_eq!(response, Response::SetPuzzle(chan.clone(), p.clone(), 1));
+        assert_eq!(publish_replaced(&mut state, NOW + DAY + UNDO_SECONDS + 1), Some(Response::SolutionsNotification(
+            SolutionsMap(HashMap::from_iter(vec![
+                (Word("GALLTJUTA".into()), vec!["foo".to_string(), "bar".to_string()]),
+            ].into_iter())),
+            HashMap::from_iter(vec![
+                (Name("foo".into()), 12 * 60),
+                (Name("bar".into()), 60),
+            ].into_iter()),
+            HashMap::new(),
+            salt,
+            None)));
+    }
+
+    #[test]
+    fn revalidate_test() {
+        let check_word = FakeCheckWord {
+            find_solutions_v: Some(vec![Word("DATORSPEL".into()), Word("SPELDATOR".into())]),
+            ..DEFAULT_CHECKWORD.clone()
+        };
+        let mut state = Niancat::new_with_puzzle(&check_word, Puzzle("PELDATORS".into()));
+        state.solutions = SolutionsMap(HashMap::from_iter(vec![
+            (Word("DATORSPEL".into()), vec!["foo".to_string()]),
+            (Word("LEDSPORTA".into()), vec!["bar".to_string()]),
+        ].into_iter()));
+
+        // Words no longer in the dictionary are removed, but the solvers of the others are kept.
+        let response = revalidate(&mut state);
+        assert_eq!(response, Some(Response::PuzzleRevalidated(Puzzle("PELDATORS".into()), 2)));
+        assert_eq!(state.solutions, SolutionsMap(HashMap::from_iter(vec![
+            (Word("DATORSPEL".into()), vec!["foo".to_string()]),
+            (Word("SPELDATOR".into()), vec![]),
+        ].into_iter())));
+
+        assert_eq!(revalidate(&mut state), None);
+        assert_eq!(revalidate(&mut Niancat::new(&check_word)), None);
+    }
+
+    #[test]
+    fn hint_test() {
+        let mut check_word = DEFAULT_CHECKWORD.clone();
+        check_word.find_solutions_v = Some(vec![Word("SPELDATOR".into()), Word("DATORSPEL".into())]);
+        let chan = Channel("D0".into());
+        let foo = Name("foo".into());
+        let p = Puzzle("ATORSPELD".into());
+        let mut state = Niancat::new(&check_word);
+
+        let response = apply(&Command::GetHint(chan.clone(), foo.clone()), &mut state, NOW);
+        assert_eq!(response, Response::NoPuzzleSet(chan.clone()));
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW);
+
+        // The hints are taken from the first solution in alphabetical order.
+        let response = apply(&Command::GetHint(chan.clone(), foo.clone()), &mut state, NOW);
+        assert_eq!(response, Response::Hint(chan.clone(), "D".into(), 1));
+        let response = apply(&Command::GetHint(chan.clone(), foo.clone()), &mut state, NOW);
+        assert_eq!(response, Response::Hint(chan.clone(), "DA".into(), 2));
+
+        // Hints are counted for each user.
+        let response = apply(&Command::GetHint(chan.clone(), Name("bar".into())), &mut state, NOW);
+        assert_eq!(response, Response::Hint(chan.clone(), "D".into(), 1));
+
+        for _ in 0..6 {
+            apply(&Command::GetHint(chan.clone(), foo.clone()), &mut state, NOW);
+        }
+        let response = apply(&Command::GetHint(chan.clone(), foo.clone()), &mut state, NOW);
+        assert_eq!(response, Response::NoMoreHints(chan.clone()));
+
+        let response = apply(&Command::CheckSolution(chan.clone(), foo.clone(), Word("DATORSPEL".into())), &mut state, NOW);
+        let expected_hash = solution_hash(&state.history.current().unwrap().salt, &Word("DATORSPEL".into()), &foo);
+        assert_eq!(response, Response::Dual(
+            Box::new(Response::CorrectSolution(chan.clone(), Word("DATORSPEL".into()))),
+            Box::new(Response::Notification(foo.clone(), expected_hash, Some(0), 8))));
+
+        // Hints are not given, or counted, after solving.
+        let response = apply(&Command::GetHint(chan.clone(), foo.clone()), &mut state, NOW);
+        assert_eq!(response, Response::AlreadySolved(chan.clone()));
+        assert_eq!(state.hints.get(&foo), Some(&8));
+
+        // The hints are included in the summary, and reset for the next puzzle.
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW + DAY);
+        assert!(state.hints.is_empty());
+        match publish_replaced(&mut state, NOW + DAY + UNDO_SECONDS + 1) {
+            Some(Response::SolutionsNotification(_, _, ref hints, _, _)) => {
+                assert_eq!(hints, &HashMap::from_iter(vec![(foo.clone(), 8), (Name("bar".into()), 1)].into_iter()));
+            },
+            r => assert!(false, "Expected a solutions notification, but got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn weekly_summary_test() {
+        let mut check_word = DEFAULT_CHECKWORD.clone();
+        check_word.find_solutions_v = Some(vec![Word("GALLTJUTA".into())]);
+        let chan = Channel("C0".into());
+        let p = Puzzle("AGALLTJUT".into());
+        let mut state = Niancat::new(&check_word);
+
+        // A new bot doesn't summarize the week it was started in.
+        assert_eq!(weekly_summary(&mut state, NOW), None);
+
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW);
+        apply(&Command::CheckSolution(chan.clone(), Name("foo".into()), Word("GALLTJUTA".into())), &mut state, NOW + 10);
+        apply(&Command::SetPuzzle(chan.clone(), setter(), p.clone()), &mut state, NOW + DAY);
+
+        // The week is summarized once it has ended, even if no new puzzle is set, and only once.
+        assert_eq!(weekly_summary(&mut state, NOW + DAY), None);
+        assert_eq!(weekly_summary(&mut state, NOW + 7 * DAY), Some(Response::WeeklySummary(WeeklySummary {
+            puzzles: 2,
+            leaderboard: vec![(Name("foo".into()), 1)],
+        })));
+        assert_eq!(weekly_summary(&mut state, NOW + 7 * DAY + 60), None);
+
+        // Weeks without puzzles are not summarized.
+        assert_eq!(weekly_summary(&mut state, NOW + 14 * DAY), None);
+    }
+
+    #[test]
+    fn save_guesses_test() {
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+        let mut state = Niancat::new_with_puzzle(&dictionary, Puzzle("TAGALLTJU".into()));
+        let chan = Channel("D0".into());
+        let foo = Name("foo".into());
+
+        // Wrong guesses are saved a while after the first of them, not for each guess.
+        apply(&Command::CheckSolution(chan.clone(), foo.clone(), Word("GALLTJUAT".into())), &mut state, NOW);
+        apply(&Command::CheckSolution(chan.clone(), foo.clone(), Word("GALLTJUTE".into())), &mut state, NOW + 30);
+        assert!(!state.take_changed());
+        save_guesses(&mut state, NOW + SAVE_GUESSES_SECONDS - 1);
+        assert!(!state.take_changed());
+        save_guesses(&mut state, NOW + SAVE_GUESSES_SECONDS);
+        assert!(state.take_changed());
+        save_guesses(&mut state, NOW + 2 * SAVE_GUESSES_SECONDS);
+        assert!(!state.take_changed());
+
+        // Other changes save them right away.
+        apply(&Command::CheckSolution(chan.clone(), foo.clone(), Word("GALLTJUAT".into())), &mut state, NOW + DAY);
+        apply(&Command::CheckSolution(chan.clone(), foo.clone(), Word("GALLTJUTA".into())), &mut state, NOW + DAY);
+        assert!(state.take_changed());
+        save_guesses(&mut state, NOW + 2 * DAY);
+        assert!(!state.take_changed());
+    }
+
+    #[test]
+    fn changed_test() {
+        let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string(), "DATORSPEL".to_string()].into_iter());
+        let mut state = Niancat::new(&dictionary);
+        let chan = Channel("D0".into());
+        let foo = Name("foo".into());
+        let bar = Name("bar".into());
+
+        // The state is marked as changed exactly when its snapshot changes, so it is saved when needed.
+        // Wrong guesses are left to `save_guesses`.
+        let commands = vec![
+            Command::GetPuzzle(chan.clone()),
+            Command::SetPuzzle(chan.clone(), setter(), Puzzle("TAGALLTJU".into())),
+            Command::GetPuzzle(chan.clone()),
+            Command::CheckSolution(chan.clone(), foo.clone(), Word("GALLTJUTA".into())),
+            Command::GetLeaderboard(chan.clone()),
+            Command::SetUnsolution(chan.clone(), foo.clone(), "FOO".into()),
+            Command::GetUnsolutions(chan.clone(), foo.clone()),
+            Command::GetHint(chan.clone(), bar.clone()),
+            Command::SetRole(chan.clone(), bar.clone(), "setter".into()),
+            Command::GetRoles(chan.clone()),
+            Command::QueuePuzzle(chan.clone(), foo.clone(), Puzzle("PELDATORS".into())),
+            Command::GetQueue(chan.clone(), foo.clone()),
+            Command::RemoveFromQueue(chan.clone(), foo.clone(), "1".into()),
+            Command::SetPuzzle(chan.clone(), setter(), Puzzle("PELDATORS".into())),
+            Command::Undo(chan.clone()),
+            Command::Help(chan.clone()),
+        ];
+        for command in &commands {
+            let before = state.to_state();
+            apply(command, &mut state, NOW);
+            assert_eq!(state.take_changed(), state.to_state() != before, "{:?}", command);
+        }
+    }
+
     //
     // This is a simplified list of tests where we only test the response, not the new state.
     //
@@ -401,7 +1930,15 @@ mod tests {
             is_solution_v: true,
             no_of_solutions_v: 7,
             find_solutions_v: None,
-            has_solution_v: true };
+            has_solution_v: true,
+            near_misses_v: None };
+
+        let near_misses_checkword: FakeCheckWord = FakeCheckWord {
+            is_solution_v: false,
+            no_of_solutions_v: 1,
+            find_solutions_v: Some(vec![Word("ABCDEFGHI".into())]),
+            has_solution_v: true,
+            near_misses_v: Some(vec![Word("ABCDEFGHI".into()), Word("IHGFEDCBX".into())]) };
 
         let tests: Vec<CommandTest> = vec![
             CommandTest {
@@ -432,11 +1969,20 @@ mod tests {
                 expected: Response::IncorrectSolution(chan.clone(), word1.clone(), Reason::NotInDictionary)
             },
 
+            CommandTest {
+                description: "Word is not in dictionary, but close to other words",
+                state: Niancat::new_with_puzzle(&near_misses_checkword, Puzzle("ABCDEFGHI".into())),
+                command: Command::CheckSolution(chan.clone(), name1.clone(), word1.clone()),
+                // Solutions to the puzzle are never suggested.
+                expected: Response::IncorrectSolution(chan.clone(), word1.clone(),
+                    Reason::NearMisses(vec![Word("IHGFEDCBX".into())]))
+            },
+
             CommandTest {
                 description: "Word is the wrong length",
                 state: Niancat::new_with_puzzle(&NOT_SOLUTION_CHECKWORD, Puzzle("ABCDEFGHI".into())),
                 command: Command::CheckSolution(chan.clone(), name1.clone(), Word("NOTNINE".into())),
-                expected: Response::IncorrectSolution(chan.clone(), Word("NOTNINE".into()), Reason::NotNineCharacters)
+                expected: Response::IncorrectSolution(chan.clone(), Word("NOTNINE".into()), Reason::WrongLength(9))
             },
 
             CommandTest {
@@ -460,7 +2006,7 @@ mod tests {
                 command: Command::CheckSolution(chan.clone(), name1.clone(), word2.clone()),
                 expected: Response::Dual(
                     Box::new(Response::CorrectSolution(chan.clone(), word2.clone())),
-                    Box::new(Response::Notification(name1.clone(), expected_hash.clone())))
+                    Box::new(Response::Notification(name1.clone(), expected_hash.clone(), None, 0)))
             },
 
             CommandTest {
@@ -469,7 +2015,7 @@ mod tests {
                 command: Command::CheckSolution(chan.clone(), name1.clone(), Word("GALL TJUT A".into())),
                 expected: Response::Dual(
                     Box::new(Response::CorrectSolution(chan.clone(), Word("GALL TJUT A".into()))),
-                    Box::new(Response::Notification(name1.clone(), expected_hash.clone())))
+                    Box::new(Response::Notification(name1.clone(), expected_hash.clone(), None, 0)))
             },
 
             CommandTest {
@@ -481,7 +2027,7 @@ mod tests {
         ];
 
         for mut test in tests {
-            let actual = apply(&test.command, &mut test.state);
+            let actual = apply(&test.command, &mut test.state, NOW);
             assert_eq!(actual, test.expected, "{}", test.description);
         }
     }
@@ -1,34 +1,217 @@
 extern crate niancat;
 extern crate slack;
+extern crate getopts;
 
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::time::Duration;
 use std::thread;
 
-use niancat::{SlackListChannels, initialize, NiancatHandler};
+use getopts::Options;
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
+use niancat::{SlackListChannels, SlackEventHandler, ListChannels, initialize_games, NiancatHandler};
+use niancat::dictionary::SharedDictionary;
+use niancat::config::{Config, PartialConfig};
+use niancat::store::{FileSettingsStore, FileStateStore, SettingsStore, StateStore, UserSettings};
+
+const DEFAULT_CONFIG_FILE: &'static str = "niancat.toml";
 
-    if args.len() != 4 {
-        println!("Usage: niancat <token> <channel> <dictionary>")
+/// Each game saves its state to its own file. With more than one game, the channel name is added to
+/// the file name, like `niancat_state.konsulatet.json`.
+fn state_file_for(state_file: &str, channel_name: &str, no_of_games: usize) -> PathBuf {
+    let path = PathBuf::from(state_file);
+    if no_of_games == 1 {
+        return path;
     }
-    let api_key = args[1].clone();
-    let channel_name = args[2].clone();
-    let dictionary_file = args[3].clone();
+
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or("niancat_state".to_string());
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, channel_name, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, channel_name),
+    };
+    path.with_file_name(file_name)
+}
+
+/// The settings users choose for themselves are saved next to the state, like
+/// `niancat_state_users.json`.
+fn settings_file_for(state_file: &str) -> PathBuf {
+    let path = PathBuf::from(state_file);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or("niancat_state".to_string());
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}_users.{}", stem, ext.to_string_lossy()),
+        None => format!("{}_users", stem),
+    };
+    path.with_file_name(file_name)
+}
+
+fn comma_separated(s: String) -> Vec<String> {
+    s.split(',').map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect()
+}
+
+/// Read the config file, and override it with the command line flags.
+fn read_config(args: &[String]) -> Result<Config, String> {
+    let mut opts = Options::new();
+    opts.optopt("c", "config", &format!("config file, {} by default", DEFAULT_CONFIG_FILE), "FILE");
+    opts.optopt("", "token", "Slack API token", "TOKEN");
+    opts.optopt("", "token-env", "environment variable with the Slack API token", "VAR");
+    opts.optopt("", "channels", "channels to play in, separated by commas", "CHANNELS");
+    opts.optmulti("", "dictionary", "word list, may be given more than once", "FILE");
+    opts.optopt("", "allow-file", "file with words added to the word lists", "FILE");
+    opts.optopt("", "deny-file", "file with words removed from the word lists", "FILE");
+    opts.optopt("", "state-file", "file to save the game state in", "FILE");
+    opts.optopt("", "language", "language of the responses: sv or en", "LANGUAGE");
+    opts.optopt("", "channel-languages", "languages of single channels, like general=en, separated by commas", "LANGUAGES");
+    opts.optopt("", "reconnect-delay", "seconds to wait before reconnecting", "SECONDS");
+    opts.optopt("", "puzzle-length", "length of new puzzles", "LENGTH");
+    opts.optopt("", "daily-puzzle-time", "generate a new puzzle every day at this time, in UTC", "HH:MM");
+    opts.optopt("", "reminder-times", "remind of unsolved puzzles at these times, in UTC, separated by commas", "TIMES");
+    opts.optopt("", "publish-solutions-time", "publish the solutions and end the puzzle at this time, in UTC", "HH:MM");
+    opts.optflag("", "prefer-unique-puzzles", "prefer generated puzzles with a single solution");
+    opts.optopt("", "avoid-recent-puzzles", "don't generate puzzles used in this many days", "DAYS");
+    opts.optopt("", "commands", "enabled commands, separated by commas", "COMMANDS");
+    opts.optopt("", "admins", "users that may run admin commands, separated by commas", "NAMES");
+    opts.optopt("", "setters", "users that may set puzzles, separated by commas", "NAMES");
+    opts.optopt("", "default-role", "role of all other users: player, setter or admin", "ROLE");
+    opts.optflag("", "report-spoilers", "ask the admins to delete spoilers posted in public channels");
+    opts.optflag("", "guess-stats", "include stats of the guesses in the summary when a puzzle ends");
+    opts.optopt("", "guess-burst", "limit each user to this many guesses at once", "GUESSES");
+    opts.optopt("", "guess-interval", "seconds until a limited user may guess again", "SECONDS");
+    opts.optflag("h", "help", "print this help");
+
+    let usage = opts.usage("Usage: niancat [options]");
+    let matches = try!(opts.parse(args).map_err(|e| format!("{}\n{}", e, usage)));
+    if matches.opt_present("help") {
+        println!("{}", usage);
+        process::exit(0);
+    }
+    if !matches.free.is_empty() {
+        return Err(format!("Unexpected arguments: {}\n{}", matches.free.join(" "), usage));
+    }
+
+    // The default config file is optional, but a config file given on the command line must exist.
+    let file_config = match matches.opt_str("config") {
+        Some(path) => try!(PartialConfig::from_file(&path)),
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => try!(PartialConfig::from_file(DEFAULT_CONFIG_FILE)),
+        None => PartialConfig::default(),
+    };
+
+    let integer_flag = |name: &str| -> Result<Option<i64>, String> {
+        match matches.opt_str(name) {
+            None => Ok(None),
+            Some(x) => x.parse().map(Some).map_err(|e| format!("Invalid --{} {}: {}", name, x, e)),
+        }
+    };
+
+    let dictionaries = matches.opt_strs("dictionary");
+    let flags = PartialConfig {
+        token: matches.opt_str("token"),
+        token_env: matches.opt_str("token-env"),
+        channels: matches.opt_str("channels").map(comma_separated),
+        dictionaries: if dictionaries.is_empty() { None } else { Some(dictionaries) },
+        allow_file: matches.opt_str("allow-file"),
+        deny_file: matches.opt_str("deny-file"),
+        state_file: matches.opt_str("state-file"),
+        language: matches.opt_str("language"),
+        channel_languages: matches.opt_str("channel-languages").map(comma_separated),
+        reconnect_delay: try!(integer_flag("reconnect-delay")),
+        puzzle_length: try!(integer_flag("puzzle-length")),
+        daily_puzzle_time: matches.opt_str("daily-puzzle-time"),
+        reminder_times: matches.opt_str("reminder-times").map(comma_separated),
+        publish_solutions_time: matches.opt_str("publish-solutions-time"),
+        prefer_unique_puzzles: if matches.opt_present("prefer-unique-puzzles") { Some(true) } else { None },
+        avoid_recent_puzzles: try!(integer_flag("avoid-recent-puzzles")),
+        commands: matches.opt_str("commands").map(comma_separated),
+        admins: matches.opt_str("admins").map(comma_separated),
+        setters: matches.opt_str("setters").map(comma_separated),
+        default_role: matches.opt_str("default-role"),
+        report_spoilers: if matches.opt_present("report-spoilers") { Some(true) } else { None },
+        guess_stats: if matches.opt_present("guess-stats") { Some(true) } else { None },
+        guess_burst: try!(integer_flag("guess-burst")),
+        guess_interval: try!(integer_flag("guess-interval")),
+    };
+
+    file_config.merge(flags).validate(|var| env::var(var).ok())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let config = match read_config(&args[1..]) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Invalid configuration: {}", e);
+            process::exit(1);
+        }
+    };
+    let api_key = config.token.clone();
+    let channel_names = config.channels.clone();
+    let mut stores: Vec<FileStateStore> = channel_names.iter()
+        .map(|name| FileStateStore::new(state_file_for(&config.state_file, name, channel_names.len())))
+        .collect();
 
     let slack_list_channels = SlackListChannels {
         token: api_key.clone(),
     };
 
-    let init_result = initialize(&slack_list_channels, &dictionary_file, &channel_name);
-    let (dictionary, channel_id) = match init_result {
-        Err(reason) => panic!(reason),
+    let init_result = {
+        let games: Vec<(String, &StateStore)> = channel_names.iter().cloned()
+            .zip(stores.iter().map(|s| s as &StateStore))
+            .collect();
+        initialize_games(&slack_list_channels, &config.dictionary_files(), &games)
+    };
+    let (dictionary, games) = match init_result {
+        Err(reason) => {
+            println!("Could not start niancat: {}", reason);
+            process::exit(1);
+        },
         Ok(x) => x,
     };
 
+    // The dictionary can be reloaded with !laddaom, or by sending SIGHUP to the process, and edited
+    // with !tillåt and !förbjud.
+    let dictionary = SharedDictionary::new(dictionary);
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.enable_reload(dictionary.clone(), config.dictionary_files());
+    for (name, (channel_id, state)) in channel_names.iter().zip(games) {
+        handler.add_game(name, channel_id, Box::new(stores.remove(0)), &state);
+        handler.set_language(name, config.language_for(name));
+    }
+    let settings_store = FileSettingsStore::new(settings_file_for(&config.state_file));
+    let settings = match settings_store.load() {
+        Ok(settings) => settings.unwrap_or(UserSettings::default()),
+        Err(e) => {
+            println!("Could not load the saved user settings, reason: {}", e);
+            process::exit(1);
+        }
+    };
+    handler.set_settings_store(Box::new(settings_store), &settings);
+    handler.set_puzzle_length(config.puzzle_length);
+    handler.set_generator_options(&config.generator_options());
+    handler.set_roles(&config.roles());
+    handler.set_report_spoilers(config.report_spoilers);
+    handler.set_show_guess_stats(config.guess_stats);
+    if let Some(limit) = config.guess_limit() {
+        handler.set_guess_limit(limit);
+    }
+    handler.set_schedule(config.schedule());
+    if let Some(ref commands) = config.commands {
+        handler.set_enabled_commands(commands);
+    }
+    let mut handler = SlackEventHandler::new(handler);
 
+    // Get the members of all channels, so private messages can be sent to the right game.
+    match slack_list_channels.list_channels() {
+        Ok(channels) => {
+            for c in channels.channels {
+                handler.update_channel(&c);
+            }
+        },
 
-    let mut handler = NiancatHandler::new(&dictionary, channel_id);
+        Err(e) => {
+            panic!("Could not list channels! Reason: {:?}", e);
+        }
+    }
 
     // Get an initial list of all users.
     match slack_list_channels.list_users() {
@@ -45,14 +228,14 @@ fn main() {
 
     loop {
         let mut client = slack::RtmClient::new(&api_key);
-        let r = client.login_and_run::<NiancatHandler>(&mut handler);
+        let r = client.login_and_run::<SlackEventHandler>(&mut handler);
         match r {
             Ok(_) => {}
             Err(err) => println!("Error: {}", err),
         }
 
-        print!("Reconnecting in 60 seconds... ");
-        thread::sleep(Duration::from_secs(60));
+        print!("Reconnecting in {} seconds... ", config.reconnect_delay);
+        thread::sleep(Duration::from_secs(config.reconnect_delay));
         println!("Reconnecting!");
     }
 }
@@ -0,0 +1,605 @@
+use std::fmt;
+
+/// A language the bot responds in. Each game has a language, and users can choose their own
+/// language for private messages with `!språk`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Language {
+    Swedish,
+    English,
+}
+
+pub const LANGUAGE_CODES: &'static [&'static str] = &["sv", "en"];
+
+impl Language {
+    /// The language with the code used in the config and in `!språk`.
+    pub fn from_code(code: &str) -> Option<Language> {
+        match code {
+            "sv" => Some(Language::Swedish),
+            "en" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match *self {
+            Language::Swedish => "sv",
+            Language::English => "en",
+        }
+    }
+}
+
+impl Default for Language {
+    fn default() -> Language {
+        Language::Swedish
+    }
+}
+
+/// The key of a message in the catalogs. Most kinds of responses have their own message, and some
+/// have one for each case. The parts that several messages share have their own keys.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Message {
+    NoPuzzleToGenerate,
+    PuzzleQueued,
+    Queue,
+    EmptyQueue,
+    RemovedFromQueue,
+    NoSuchQueueEntry,
+    QueueEmpty,
+    OnlyAdmins,
+    OnlySetters,
+    NotAllowed,
+    RoleSet,
+    UnknownRole,
+    ConfiguredAdmin,
+    Roles,
+    EveryoneHasRole,
+    PlayerRole,
+    SetterRole,
+    AdminRole,
+    NoPuzzleSet,
+    GetPuzzle,
+    GetPuzzleWithSolutions,
+    PuzzleTitle,
+    SolutionCount,
+    SetPuzzle,
+    SetPuzzleWithSolutions,
+    NewPuzzleTitle,
+    PuzzleWrongLength,
+    PuzzleNotInDictionary,
+    ConfirmSetPuzzle,
+    ConfirmGeneratePuzzle,
+    ConfirmNextPuzzle,
+    SetRecently,
+    NoSolvers,
+    PuzzleRestored,
+    PuzzleRestoredWithSolutions,
+    RestoredPuzzleTitle,
+    NothingToUndo,
+    CorrectSolution,
+    Notification,
+    Solved,
+    HashField,
+    SolvedAfter,
+    SolvedWithHints,
+    OneHint,
+    Hints,
+    NotInDictionary,
+    NearMisses,
+    Or,
+    WrongLength,
+    NonMatchingWord,
+    SpoilerWarning,
+    SpoilerReport,
+    TooManyGuesses,
+    BruteForceReport,
+    Solutions,
+    GuessCount,
+    MostCommonWrongGuess,
+    TimesGuessed,
+    Salt,
+    SolutionsTitle,
+    SaltField,
+    VerifyField,
+    HashVerified,
+    HashNotYetVerifiable,
+    UnknownHash,
+    SetUnsolution,
+    NoUnsolutions,
+    GetUnsolutions,
+    UnsolutionsPrivateOnly,
+    Guesses,
+    NoGuesses,
+    GuessCorrect,
+    GuessNotInDictionary,
+    GuessWrongLength,
+    GuessNonMatching,
+    Unsolutions,
+    Leaderboard,
+    EmptyLeaderboard,
+    UserStats,
+    WeeklySummary,
+    Hint,
+    NoMoreHints,
+    AlreadySolved,
+    GameSelected,
+    UnknownGame,
+    LanguageSet,
+    UnknownLanguage,
+    ReloadStarted,
+    ReloadNotAdmin,
+    ReloadNotConfigured,
+    ReloadAlreadyRunning,
+    Reloaded,
+    ReloadFailed,
+    PuzzleRevalidated,
+    Reminder,
+    WordAllowed,
+    WordDenied,
+    EditNotAdmin,
+    EditNotConfigured,
+    EditWhileReloading,
+    EditFailed,
+    LessThanAMinute,
+    Help,
+    HelpTitle,
+    InvalidCommand,
+    UnknownCommand,
+    WrongNoOfParameters,
+}
+
+const SWEDISH_HELP: &'static str = r#"
+"Dagens nia" är ett ordpussel från Svenska Dagbladet. Varje dag får man {length} bokstäver, och ska hitta
+vilket svenskt ord man kan konstruera med hjälp av dessa bokstäver.
+Boten 'niancat' hjälper dig att lösa {nian} genom att kontrollera om ord finns med i SAOL eller inte,
+och att bokstäverna matchar dagens {nia}. Om du skriver in ett lösningsförslag i ett privat-meddelande
+till boten så kommer den säga till om ordet är korrekt, och i sådana fall automatiskt notifiera
+kanalen om att du hittat en lösning.
+
+Innan du har löst dagens {nia} är det bra om du inte skriver in lösningsförslag i kanalen, då det är
+möjligt att du är nära utan att veta om det, och därmed i praktiken löser den åt andra. När du löst
+den kan du skriva lösningsförslag i kanalen, men håll dig gärna till ord som inte är nära den
+riktiga lösningen.
+
+Kommandon:
+    !setnian <pussel>   Sätt {nian}.
+    !nian               Visa {nian}.
+    !slumpnian          Sätt en slumpvis vald {nia} från ordlistan.
+    !nästanian          Sätt nästa {nia} i kön.
+    !ångra              Återställ {nian} som byttes ut för högst 15 minuter sedan, med dess lösare.
+    !unsolution <text>  Sätt en olösning, att visas när nästa {nia} sätts.
+    !unsolutions        Visa alla mina olösningar.
+    !mina               Visa mina gissningar på dagens {nia}. Bara i privat-meddelande.
+    !topnian            Visa topplistan, totalt och för denna månad.
+    !statsnian <namn>   Visa statistik för en användare.
+    !ledtråd            Visa en bokstav till av en lösning. Bara i privat-meddelande.
+    !spela <spel>       Välj vilket spel dina privat-meddelanden gäller.
+    !språk <sv|en>      Välj vilket språk boten svarar dig på i privat-meddelanden.
+    !laddaom            Ladda om ordlistan. Bara för administratörer.
+    !tillåt <ord>       Lägg till ett ord i ordlistan. Bara för administratörer, i privat-meddelande.
+    !förbjud <ord>      Ta bort ett ord från ordlistan. Bara för administratörer, i privat-meddelande.
+    !köa <pussel>       Lägg en {nia} sist i kön. Bara för administratörer, i privat-meddelande.
+    !kö                 Visa kön. Bara för administratörer, i privat-meddelande.
+    !avköa <nummer>     Ta bort en {nia} från kön. Bara för administratörer, i privat-meddelande.
+    !roll <namn> <roll> Ge en användare rollen player, setter eller admin. Bara för administratörer.
+    !roller             Visa vilka som är sättare och administratörer.
+    !verifiera <hash>   Visa vem som löste {nian} med en hash, när lösningarna har publicerats.
+    !helpnian           Visa denna hjälptext.
+
+Kommandona !setnian, !slumpnian, !nästanian, !ångra, !nian och !helpnian kan man köra både i
+kanalen och i privat-meddelande till niancat. Olösningar kan bara sättas och visas i
+privat-meddelande. {Nian} kan bara sättas av sättare och administratörer, om boten är inställd så.
+Lösningarna på en {nia} som byts ut visas efter 15 minuter, när den inte längre kan återställas.
+"#;
+
+const ENGLISH_HELP: &'static str = r#"
+"Dagens nia" is a word puzzle from Svenska Dagbladet. Every day there are {length} letters, and the
+puzzle is to find the Swedish word that can be made from those letters.
+The bot 'niancat' helps you solve the puzzle by checking if words are in SAOL or not, and if the
+letters match today's puzzle. If you write a solution in a private message to the bot, it tells you
+if the word is correct, and if it is, it notifies the channel that you have found a solution.
+
+Before you have solved today's puzzle, please don't write guesses in the channel, since you might
+be close without knowing it, and so in practice solve it for others. Once you have solved it you
+can write guesses in the channel, but preferably words that are not close to the real solution.
+
+Commands:
+    !setnian <puzzle>   Set the puzzle.
+    !nian               Show the puzzle.
+    !slumpnian          Set a puzzle chosen at random from the dictionary.
+    !nästanian          Set the next puzzle in the queue.
+    !ångra              Restore the puzzle replaced at most 15 minutes ago, with its solvers.
+    !unsolution <text>  Set an unsolution, to be shown when the next puzzle is set.
+    !unsolutions        Show all my unsolutions.
+    !mina               Show my guesses on today's puzzle. Only in private messages.
+    !topnian            Show the leaderboard, all time and for this month.
+    !statsnian <name>   Show the statistics of a user.
+    !ledtråd            Show one more letter of a solution. Only in private messages.
+    !spela <game>       Choose which game your private messages are for.
+    !språk <sv|en>      Choose the language the bot answers you in, in private messages.
+    !laddaom            Reload the dictionary. Only for admins.
+    !tillåt <word>      Add a word to the dictionary. Only for admins, in private messages.
+    !förbjud <word>     Remove a word from the dictionary. Only for admins, in private messages.
+    !köa <puzzle>       Add a puzzle last in the queue. Only for admins, in private messages.
+    !kö                 Show the queue. Only for admins, in private messages.
+    !avköa <number>     Remove a puzzle from the queue. Only for admins, in private messages.
+    !roll <name> <role> Give a user the role player, setter or admin. Only for admins.
+    !roller             Show who are setters and admins.
+    !verifiera <hash>   Show who solved the puzzle with a hash, once its solutions are published.
+    !helpnian           Show this help text.
+
+The commands !setnian, !slumpnian, !nästanian, !ångra, !nian and !helpnian work both in the
+channel and in private messages to niancat. Unsolutions can only be set and shown in private
+messages. The puzzle can only be set by setters and admins, if the bot is configured so.
+The solutions of a replaced puzzle are shown after 15 minutes, once it can no longer be restored.
+"#;
+
+/// The Swedish catalog. Arguments are written as {0}, {1} and so on.
+fn swedish(message: Message) -> &'static str {
+    match message {
+        Message::NoPuzzleToGenerate => "Det finns inga ord som är {0} tecken långa att slumpa fram en {nia} av.",
+        Message::PuzzleQueued => "{Nian} {0} är köad, som nummer {1}.",
+        Message::Queue => "*Köade {nior}:*\n{0}",
+        Message::EmptyQueue => "Kön är tom.",
+        Message::RemovedFromQueue => "{Nian} {0} är borttagen från kön.",
+        Message::NoSuchQueueEntry => "Det finns ingen {nia} med nummer {0} i kön.",
+        Message::QueueEmpty => "Det finns inga köade {nior}.",
+        Message::OnlyAdmins => "Bara administratörer får göra det.",
+        Message::OnlySetters => "Bara sättare och administratörer får sätta {nian}.",
+        Message::NotAllowed => "Det får du inte göra.",
+        Message::RoleSet => "{0} är nu {1}.",
+        Message::UnknownRole => "Det finns ingen roll som heter {0}. Roller: {1}",
+        Message::ConfiguredAdmin => "{0} är administratör enligt konfigurationen, och kan inte få en annan roll.",
+        Message::Roles => "*Roller:*\n{0}Alla andra är {1}.",
+        Message::EveryoneHasRole => "Alla är {0}.",
+        Message::PlayerRole => "spelare",
+        Message::SetterRole => "sättare",
+        Message::AdminRole => "administratör",
+        Message::NoPuzzleSet => "{Nian} är inte satt!",
+        Message::GetPuzzle => "{0}",
+        Message::GetPuzzleWithSolutions => "{0}.\nDet finns {1} lösningar.",
+        Message::PuzzleTitle => "Dagens {nia}",
+        Message::SolutionCount => "Lösningar",
+        Message::SetPuzzle => "Dagens {nia} är satt till {0}.",
+        Message::SetPuzzleWithSolutions => "Dagens {nia} är satt till {0}.\nDet finns {1} lösningar.",
+        Message::NewPuzzleTitle => "Ny {nia}",
+        Message::PuzzleWrongLength => "Ogiltig {nia}! {0} är inte {1} tecken långt.",
+        Message::PuzzleNotInDictionary => "Ogiltig {nia}! {0} finns inte med i SAOL.",
+        Message::ConfirmSetPuzzle => "{0} Skriv !setnian {1} igen för att byta {nian} ändå.",
+        Message::ConfirmGeneratePuzzle => "{0} Skriv !slumpnian igen för att byta {nian} ändå.",
+        Message::ConfirmNextPuzzle => "{0} Skriv !nästanian igen för att byta {nian} ändå.",
+        Message::SetRecently => "{Nian} sattes för {0} sedan.",
+        Message::NoSolvers => "Ingen har löst {nian} än.",
+        Message::PuzzleRestored => "{Nian} är återställd till {0}.",
+        Message::PuzzleRestoredWithSolutions => "{Nian} är återställd till {0}.\nDet finns {1} lösningar.",
+        Message::RestoredPuzzleTitle => "Återställd {nia}",
+        Message::NothingToUndo => "Det finns ingen {nia} att ångra.",
+        Message::CorrectSolution => "Ordet {0} är korrekt!",
+        Message::Notification => "{0} löste {nian}{1}{2}: {3}",
+        Message::Solved => "{0} löste {nian}{1}{2}!",
+        Message::HashField => "Hash",
+        Message::SolvedAfter => " efter {0}",
+        Message::SolvedWithHints => " med {0}",
+        Message::OneHint => "1 ledtråd",
+        Message::Hints => "{0} ledtrådar",
+        Message::NotInDictionary => "Ordet {0} finns inte med i SAOL.",
+        Message::NearMisses => "Ordet {0} finns inte med i SAOL. Menade du {1}?",
+        Message::Or => " eller ",
+        Message::WrongLength => "Ordet {0} är inte {1} tecken långt.",
+        Message::NonMatchingWord => "Ordet {0} matchar inte dagens {nia} {1}. För många {2}, för få {3}.",
+        Message::SpoilerWarning =>
+            "Ditt meddelande i <#{0}> avslöjar dagens {nia}, med ordet {1}. Skriv inga lösningar eller nästan-lösningar \
+             i kanalen!",
+        Message::SpoilerReport => "{0} kan ha avslöjat dagens {nia} i <#{1}>. Ta gärna bort meddelandet.",
+        Message::TooManyGuesses => "Du gissar för ofta! Vänta {0} sekunder innan du gissar igen.",
+        Message::BruteForceReport =>
+            "{0} fortsätter att gissa fast hen har ombetts att vänta. Det kan vara ett skript som provar alla ord.",
+        Message::Solutions => "*Gårdagens lösningar:*\n",
+        Message::GuessCount => "Antal gissningar",
+        Message::MostCommonWrongGuess => "Vanligaste felgissningen",
+        Message::TimesGuessed => "{0} ({1} gånger)",
+        Message::Salt => "Saltet för lösningarnas hashar var {0}. Kontrollera en hash med !verifiera <hash>.\n",
+        Message::SolutionsTitle => "Gårdagens lösningar",
+        Message::SaltField => "Salt",
+        Message::VerifyField => "Kontrollera en hash",
+        Message::HashVerified => "Hashen är {0}s lösning {1} på {nian} {2}.",
+        Message::HashNotYetVerifiable =>
+            "Hashen hör till dagens {nia}. Den kan kontrolleras när lösningarna har publicerats.",
+        Message::UnknownHash => "Ingen lösning har hashen {0}.",
+        Message::SetUnsolution => "Olösning: {0}",
+        Message::NoUnsolutions => "Du har inga olösningar.",
+        Message::GetUnsolutions => "Dina olösningar:\n{0}",
+        Message::UnsolutionsPrivateOnly => "Olösningar visas bara i privat-meddelande till niancat.",
+        Message::Guesses => "Dina gissningar på dagens {nia}:\n{0}",
+        Message::NoGuesses => "Du har inte gissat något på dagens {nia} än.",
+        Message::GuessCorrect => "rätt!",
+        Message::GuessNotInDictionary => "finns inte i SAOL",
+        Message::GuessWrongLength => "inte {0} tecken",
+        Message::GuessNonMatching => "för många {0}, för få {1}",
+        Message::Unsolutions => "*Olösningar:*\n",
+        Message::Leaderboard => "*Topplista, totalt:*\n{0}*Topplista, denna månad:*\n{1}",
+        Message::EmptyLeaderboard => "Ingen har löst någon {nia}.\n",
+        Message::UserStats =>
+            "{0} har löst {1} {nior}.\nGenomsnittlig lösningstid: {2}\nNuvarande svit: {3}\nLängsta svit: {4}",
+        Message::WeeklySummary => "*Veckans sammanfattning:* {0} {nior}.\n{1}",
+        Message::Hint => "Ledtråd {0}: en lösning börjar med {1}",
+        Message::NoMoreHints => "Det finns inga fler ledtrådar.",
+        Message::AlreadySolved => "Du har redan löst dagens {nia}, så du behöver inga ledtrådar.",
+        Message::GameSelected => "Du spelar nu {0}.",
+        Message::UnknownGame => "Det finns inget spel som heter {0}. Spel: {1}",
+        Message::LanguageSet => "Jag svarar dig nu på svenska.",
+        Message::UnknownLanguage => "Det finns inget språk som heter {0}. Språk: {1}",
+        Message::ReloadStarted => "Laddar om ordlistan...",
+        Message::ReloadNotAdmin => "Bara administratörer kan ladda om ordlistan.",
+        Message::ReloadNotConfigured => "Ordlistan kan inte laddas om.",
+        Message::ReloadAlreadyRunning => "Ordlistan laddas redan om.",
+        Message::Reloaded => "Ordlistan är omladdad.",
+        Message::ReloadFailed => "Ordlistan kunde inte laddas om, och den gamla används fortfarande. Orsak: {0}",
+        Message::PuzzleRevalidated => "Ordlistan har ändrats. {Nian} {0} har nu {1} lösningar.",
+        Message::Reminder => "Ingen har löst {nian} {0} än! Den har {1} lösningar.",
+        Message::WordAllowed => "Ordet {0} är nu tillåtet.",
+        Message::WordDenied => "Ordet {0} är nu förbjudet.",
+        Message::EditNotAdmin => "Bara administratörer kan ändra ordlistan.",
+        Message::EditNotConfigured => "Det finns ingen lokal ordlista att ändra.",
+        Message::EditWhileReloading => "Ordlistan laddas om. Försök igen när den är klar.",
+        Message::EditFailed => "Ändringen kunde inte sparas. Orsak: {0}",
+        Message::LessThanAMinute => "mindre än en minut",
+        Message::Help => SWEDISH_HELP,
+        Message::HelpTitle => "Hjälp",
+        Message::InvalidCommand => "Ogiltigt kommando '{0}'. Orsak: {1}",
+        Message::UnknownCommand => "okänt kommando!",
+        Message::WrongNoOfParameters => "fel antal parametrar!",
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::NoPuzzleToGenerate => "There are no words {0} letters long to generate a puzzle from.",
+        Message::PuzzleQueued => "The puzzle {0} is queued, as number {1}.",
+        Message::Queue => "*Queued puzzles:*\n{0}",
+        Message::EmptyQueue => "The queue is empty.",
+        Message::RemovedFromQueue => "The puzzle {0} is removed from the queue.",
+        Message::NoSuchQueueEntry => "There is no puzzle number {0} in the queue.",
+        Message::QueueEmpty => "There are no queued puzzles.",
+        Message::OnlyAdmins => "Only admins may do that.",
+        Message::OnlySetters => "Only setters and admins may set the puzzle.",
+        Message::NotAllowed => "You may not do that.",
+        Message::RoleSet => "{0} is now {1}.",
+        Message::UnknownRole => "There is no role called {0}. Roles: {1}",
+        Message::ConfiguredAdmin => "{0} is an admin by the config, and can't be given another role.",
+        Message::Roles => "*Roles:*\n{0}Everyone else is {1}.",
+        Message::EveryoneHasRole => "Everyone is {0}.",
+        Message::PlayerRole => "player",
+        Message::SetterRole => "setter",
+        Message::AdminRole => "admin",
+        Message::NoPuzzleSet => "The puzzle is not set!",
+        Message::GetPuzzle => "{0}",
+        Message::GetPuzzleWithSolutions => "{0}.\nThere are {1} solutions.",
+        Message::PuzzleTitle => "Today's puzzle",
+        Message::SolutionCount => "Solutions",
+        Message::SetPuzzle => "Today's puzzle is set to {0}.",
+        Message::SetPuzzleWithSolutions => "Today's puzzle is set to {0}.\nThere are {1} solutions.",
+        Message::NewPuzzleTitle => "New puzzle",
+        Message::PuzzleWrongLength => "Invalid puzzle! {0} is not {1} letters long.",
+        Message::PuzzleNotInDictionary => "Invalid puzzle! {0} is not in SAOL.",
+        Message::ConfirmSetPuzzle => "{0} Write !setnian {1} again to replace the puzzle anyway.",
+        Message::ConfirmGeneratePuzzle => "{0} Write !slumpnian again to replace the puzzle anyway.",
+        Message::ConfirmNextPuzzle => "{0} Write !nästanian again to replace the puzzle anyway.",
+        Message::SetRecently => "The puzzle was set {0} ago.",
+        Message::NoSolvers => "Nobody has solved the puzzle yet.",
+        Message::PuzzleRestored => "The puzzle is restored to {0}.",
+        Message::PuzzleRestoredWithSolutions => "The puzzle is restored to {0}.\nThere are {1} solutions.",
+        Message::RestoredPuzzleTitle => "Restored puzzle",
+        Message::NothingToUndo => "There is no puzzle to undo.",
+        Message::CorrectSolution => "The word {0} is correct!",
+        Message::Notification => "{0} solved the puzzle{1}{2}: {3}",
+        Message::Solved => "{0} solved the puzzle{1}{2}!",
+        Message::HashField => "Hash",
+        Message::SolvedAfter => " after {0}",
+        Message::SolvedWithHints => " with {0}",
+        Message::OneHint => "1 hint",
+        Message::Hints => "{0} hints",
+        Message::NotInDictionary => "The word {0} is not in SAOL.",
+        Message::NearMisses => "The word {0} is not in SAOL. Did you mean {1}?",
+        Message::Or => " or ",
+        Message::WrongLength => "The word {0} is not {1} letters long.",
+        Message::NonMatchingWord => "The word {0} does not match today's puzzle {1}. Too many {2}, too few {3}.",
+        Message::SpoilerWarning =>
+            "Your message in <#{0}> gives away today's puzzle, with the word {1}. Please don't post solutions or near \
+             solutions in the channel!",
+        Message::SpoilerReport => "{0} may have given away today's puzzle in <#{1}>. Please delete the message.",
+        Message::TooManyGuesses => "You are guessing too often! Wait {0} seconds before guessing again.",
+        Message::BruteForceReport =>
+            "{0} keeps guessing after being asked to wait. It may be a script trying every word.",
+        Message::Solutions => "*Yesterday's solutions:*\n",
+        Message::GuessCount => "Guesses",
+        Message::MostCommonWrongGuess => "Most common wrong guess",
+        Message::TimesGuessed => "{0} ({1} times)",
+        Message::Salt => "The salt of the solution hashes was {0}. Verify a hash with !verifiera <hash>.\n",
+        Message::SolutionsTitle => "Yesterday's solutions",
+        Message::SaltField => "Salt",
+        Message::VerifyField => "Verify a hash",
+        Message::HashVerified => "The hash is {0}'s solution {1} to the puzzle {2}.",
+        Message::HashNotYetVerifiable =>
+            "The hash belongs to today's puzzle. It can be verified once the solutions are published.",
+        Message::UnknownHash => "No solution has the hash {0}.",
+        Message::SetUnsolution => "Unsolution: {0}",
+        Message::NoUnsolutions => "You have no unsolutions.",
+        Message::GetUnsolutions => "Your unsolutions:\n{0}",
+        Message::UnsolutionsPrivateOnly => "Unsolutions are only shown in a private message to niancat.",
+        Message::Guesses => "Your guesses on today's puzzle:\n{0}",
+        Message::NoGuesses => "You have not guessed anything on today's puzzle yet.",
+        Message::GuessCorrect => "correct!",
+        Message::GuessNotInDictionary => "not in the dictionary",
+        Message::GuessWrongLength => "not {0} letters",
+        Message::GuessNonMatching => "too many {0}, too few {1}",
+        Message::Unsolutions => "*Unsolutions:*\n",
+        Message::Leaderboard => "*Leaderboard, all time:*\n{0}*Leaderboard, this month:*\n{1}",
+        Message::EmptyLeaderboard => "Nobody has solved a puzzle.\n",
+        Message::UserStats =>
+            "{0} has solved {1} puzzles.\nAverage solve time: {2}\nCurrent streak: {3}\nLongest streak: {4}",
+        Message::WeeklySummary => "*This week:* {0} puzzles.\n{1}",
+        Message::Hint => "Hint {0}: a solution starts with {1}",
+        Message::NoMoreHints => "There are no more hints.",
+        Message::AlreadySolved => "You have already solved today's puzzle, so you need no hints.",
+        Message::GameSelected => "You are now playing {0}.",
+        Message::UnknownGame => "There is no game called {0}. Games: {1}",
+        Message::LanguageSet => "I will now answer you in English.",
+        Message::UnknownLanguage => "There is no language called {0}. Languages: {1}",
+        Message::ReloadStarted => "Reloading the dictionary...",
+        Message::ReloadNotAdmin => "Only admins can reload the dictionary.",
+        Message::ReloadNotConfigured => "The dictionary can't be reloaded.",
+        Message::ReloadAlreadyRunning => "The dictionary is already being reloaded.",
+        Message::Reloaded => "The dictionary is reloaded.",
+        Message::ReloadFailed => "The dictionary could not be reloaded, and the old one is still used. Reason: {0}",
+        Message::PuzzleRevalidated => "The dictionary has changed. The puzzle {0} now has {1} solutions.",
+        Message::Reminder => "Nobody has solved the puzzle {0} yet! It has {1} solutions.",
+        Message::WordAllowed => "The word {0} is now allowed.",
+        Message::WordDenied => "The word {0} is now denied.",
+        Message::EditNotAdmin => "Only admins can change the dictionary.",
+        Message::EditNotConfigured => "There is no local word list to change.",
+        Message::EditWhileReloading => "The dictionary is being reloaded. Try again when it is done.",
+        Message::EditFailed => "The change could not be saved. Reason: {0}",
+        Message::LessThanAMinute => "less than a minute",
+        Message::Help => ENGLISH_HELP,
+        Message::HelpTitle => "Help",
+        Message::InvalidCommand => "Invalid command '{0}'. Reason: {1}",
+        Message::UnknownCommand => "unknown command!",
+        Message::WrongNoOfParameters => "wrong number of parameters!",
+    }
+}
+
+fn template(language: Language, message: Message) -> &'static str {
+    match language {
+        Language::Swedish => swedish(message),
+        Language::English => english(message),
+    }
+}
+
+/// The text of a message in a language, with the arguments in place of {0}, {1} and so on. Braces
+/// that are not followed by the number of an argument are kept as they are. Messages about the
+/// puzzle are written with `puzzle_text`.
+pub fn text(language: Language, message: Message, args: &[&fmt::Display]) -> String {
+    fill(template(language, message), args)
+}
+
+/// The text of a message in a game with puzzles of a length. Like `text`, but the Swedish names of
+/// the puzzles, like "nia" for nine letters, are put in place of {nia}, {nian}, {Nian} and {nior},
+/// and the name of the length in place of {length}. The names are put in before the arguments, so
+/// that nothing in the arguments is taken for a name.
+pub fn puzzle_text(language: Language, length: usize, message: Message, args: &[&fmt::Display]) -> String {
+    let noun = puzzle_noun(length);
+    let definite = format!("{}n", noun);
+    let mut chars = definite.chars();
+    let capitalized: String = chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default();
+    let plural = format!("{}or", &noun[..noun.len() - 1]);
+
+    let template = template(language, message)
+        .replace("{nia}", &noun)
+        .replace("{nian}", &definite)
+        .replace("{Nian}", &capitalized)
+        .replace("{nior}", &plural)
+        .replace("{length}", &length_name(language, length));
+    fill(&template, args)
+}
+
+fn fill(template: &str, args: &[&fmt::Display]) -> String {
+    let mut s = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        s.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let arg = after.find('}')
+            .and_then(|end| after[..end].parse::<usize>().ok().map(|i| (i, end)))
+            .and_then(|(i, end)| args.get(i).map(|arg| (arg, end)));
+        match arg {
+            Some((arg, end)) => {
+                s.push_str(&format!("{}", arg));
+                rest = &after[end + 1..];
+            },
+            None => {
+                s.push('{');
+                rest = after;
+            },
+        }
+    }
+    s.push_str(rest);
+    s
+}
+
+/// The name of a length, like "nio" or "nine". Long lengths are written with digits.
+pub fn length_name(language: Language, length: usize) -> String {
+    const SWEDISH_NAMES: &'static [&'static str] = &[
+        "noll", "ett", "två", "tre", "fyra", "fem", "sex", "sju", "åtta", "nio", "tio", "elva", "tolv",
+    ];
+    const ENGLISH_NAMES: &'static [&'static str] = &[
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    ];
+
+    let names = match language {
+        Language::Swedish => SWEDISH_NAMES,
+        Language::English => ENGLISH_NAMES,
+    };
+    names.get(length).map(|x| x.to_string()).unwrap_or(format!("{}", length))
+}
+
+/// The Swedish name of a puzzle with letters of a length, like "nia" or "sjua". Long lengths are
+/// written with digits, like "13:a".
+fn puzzle_noun(length: usize) -> String {
+    const NAMES: &'static [&'static str] = &[
+        "nolla", "etta", "tvåa", "trea", "fyra", "femma", "sexa", "sjua", "åtta", "nia", "tia", "elva", "tolva",
+    ];
+
+    NAMES.get(length).map(|x| x.to_string()).unwrap_or(format!("{}:a", length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_codes_test() {
+        for code in LANGUAGE_CODES {
+            assert_eq!(Language::from_code(code).map(|l| l.code()), Some(*code));
+        }
+        assert_eq!(Language::from_code("fi"), None);
+    }
+
+    #[test]
+    fn text_test() {
+        assert_eq!(text(Language::Swedish, Message::TimesGuessed, &[&"GALLTJUTA", &2]), "GALLTJUTA (2 gånger)");
+        assert_eq!(text(Language::English, Message::TimesGuessed, &[&"GALLTJUTA", &2]), "GALLTJUTA (2 times)");
+        // Arguments are not filled in again, and unknown arguments are kept.
+        assert_eq!(text(Language::English, Message::SetUnsolution, &[&"{0} {1}"]), "Unsolution: {0} {1}");
+        assert_eq!(text(Language::English, Message::SetUnsolution, &[]), "Unsolution: {0}");
+    }
+
+    #[test]
+    fn length_name_test() {
+        assert_eq!(length_name(Language::Swedish, 7), "sju");
+        assert_eq!(length_name(Language::Swedish, 9), "nio");
+        assert_eq!(length_name(Language::Swedish, 10), "tio");
+        assert_eq!(length_name(Language::Swedish, 15), "15");
+        assert_eq!(length_name(Language::English, 9), "nine");
+        assert_eq!(length_name(Language::English, 15), "15");
+    }
+
+    #[test]
+    fn puzzle_text_test() {
+        assert_eq!(puzzle_text(Language::Swedish, 9, Message::PuzzleQueued, &[&"ABC DEF GHI", &2]),
+                   "Nian ABC DEF GHI är köad, som nummer 2.");
+        assert_eq!(puzzle_text(Language::English, 9, Message::PuzzleQueued, &[&"ABC DEF GHI", &2]),
+                   "The puzzle ABC DEF GHI is queued, as number 2.");
+        assert_eq!(puzzle_text(Language::Swedish, 9, Message::SetPuzzle, &[&"ABC DEF GHI"]),
+                   "Dagens nia är satt till ABC DEF GHI.");
+        assert_eq!(puzzle_text(Language::Swedish, 7, Message::SetPuzzle, &[&"ABC DE FG"]),
+                   "Dagens sjua är satt till ABC DE FG.");
+        assert_eq!(puzzle_text(Language::Swedish, 10, Message::PuzzleRestored, &[&"ABC DEF GH IJ"]),
+                   "Tian är återställd till ABC DEF GH IJ.");
+        assert_eq!(puzzle_text(Language::Swedish, 8, Message::UserStats, &[&"erike", &3, &"", &1, &2]),
+                   "erike har löst 3 åttor.\nGenomsnittlig lösningstid: \nNuvarande svit: 1\nLängsta svit: 2");
+        assert_eq!(puzzle_text(Language::Swedish, 13, Message::Solved, &[&"erike", &"", &""]), "erike löste 13:an!");
+        assert!(puzzle_text(Language::English, 7, Message::Help, &[]).contains("there are seven letters"));
+        // Names in the arguments are not filled in.
+        assert_eq!(puzzle_text(Language::Swedish, 7, Message::SetUnsolution, &[&"{nian}"]), "Olösning: {nian}");
+    }
+}
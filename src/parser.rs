@@ -5,7 +5,8 @@ use response::*;
 struct CommandParser {
     name: &'static str,
     pos_args: Option<usize>,
-    make: Box<Fn(Channel, &Vec<&str>) -> Command>,
+    private_only: bool,
+    make: Box<Fn(Channel, Name, &Vec<&str>) -> Command>,
 }
 
 impl CommandParser {
@@ -19,38 +20,201 @@ impl CommandParser {
 
 pub type CommandResult = Option<Result<Command, InvalidCommand>>;
 
-pub fn parse_command(chan: &Channel, name: &Name, text: &String) -> CommandResult {
-    let commands: Vec<CommandParser> = vec![
+fn command_parsers() -> Vec<CommandParser> {
+    vec![
         CommandParser {
             name: "!setnian",
             pos_args: Some(1),
-            make: Box::new(|c, args| Command::SetPuzzle(c, Puzzle(args[0].into()))),
+            private_only: false,
+            make: Box::new(|c, n, args| Command::SetPuzzle(c, n, Puzzle(args[0].into()))),
         },
 
         CommandParser {
             name: "!setnian",
             pos_args: Some(3),
-            make: Box::new(|c, args| Command::SetPuzzle(c, Puzzle::new(&args.concat()))),
+            private_only: false,
+            make: Box::new(|c, n, args| Command::SetPuzzle(c, n, Puzzle::new(&args.concat()))),
+        },
+
+        CommandParser {
+            name: "!slumpnian",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, n, _| Command::GeneratePuzzle(c, n)),
+        },
+
+        CommandParser {
+            name: "!nästanian",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, n, _| Command::NextPuzzle(c, n)),
+        },
+
+        CommandParser {
+            name: "!ångra",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, _, _| Command::Undo(c)),
+        },
+
+        CommandParser {
+            name: "!köa",
+            pos_args: Some(1),
+            private_only: true,
+            make: Box::new(|c, n, args| Command::QueuePuzzle(c, n, Puzzle::new(&args[0].to_string()))),
+        },
+
+        CommandParser {
+            name: "!köa",
+            pos_args: Some(3),
+            private_only: true,
+            make: Box::new(|c, n, args| Command::QueuePuzzle(c, n, Puzzle::new(&args.concat()))),
+        },
+
+        CommandParser {
+            name: "!kö",
+            pos_args: Some(0),
+            private_only: true,
+            make: Box::new(|c, n, _| Command::GetQueue(c, n)),
+        },
+
+        CommandParser {
+            name: "!avköa",
+            pos_args: Some(1),
+            private_only: true,
+            make: Box::new(|c, n, args| Command::RemoveFromQueue(c, n, args[0].into())),
         },
 
         CommandParser {
             name: "!nian",
             pos_args: Some(0),
-            make: Box::new(|c, _| Command::GetPuzzle(c)),
+            private_only: false,
+            make: Box::new(|c, _, _| Command::GetPuzzle(c)),
         },
 
         CommandParser {
             name: "!helpnian",
             pos_args: Some(0),
-            make: Box::new(|c, _| Command::Help(c)),
+            private_only: false,
+            make: Box::new(|c, _, _| Command::Help(c)),
         },
 
         CommandParser {
             name: "!halp",
             pos_args: Some(0),
-            make: Box::new(|c, _| Command::Help(c)),
+            private_only: false,
+            make: Box::new(|c, _, _| Command::Help(c)),
+        },
+
+        CommandParser {
+            name: "!topnian",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, _, _| Command::GetLeaderboard(c)),
+        },
+
+        CommandParser {
+            name: "!statsnian",
+            pos_args: Some(1),
+            private_only: false,
+            make: Box::new(|c, _, args| Command::GetUserStats(c, Name(args[0].trim_left_matches('@').into()))),
+        },
+
+        CommandParser {
+            name: "!ledtråd",
+            pos_args: Some(0),
+            private_only: true,
+            make: Box::new(|c, n, _| Command::GetHint(c, n)),
+        },
+
+        CommandParser {
+            name: "!spela",
+            pos_args: Some(1),
+            private_only: false,
+            make: Box::new(|c, n, args| Command::SelectGame(c, n, args[0].into())),
+        },
+
+        CommandParser {
+            name: "!språk",
+            pos_args: Some(1),
+            private_only: false,
+            make: Box::new(|c, n, args| Command::SetLanguage(c, n, args[0].into())),
+        },
+
+        CommandParser {
+            name: "!laddaom",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, n, _| Command::ReloadDictionary(c, n)),
+        },
+
+        CommandParser {
+            name: "!roll",
+            pos_args: Some(2),
+            private_only: false,
+            make: Box::new(|c, _, args| Command::SetRole(c, Name(args[0].trim_left_matches('@').into()), args[1].into())),
+        },
+
+        CommandParser {
+            name: "!roller",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, _, _| Command::GetRoles(c)),
+        },
+
+        CommandParser {
+            name: "!verifiera",
+            pos_args: Some(1),
+            private_only: false,
+            make: Box::new(|c, _, args| Command::VerifyHash(c, args[0].into())),
+        },
+
+        CommandParser {
+            name: "!tillåt",
+            pos_args: Some(1),
+            private_only: true,
+            make: Box::new(|c, n, args| Command::AllowWord(c, n, Word(args[0].into()))),
+        },
+
+        CommandParser {
+            name: "!förbjud",
+            pos_args: Some(1),
+            private_only: true,
+            make: Box::new(|c, n, args| Command::DenyWord(c, n, Word(args[0].into()))),
+        },
+
+        CommandParser {
+            name: "!unsolution",
+            pos_args: None,
+            private_only: true,
+            make: Box::new(|c, n, args| Command::SetUnsolution(c, n, args.join(" "))),
+        },
+
+        CommandParser {
+            name: "!unsolutions",
+            pos_args: Some(0),
+            private_only: false,
+            make: Box::new(|c, n, _| Command::GetUnsolutions(c, n)),
         },
-    ];
+
+        CommandParser {
+            name: "!mina",
+            pos_args: Some(0),
+            private_only: true,
+            make: Box::new(|c, n, _| Command::GetGuesses(c, n)),
+        },
+    ]
+}
+
+/// The names of all commands, like "!nian".
+pub fn command_names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = command_parsers().iter().map(|c| c.name).collect();
+    names.dedup();
+    names
+}
+
+pub fn parse_command(chan: &Channel, name: &Name, text: &String) -> CommandResult {
+    let commands = command_parsers();
 
     let parts: Vec<&str> = text.split_whitespace().collect();
 
@@ -66,9 +230,15 @@ pub fn parse_command(chan: &Channel, name: &Name, text: &String) -> CommandResul
         // the next `CommandParser`. However, we want to respond to it as an invalid command, with
         // the wrong number of parameters.
         for command in commands {
+            // Private commands in public channels are ignored, as they might be meant for another
+            // bot.
+            if command.private_only && !chan.is_private() {
+                continue;
+            }
+
             if command_name == command.name {
                 if command.matches_args(args.len()) {
-                    return Some(Ok((command.make)(chan.clone(), &args)));
+                    return Some(Ok((command.make)(chan.clone(), name.clone(), &args)));
                 } else {
                     invalid_reason = Some(InvalidCommandReason::WrongNoOfParameters);
                 }
@@ -87,6 +257,8 @@ pub fn parse_command(chan: &Channel, name: &Name, text: &String) -> CommandResul
         }
     } else if chan.is_private() {
         return Some(Ok(Command::CheckSolution(chan.clone(), name.clone(), Word(text.clone()))));
+    } else {
+        return Some(Ok(Command::CheckSpoiler(chan.clone(), name.clone(), text.clone())));
     }
 
     None
@@ -131,12 +303,12 @@ mod tests {
             CommandParserTest::new(
                 "Set puzzle",
                 "!setnian ABCDEFGHI", &test_channel, &test_user,
-                Some(Ok(Command::SetPuzzle(test_channel.clone(), Puzzle("ABCDEFGHI".into()))))),
+                Some(Ok(Command::SetPuzzle(test_channel.clone(), test_user.clone(), Puzzle("ABCDEFGHI".into()))))),
 
             CommandParserTest::new(
                 "Set puzzle",
                 "!setnian ABC DEF GHI", &test_channel, &test_user,
-                Some(Ok(Command::SetPuzzle(test_channel.clone(), Puzzle("ABCDEFGHI".into()))))),
+                Some(Ok(Command::SetPuzzle(test_channel.clone(), test_user.clone(), Puzzle("ABCDEFGHI".into()))))),
 
             CommandParserTest::new(
                 "Get puzzle",
@@ -149,9 +321,9 @@ mod tests {
                 Some(Ok(Command::Help(test_channel.clone())))),
 
             CommandParserTest::new(
-                "Ignore non-commands in public channel",
+                "Check non-commands in public channel for spoilers",
                 "ABCDEFGHI", &test_channel, &test_user,
-                None),
+                Some(Ok(Command::CheckSpoiler(test_channel.clone(), test_user.clone(), "ABCDEFGHI".into())))),
 
             CommandParserTest::new(
                 "Check solution",
@@ -197,6 +369,171 @@ mod tests {
                 Some(Err(InvalidCommand(test_channel.clone(), "!helpnian yoyoyo".into(),
                                         InvalidCommandReason::WrongNoOfParameters)))),
 
+            CommandParserTest::new(
+                "Generate a puzzle",
+                "!slumpnian", &test_channel, &test_user,
+                Some(Ok(Command::GeneratePuzzle(test_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Next puzzle in the queue",
+                "!nästanian", &test_channel, &test_user,
+                Some(Ok(Command::NextPuzzle(test_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Undo",
+                "!ångra", &test_channel, &test_user,
+                Some(Ok(Command::Undo(test_channel.clone())))),
+
+            CommandParserTest::new(
+                "Queue a puzzle",
+                "!köa tag all tju", &im_channel, &test_user,
+                Some(Ok(Command::QueuePuzzle(im_channel.clone(), test_user.clone(), Puzzle("TAGALLTJU".into()))))),
+
+            CommandParserTest::new(
+                "Queue a puzzle is ignored in public channel",
+                "!köa TAGALLTJU", &test_channel, &test_user,
+                None),
+
+            CommandParserTest::new(
+                "Show the queue",
+                "!kö", &im_channel, &test_user,
+                Some(Ok(Command::GetQueue(im_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Remove from the queue",
+                "!avköa 2", &im_channel, &test_user,
+                Some(Ok(Command::RemoveFromQueue(im_channel.clone(), test_user.clone(), "2".into())))),
+
+            CommandParserTest::new(
+                "Leaderboard",
+                "!topnian", &test_channel, &test_user,
+                Some(Ok(Command::GetLeaderboard(test_channel.clone())))),
+
+            CommandParserTest::new(
+                "User statistics",
+                "!statsnian erike", &test_channel, &test_user,
+                Some(Ok(Command::GetUserStats(test_channel.clone(), Name("erike".into()))))),
+
+            CommandParserTest::new(
+                "User statistics, with a mention",
+                "!statsnian @erike", &im_channel, &test_user,
+                Some(Ok(Command::GetUserStats(im_channel.clone(), Name("erike".into()))))),
+
+            CommandParserTest::new(
+                "User statistics, without a user",
+                "!statsnian", &test_channel, &test_user,
+                Some(Err(InvalidCommand(test_channel.clone(), "!statsnian".into(),
+                                        InvalidCommandReason::WrongNoOfParameters)))),
+
+            CommandParserTest::new(
+                "Select game",
+                "!spela konsulatet", &im_channel, &test_user,
+                Some(Ok(Command::SelectGame(im_channel.clone(), test_user.clone(), "konsulatet".into())))),
+
+            CommandParserTest::new(
+                "Select game, without a game",
+                "!spela", &im_channel, &test_user,
+                Some(Err(InvalidCommand(im_channel.clone(), "!spela".into(),
+                                        InvalidCommandReason::WrongNoOfParameters)))),
+
+            CommandParserTest::new(
+                "Choose a language",
+                "!språk en", &im_channel, &test_user,
+                Some(Ok(Command::SetLanguage(im_channel.clone(), test_user.clone(), "en".into())))),
+
+            CommandParserTest::new(
+                "Reload the dictionary",
+                "!laddaom", &test_channel, &test_user,
+                Some(Ok(Command::ReloadDictionary(test_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Set a role",
+                "!roll @erike setter", &test_channel, &test_user,
+                Some(Ok(Command::SetRole(test_channel.clone(), Name("erike".into()), "setter".into())))),
+
+            CommandParserTest::new(
+                "Set a role, without a role",
+                "!roll erike", &im_channel, &test_user,
+                Some(Err(InvalidCommand(im_channel.clone(), "!roll erike".into(),
+                                        InvalidCommandReason::WrongNoOfParameters)))),
+
+            CommandParserTest::new(
+                "Get roles",
+                "!roller", &test_channel, &test_user,
+                Some(Ok(Command::GetRoles(test_channel.clone())))),
+
+            CommandParserTest::new(
+                "Verify a hash",
+                "!verifiera 0123abcd", &test_channel, &test_user,
+                Some(Ok(Command::VerifyHash(test_channel.clone(), "0123abcd".into())))),
+
+            CommandParserTest::new(
+                "Allow a word",
+                "!tillåt galltjuta", &im_channel, &test_user,
+                Some(Ok(Command::AllowWord(im_channel.clone(), test_user.clone(), Word("galltjuta".into()))))),
+
+            CommandParserTest::new(
+                "Deny a word",
+                "!förbjud galltjuta", &im_channel, &test_user,
+                Some(Ok(Command::DenyWord(im_channel.clone(), test_user.clone(), Word("galltjuta".into()))))),
+
+            CommandParserTest::new(
+                "Deny a word is ignored in public channel",
+                "!förbjud galltjuta", &test_channel, &test_user,
+                None),
+
+            CommandParserTest::new(
+                "Hint",
+                "!ledtråd", &im_channel, &test_user,
+                Some(Ok(Command::GetHint(im_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Hint is ignored in public channel",
+                "!ledtråd", &test_channel, &test_user,
+                None),
+
+            CommandParserTest::new(
+                "Set unsolution command",
+                "!unsolution FOO BAR BAZ qux", &im_channel, &test_user,
+                Some(Ok(Command::SetUnsolution(im_channel.clone(), test_user.clone(), "FOO BAR BAZ qux".into())))),
+
+            CommandParserTest::new(
+                "Set unsolution command with no params",
+                "!unsolution   ", &im_channel, &test_user,
+                Some(Err(InvalidCommand(im_channel.clone(), "!unsolution   ".into(),
+                                        InvalidCommandReason::WrongNoOfParameters)))),
+
+            CommandParserTest::new(
+                "Set unsolution command ignored in public channel",
+                "!unsolution FOO BAR BAZ qux", &test_channel, &test_user,
+                None),
+
+            CommandParserTest::new(
+                "Get guesses",
+                "!mina", &im_channel, &test_user,
+                Some(Ok(Command::GetGuesses(im_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Get guesses ignored in public channel",
+                "!mina", &test_channel, &test_user,
+                None),
+
+            CommandParserTest::new(
+                "Get unsolutions",
+                "!unsolutions", &im_channel, &test_user,
+                Some(Ok(Command::GetUnsolutions(im_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Get unsolutions is also accepted in public (but response is in private)",
+                "!unsolutions", &test_channel, &test_user,
+                Some(Ok(Command::GetUnsolutions(test_channel.clone(), test_user.clone())))),
+
+            CommandParserTest::new(
+                "Get unsolutions, too many params",
+                "!unsolutions COOL COOL COOL", &im_channel, &test_user,
+                Some(Err(InvalidCommand(im_channel.clone(), "!unsolutions COOL COOL COOL".into(),
+                                        InvalidCommandReason::WrongNoOfParameters)))),
+
         ];
 
         for test in tests {
@@ -205,35 +542,11 @@ mod tests {
         }
     }
 
-            //CommandParserTest::new(
-            //    "Set unsolution command",
-            //    "!unsolution FOO BAR BAZ qux", &IM_CHANNEL,
-            //    cmd(SetUnsolutionCommand(&IM_CHANNEL, TEST_USER.clone(), "FOO BAR BAZ qux"))),
-            //
-            //CommandParserTest::new(
-            //    "Set unsolution command with no params",
-            //    "!unsolution   ", &IM_CHANNEL,
-            //    cmd(InvalidCommand(&IM_CHANNEL, TEST_USER.clone(), "!unsolution   ", :wrong_no_of_parameters))),
-            //
-            //CommandParserTest::new(
-            //    "Set unsolution command ignored in public channel",
-            //    "!unsolution FOO BAR BAZ qux", &TEST_CHANNEL,
-            //    None),
-            //
-            //CommandParserTest::new(
-            //    "Get unsolutions",
-            //    "!unsolutions", &IM_CHANNEL,
-            //    cmd(GetUnsolutionsCommand(&IM_CHANNEL, TEST_USER.clone()))),
-            //
-            //CommandParserTest::new(
-            //    "Get unsolutions is also accepted in public (but response is in private)",
-            //    "!unsolutions", &TEST_CHANNEL,
-            //    cmd(GetUnsolutionsCommand(&TEST_CHANNEL, TEST_USER.clone()))),
-            //
-            //CommandParserTest::new(
-            //    "Get unsolutions, too many params",
-            //    "!unsolutions COOL COOL COOL", &IM_CHANNEL,
-            //    cmd(InvalidCommand(&IM_CHANNEL, TEST_USER.clone(), "!unsolutions COOL COOL COOL",
-            //                   :wrong_no_of_parameters))),
-
-}
\ No newline at end of file
+    #[test]
+    fn command_names_test() {
+        let names = command_names();
+        assert_eq!(names.iter().filter(|&&x| x == "!setnian").count(), 1);
+        assert!(names.contains(&"!nian"));
+        assert!(names.contains(&"!unsolutions"));
+    }
+}
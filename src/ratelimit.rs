@@ -0,0 +1,164 @@
+use std::cmp::min;
+use std::collections::HashMap;
+
+use types::{Name, Timestamp};
+
+/// How many guesses may be refused, without the user resting until all guesses are given back,
+/// before the user looks like a script trying every word.
+pub const FLAG_AFTER_REFUSED: u32 = 5;
+
+/// How often a user may guess: `burst` guesses at once, and then one more every `interval`
+/// seconds.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct GuessLimit {
+    pub burst: u32,
+    pub interval: Timestamp,
+}
+
+/// What to do with a guess.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Verdict {
+    Allowed,
+    /// The user must wait this many seconds before guessing again. `flag` is set once the user has
+    /// kept guessing while refused for `FLAG_AFTER_REFUSED` guesses.
+    Refused { wait: Timestamp, flag: bool },
+    /// The guess is refused without telling the user, who has already been flagged.
+    Ignored,
+}
+
+/// The guesses a user has left.
+struct Bucket {
+    tokens: u32,
+    /// When a guess was last given back, or when the bucket was last full.
+    refilled: Timestamp,
+    /// The guesses refused since the bucket was last full.
+    refused: u32,
+}
+
+/// A token bucket for each user.
+pub struct RateLimiter {
+    limit: GuessLimit,
+    buckets: HashMap<Name, Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: GuessLimit) -> RateLimiter {
+        RateLimiter { limit: limit, buckets: HashMap::new() }
+    }
+
+    /// Take a guess from the user's bucket, or refuse it if the bucket is empty.
+    pub fn check(&mut self, name: &Name, now: Timestamp) -> Verdict {
+        let limit = self.limit;
+        let bucket = self.buckets.entry(name.clone()).or_insert(Bucket {
+            tokens: limit.burst,
+            refilled: now,
+            refused: 0,
+        });
+
+        let given_back = (now - bucket.refilled) / limit.interval;
+        if given_back > 0 {
+            bucket.tokens = min(limit.burst as Timestamp, bucket.tokens as Timestamp + given_back) as u32;
+            bucket.refilled += given_back * limit.interval;
+        }
+        if bucket.tokens == limit.burst {
+            bucket.refilled = now;
+            bucket.refused = 0;
+        }
+
+        if bucket.tokens > 0 {
+            bucket.tokens -= 1;
+            Verdict::Allowed
+        } else if bucket.refused >= FLAG_AFTER_REFUSED {
+            Verdict::Ignored
+        } else {
+            bucket.refused += 1;
+            Verdict::Refused {
+                wait: bucket.refilled + limit.interval - now,
+                flag: bucket.refused == FLAG_AFTER_REFUSED,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Name;
+
+    const NOW: i64 = 1474286400;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(GuessLimit { burst: 3, interval: 10 })
+    }
+
+    #[test]
+    fn burst_test() {
+        let mut limiter = limiter();
+        let alice = Name("alice".into());
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check(&alice, NOW), Verdict::Allowed);
+        }
+        assert_eq!(limiter.check(&alice, NOW + 4), Verdict::Refused { wait: 6, flag: false });
+
+        // Other users have their own buckets.
+        assert_eq!(limiter.check(&Name("bob".into()), NOW + 4), Verdict::Allowed);
+    }
+
+    #[test]
+    fn refill_test() {
+        let mut limiter = limiter();
+        let alice = Name("alice".into());
+
+        for _ in 0..3 {
+            limiter.check(&alice, NOW);
+        }
+
+        // One guess is given back every interval.
+        assert_eq!(limiter.check(&alice, NOW + 10), Verdict::Allowed);
+        assert_eq!(limiter.check(&alice, NOW + 15), Verdict::Refused { wait: 5, flag: false });
+        assert_eq!(limiter.check(&alice, NOW + 25), Verdict::Allowed);
+
+        // A full bucket doesn't fill up any further while the user rests.
+        for _ in 0..3 {
+            assert_eq!(limiter.check(&alice, NOW + 1000), Verdict::Allowed);
+        }
+        assert_eq!(limiter.check(&alice, NOW + 1000), Verdict::Refused { wait: 10, flag: false });
+    }
+
+    #[test]
+    fn flag_test() {
+        let mut limiter = limiter();
+        let alice = Name("alice".into());
+
+        for _ in 0..3 {
+            limiter.check(&alice, NOW);
+        }
+
+        // Users are flagged once for guessing while refused, even as guesses are given back.
+        let flags = (1..40)
+            .map(|i| limiter.check(&alice, NOW + i))
+            .filter(|verdict| match *verdict {
+                Verdict::Refused { flag, .. } => flag,
+                Verdict::Allowed | Verdict::Ignored => false,
+            })
+            .count();
+        assert_eq!(flags, 1);
+
+        // Resting until the bucket is full clears the count.
+        for _ in 0..3 {
+            limiter.check(&alice, NOW + 1000);
+        }
+        let flagged = (0..FLAG_AFTER_REFUSED).map(|_| limiter.check(&alice, NOW + 1000)).last();
+        assert_eq!(flagged, Some(Verdict::Refused { wait: 10, flag: true }));
+
+        // Flagged users are not told about the guesses that are refused, until they rest again.
+        assert_eq!(limiter.check(&alice, NOW + 1000), Verdict::Ignored);
+        assert_eq!(limiter.check(&alice, NOW + 1015), Verdict::Allowed);
+        assert_eq!(limiter.check(&alice, NOW + 1016), Verdict::Ignored);
+        for _ in 0..3 {
+            limiter.check(&alice, NOW + 2000);
+        }
+        assert_eq!(limiter.check(&alice, NOW + 2000), Verdict::Refused { wait: 10, flag: false });
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+use libc;
+
+use dictionary::{Dictionary, DictionaryFiles, SharedDictionary};
+use types::{Channel, Name, Word};
+
+static SIGHUP_RECEIVED: AtomicBool = ATOMIC_BOOL_INIT;
+
+extern "C" fn on_sighup(_: libc::c_int) {
+    SIGHUP_RECEIVED.store(true, Ordering::SeqCst);
+}
+
+/// Reload the dictionary when the process gets a SIGHUP. The signal handler only sets a flag, which
+/// is checked by `NiancatHandler::poll`.
+pub fn reload_on_sighup() {
+    unsafe {
+        libc::signal(libc::SIGHUP, on_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+/// Check if a SIGHUP has been received since the last call.
+pub fn take_sighup() -> bool {
+    SIGHUP_RECEIVED.swap(false, Ordering::SeqCst)
+}
+
+/// A reload asked for with `!laddaom`. The user is told how it went, in the game they asked in.
+pub struct ReloadRequest {
+    pub channel: Channel,
+    pub name: Name,
+    pub game_index: usize,
+}
+
+/// Loads the dictionary files in a background thread, and replaces the shared dictionary when they
+/// have been loaded. If they can't be loaded, the old dictionary is kept. Also edits the overlays,
+/// so that an edit is never lost to a reload that read the files before it.
+pub struct Reloader {
+    dictionary: SharedDictionary,
+    files: DictionaryFiles,
+    /// Who asked for the running reload, if anyone, and where the result arrives.
+    pending: Option<(Option<ReloadRequest>, Receiver<Result<Dictionary, String>>)>,
+}
+
+impl Reloader {
+    pub fn new(dictionary: SharedDictionary, files: DictionaryFiles) -> Reloader {
+        Reloader { dictionary: dictionary, files: files, pending: None }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Start loading the dictionary, unless it is already being loaded.
+    pub fn start(&mut self, requested_by: Option<ReloadRequest>) -> bool {
+        if self.is_running() {
+            return false;
+        }
+
+        let (sender, receiver) = channel();
+        let files = self.files.clone();
+        thread::spawn(move || {
+            let result = files.load().map_err(|e| format!("{}", e));
+            let _ = sender.send(result);
+        });
+
+        self.pending = Some((requested_by, receiver));
+        true
+    }
+
+    /// If the dictionary has been loaded, replace the old one. Returns who asked for the reload, and
+    /// if it succeeded.
+    pub fn poll(&mut self) -> Option<(Option<ReloadRequest>, Result<(), String>)> {
+        let result = match self.pending {
+            None => return None,
+            Some((_, ref receiver)) => match receiver.try_recv() {
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => Err("The loading thread stopped".to_string()),
+                Ok(result) => result,
+            },
+        };
+
+        let (requested_by, _) = self.pending.take().unwrap();
+        let result = result.map(|dictionary| self.dictionary.replace(dictionary));
+        Some((requested_by, result))
+    }
+
+    pub fn can_allow(&self) -> bool {
+        self.files.allow.is_some()
+    }
+
+    pub fn can_deny(&self) -> bool {
+        self.files.deny.is_some()
+    }
+
+    /// Add a word to the allow overlay, and save the overlays. The dictionary is only changed if
+    /// they could be saved.
+    pub fn allow(&self, word: &Word) -> Result<(), String> {
+        let word = word.normalize();
+        if word.0.is_empty() {
+            return Err("The word is empty".to_string());
+        }
+        let mut allowed = self.dictionary.allowed_words();
+        let mut denied = self.dictionary.denied_words();
+        denied.retain(|w| w != &word);
+        if !allowed.contains(&word) {
+            allowed.push(word.clone());
+        }
+
+        try!(self.files.save_overlays(&allowed, &denied).map_err(|e| format!("{}", e)));
+        self.dictionary.allow(&word);
+        Ok(())
+    }
+
+    /// Add a word to the deny overlay, and save the overlays. The dictionary is only changed if
+    /// they could be saved.
+    pub fn deny(&self, word: &Word) -> Result<(), String> {
+        let word = word.normalize();
+        if word.0.is_empty() {
+            return Err("The word is empty".to_string());
+        }
+        let mut allowed = self.dictionary.allowed_words();
+        let mut denied = self.dictionary.denied_words();
+        allowed.retain(|w| w != &word);
+        if !denied.contains(&word) {
+            denied.push(word.clone());
+        }
+
+        try!(self.files.save_overlays(&allowed, &denied).map_err(|e| format!("{}", e)));
+        self.dictionary.deny(&word);
+        Ok(())
+    }
+}
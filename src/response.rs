@@ -1,28 +1,77 @@
 use types::*;
+use stats::*;
+use roles::{Role, ROLE_NAMES};
+use messages::{Language, Message, LANGUAGE_CODES, length_name, puzzle_text, text};
 use std::collections::HashMap;
 use std::fmt;
 
+/// A message to send to a channel. The text is the whole message as plain text, for backends and
+/// clients that can't show the attachment, if there is one.
 #[derive(PartialEq, Eq, Clone, Debug)]
-pub struct SlackResponse(pub Channel, pub String);
+pub struct SlackResponse(pub Channel, pub String, pub Option<Attachment>);
+
+/// A formatted version of a message, shown instead of its text where Slack can show it. The text and
+/// the field values may use Slack's markup, like code blocks.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Attachment {
+    pub title: String,
+    pub text: String,
+    pub fields: Vec<Field>,
+}
+
+/// A titled value below the text of an attachment. Short fields are shown side by side.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Field {
+    pub title: String,
+    pub value: String,
+    pub short: bool,
+}
 
 pub type TooMany = String;
 pub type TooFew = String;
 pub type WordHash = String;
+/// The secret that the solution hashes of a puzzle are salted with.
+pub type Salt = String;
 
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub struct SolutionsMap(pub HashMap<Word, Vec<String>>);
 
-#[derive(Eq, PartialEq, Debug)]
+/// The time, in seconds from the puzzle being set, when each user first solved it.
+pub type SolveTimes = HashMap<Name, i64>;
+/// The number of hints each user has asked for.
+pub type HintCounts = HashMap<Name, usize>;
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct UnsolutionsMap(pub HashMap<Name, Vec<String>>);
+
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum Reason {
     NotInDictionary,
-    NotNineCharacters,
+    /// Not in the dictionary, but these words are close to the guess.
+    NearMisses(Vec<Word>),
+    WrongLength(usize),
     NonMatchingWord(Puzzle, TooMany, TooFew),
 }
 
+#[derive(Eq, PartialEq, Debug)]
+pub enum RefusedReason {
+    NotAdmin,
+    NotConfigured,
+    AlreadyRunning,
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub enum InvalidPuzzleReason {
     NotInDictionary,
-    NotNineCharacters,
+    WrongLength(usize),
+}
+
+/// Why replacing the puzzle needs to be confirmed.
+#[derive(Eq, PartialEq, Debug)]
+pub enum ConfirmReason {
+    /// The current puzzle was set this many seconds ago.
+    SetRecently(i64),
+    NoSolvers,
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -37,202 +86,846 @@ pub enum Response {
     NoPuzzleSet(Channel),
     SetPuzzle(Channel, Puzzle, usize),
     InvalidPuzzle(Channel, Puzzle, InvalidPuzzleReason),
+    /// The puzzle is set if the same command is given again.
+    ConfirmSetPuzzle(Channel, Puzzle, ConfirmReason),
+    /// A puzzle is generated if `!slumpnian` is given again.
+    ConfirmGeneratePuzzle(Channel, ConfirmReason),
+    /// The next puzzle in the queue is set if `!nästanian` is given again.
+    ConfirmNextPuzzle(Channel, ConfirmReason),
+    /// The replaced puzzle, and its number of solutions.
+    PuzzleRestored(Channel, Puzzle, usize),
+    NothingToUndo(Channel),
+    /// The dictionary has no words of the puzzle length to generate a puzzle from.
+    NoPuzzleToGenerate(Channel, usize),
+    /// A puzzle added to the queue, and its position in it.
+    PuzzleQueued(Channel, Puzzle, usize),
+    Queue(Channel, Vec<Puzzle>),
+    RemovedFromQueue(Channel, Puzzle),
+    NoSuchQueueEntry(Channel, String),
+    QueueEmpty(Channel),
+    /// The user does not have the role needed to run a command.
+    PermissionDenied(Channel, Role),
+    RoleSet(Channel, Name, Role),
+    UnknownRole(Channel, String),
+    /// The user is an admin by the config, and can't be given a lesser role with `!roll`.
+    ConfiguredAdmin(Channel, Name),
+    /// The default role, and the users with other roles.
+    Roles(Channel, Role, Vec<(Name, Role)>),
     CorrectSolution(Channel, Word),
-    Notification(Name, WordHash),
-    SolutionsNotification(SolutionsMap),
+    Notification(Name, WordHash, Option<i64>, usize),
+    /// The solutions of the puzzle that ended, with the stats of the guesses if they are shown.
+    SolutionsNotification(SolutionsMap, SolveTimes, HintCounts, Salt, Option<GuessStats>),
+    /// The puzzle, solver and word that a solution hash was made from.
+    HashVerified(Channel, Puzzle, Name, Word),
+    /// The hash belongs to the current puzzle, whose salt is still secret.
+    HashNotYetVerifiable(Channel),
+    UnknownHash(Channel, WordHash),
     IncorrectSolution(Channel, Word, Reason),
+    /// A private warning to a user who gave away the puzzle, with the channel and the word.
+    SpoilerWarning(Channel, Channel, Word),
+    /// A private request to an admin to delete a spoiler, by a user in a channel.
+    SpoilerReport(Channel, Name, Channel),
+    /// The user has guessed too often, and must wait this many seconds.
+    TooManyGuesses(Channel, i64),
+    /// A private warning to an admin about a user who keeps guessing while refused.
+    BruteForceReport(Channel, Name),
+    SetUnsolution(Channel, String),
+    GetUnsolutions(Channel, Vec<String>),
+    /// Unsolutions were asked for in a public channel.
+    UnsolutionsPrivateOnly(Channel),
+    Guesses(Channel, Vec<Guess>),
+    UnsolutionsNotification(UnsolutionsMap),
+    Leaderboard(Channel, Leaderboard, Leaderboard),
+    UserStats(Channel, Name, UserStats),
+    WeeklySummary(WeeklySummary),
+    Hint(Channel, String, usize),
+    NoMoreHints(Channel),
+    /// A hint was asked for by a user who has already solved the puzzle.
+    AlreadySolved(Channel),
+    GameSelected(Channel, String),
+    /// The language the user has chosen for private messages.
+    LanguageSet(Channel, Language),
+    UnknownLanguage(Channel, String),
+    ReloadStarted(Channel),
+    ReloadRefused(Channel, RefusedReason),
+    Reloaded(Channel),
+    ReloadFailed(Channel, String),
+    WordAllowed(Channel, Word),
+    WordDenied(Channel, Word),
+    EditRefused(Channel, RefusedReason),
+    EditFailed(Channel, String),
+    /// A puzzle nobody has solved yet, and its number of solutions.
+    Reminder(Puzzle, usize),
+    /// The current puzzle, and its number of solutions after the dictionary was reloaded or edited.
+    PuzzleRevalidated(Puzzle, usize),
+    /// A game that does not exist, and the names of all games.
+    UnknownGame(Channel, String, Vec<String>),
     Help(Channel),
     Dual(Box<Response>, Box<Response>),
-    //Triple(Box<Response>, Box<Response>, Box<Response>),
+    Multiple(Vec<Response>),
+}
+
+impl Response {
+    /// Combine responses into a single response, using the simplest variant possible.
+    pub fn combine(mut responses: Vec<Response>) -> Response {
+        match responses.len() {
+            1 => responses.remove(0),
+            2 => {
+                let second = responses.remove(1);
+                let first = responses.remove(0);
+                Response::Dual(Box::new(first), Box::new(second))
+            },
+            _ => Response::Multiple(responses),
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
 pub struct InvalidCommand(pub Channel, pub String, pub InvalidCommandReason);
 
 pub trait Respond {
-    fn serialize(&self, r: &Response) -> Vec<SlackResponse>;
-    fn serialize_invalid_command(&self, r: &InvalidCommand) -> Vec<SlackResponse>;
+    /// Serialize a response to a command from a user, or to no one in particular if the user's
+    /// language is `None`.
+    fn serialize(&self, r: &Response, user_language: Option<Language>) -> Vec<SlackResponse>;
+    fn serialize_invalid_command(&self, r: &InvalidCommand, user_language: Option<Language>) -> Vec<SlackResponse>;
 }
 
 struct SlackResponder {
     main_channel: Channel,
+    /// The language of the game, used in its channel and for users who have not chosen another.
+    language: Language,
+    /// The length of the puzzles of the game, which the messages name.
+    puzzle_length: usize,
 }
 
+/// Break a puzzle into groups of at most three letters, to make it easier to read. The groups are as
+/// even as possible, with the longer groups first, like "ABC DEF GH IJ".
 pub fn break_puzzle(&Puzzle(ref p): &Puzzle) -> String {
-    if !is_right_length(&p) {
-        panic!("Can't break apart puzzle, because {} is not the right length!", p);
+    let letters: Vec<char> = p.chars().collect();
+    let groups = (letters.len() + 2) / 3;
+
+    let mut parts = vec![];
+    let mut start = 0;
+    for i in 0..groups {
+        let size = letters.len() / groups + if i < letters.len() % groups { 1 } else { 0 };
+        parts.push(letters[start..start + size].iter().cloned().collect::<String>());
+        start += size;
+    }
+    parts.join(" ")
+}
+
+/// Show a puzzle as a grid, with a row for each group of `break_puzzle`. Nine letters are a 3×3 grid.
+fn puzzle_grid(puzzle: &Puzzle) -> String {
+    let rows: Vec<String> = break_puzzle(puzzle).split(' ')
+        .map(|group| group.chars().map(|c| c.to_string()).collect::<Vec<String>>().join(" "))
+        .collect();
+    format!("```{}```", rows.join("\n"))
+}
+
+fn puzzle_attachment(language: Language,
+                     length: usize,
+                     title: Message,
+                     puzzle: &Puzzle,
+                     solutions: usize) -> Attachment {
+    let mut fields = vec![];
+    if solutions > 1 {
+        fields.push(Field {
+            title: text(language, Message::SolutionCount, &[]),
+            value: solutions.to_string(),
+            short: true,
+        });
+    }
+    Attachment { title: puzzle_text(language, length, title, &[]), text: puzzle_grid(puzzle), fields: fields }
+}
+
+/// Format a duration in seconds for humans, like "2 h 5 min".
+pub fn format_duration(language: Language, seconds: i64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 1 {
+        return text(language, Message::LessThanAMinute, &[]);
+    }
+
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    match (hours, minutes) {
+        (0, m) => format!("{} min", m),
+        (h, 0) => format!("{} h", h),
+        (h, m) => format!("{} h {} min", h, m),
+    }
+}
+
+fn format_leaderboard(language: Language, length: usize, leaderboard: &Leaderboard) -> String {
+    if leaderboard.is_empty() {
+        return puzzle_text(language, length, Message::EmptyLeaderboard, &[]);
     }
 
-    let a = p.chars().take(3).collect::<String>();
-    let b = p.chars().skip(3).take(3).collect::<String>();
-    let c = p.chars().skip(6).take(3).collect::<String>();
-    let puzzle = vec![a, b, c].join(" ");
-    puzzle
+    leaderboard.iter()
+        .take(10)
+        .enumerate()
+        .map(|(i, &(Name(ref name), solved))| format!("{}. {}: {}\n", i + 1, name, solved))
+        .collect()
+}
+
+fn role_name(language: Language, role: Role) -> String {
+    let message = match role {
+        Role::Player => Message::PlayerRole,
+        Role::Setter => Message::SetterRole,
+        Role::Admin => Message::AdminRole,
+    };
+    text(language, message, &[])
 }
 
-const HELP_TEXT: &'static str = r#"
-"Dagens nia" är ett ordpussel från Svenska Dagbladet. Varje dag får man nio bokstäver, och ska hitta
-vilket svenskt ord man kan konstruera med hjälp av dessa bokstäver.
-Boten 'niancat' hjälper dig att lösa nian genom att kontrollera om ord finns med i SAOL eller inte,
-och att bokstäverna matchar dagens nia. Om du skriver in ett lösningsförslag i ett privat-meddelande
-till boten så kommer den säga till om ordet är korrekt, och i sådana fall automatiskt notifiera
-kanalen om att du hittat en lösning.
-
-Innan du har löst dagens nia är det bra om du inte skriver in lösningsförslag i kanalen, då det är
-möjligt att du är nära utan att veta om det, och därmed i praktiken löser den åt andra. När du löst
-den kan du skriva lösningsförslag i kanalen, men håll dig gärna till ord som inte är nära den
-riktiga lösningen.
-
-Kommandon:
-    !setnian <pussel>   Sätt nian.
-    !nian               Visa nian.
-    !unsolution <text>  Sätt en olösning, att visas när nästa nian sätts.
-    !unsolutions        Visa alla mina olösning. Svar, om det finns, visas i en privat kanal.
-    !helpnian           Visa denna hjälptext.
-
-Alla dessa kommandon kan man köra både i kanalen och i privat-meddelande till tiancat.
-"#;
-
-impl fmt::Display for SolutionsMap {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        try!(write!(f, "*Gårdagens lösningar:*\n"));
-
-        for (&Word(ref word), solvers) in &self.0 {
-            try!(write!(f, "*{}*: {}\n", word, solvers.join(", ")));
+fn format_confirm_reason(language: Language, length: usize, reason: &ConfirmReason) -> String {
+    match *reason {
+        ConfirmReason::SetRecently(age) =>
+            puzzle_text(language, length, Message::SetRecently, &[&format_duration(language, age)]),
+        ConfirmReason::NoSolvers => puzzle_text(language, length, Message::NoSolvers, &[]),
+    }
+}
+
+fn format_queue(queue: &[Puzzle]) -> String {
+    queue.iter()
+        .enumerate()
+        .map(|(i, puzzle)| format!("{}. {}\n", i + 1, break_puzzle(puzzle)))
+        .collect()
+}
+
+fn format_hints(language: Language, hints: usize) -> String {
+    match hints {
+        1 => text(language, Message::OneHint, &[]),
+        n => text(language, Message::Hints, &[&n]),
+    }
+}
+
+/// The solutions for a puzzle, as each word with its solvers ordered by solve time. Words are ordered
+/// by their first solver.
+fn solution_rows(language: Language,
+                 &SolutionsMap(ref solutions): &SolutionsMap,
+                 times: &SolveTimes,
+                 hints: &HintCounts) -> Vec<(String, String)> {
+    let solve_time = |name: &String| times.get(&Name(name.clone())).cloned().unwrap_or(i64::max_value());
+
+    let mut words: Vec<(&Word, Vec<&String>)> = solutions.iter()
+        .map(|(word, solvers)| {
+            let mut solvers: Vec<&String> = solvers.iter().collect();
+            solvers.sort_by_key(|name| solve_time(name));
+            solvers.dedup();
+            (word, solvers)
+        })
+        .collect();
+    words.sort_by_key(|&(word, ref solvers)| {
+        (solvers.first().map(|name| solve_time(name)).unwrap_or(i64::max_value()), word.0.clone())
+    });
+
+    words.into_iter()
+        .map(|(&Word(ref word), solvers)| {
+            let solvers: Vec<String> = solvers.iter()
+                .map(|name| {
+                    let name = Name((*name).clone());
+                    let mut details = vec![];
+                    if let Some(&t) = times.get(&name) {
+                        details.push(format_duration(language, t));
+                    }
+                    if let Some(&h) = hints.get(&name) {
+                        details.push(format_hints(language, h));
+                    }
+
+                    if details.is_empty() {
+                        name.0
+                    } else {
+                        format!("{} ({})", name.0, details.join(", "))
+                    }
+                })
+                .collect();
+            (word.clone(), solvers.join(", "))
+        })
+        .collect()
+}
+
+/// The stats of the guesses on a puzzle, as titles and values.
+fn guess_stat_rows(language: Language, stats: &GuessStats) -> Vec<(String, String)> {
+    let mut rows = vec![(text(language, Message::GuessCount, &[]), stats.guesses.to_string())];
+    if let Some((Word(ref word), n)) = stats.most_common_wrong {
+        rows.push((text(language, Message::MostCommonWrongGuess, &[]), text(language, Message::TimesGuessed, &[word, &n])));
+    }
+    rows
+}
+
+fn format_solutions(language: Language,
+                    rows: &[(String, String)],
+                    salt: &Salt,
+                    stats: Option<&GuessStats>) -> String {
+    let mut s = text(language, Message::Solutions, &[]);
+    for &(ref word, ref solvers) in rows {
+        s.push_str(&format!("*{}*: {}\n", word, solvers));
+    }
+    if let Some(stats) = stats {
+        for (title, value) in guess_stat_rows(language, stats) {
+            s.push_str(&format!("{}: {}\n", title, value));
         }
-        Ok(())
+    }
+    if !salt.is_empty() {
+        s.push_str(&text(language, Message::Salt, &[salt]));
+    }
+    s
+}
+
+fn format_guess(language: Language, &Guess { word: Word(ref word), ref reason, .. }: &Guess) -> String {
+    let outcome = match *reason {
+        None => text(language, Message::GuessCorrect, &[]),
+        Some(Reason::NotInDictionary) | Some(Reason::NearMisses(_)) => text(language, Message::GuessNotInDictionary, &[]),
+        Some(Reason::WrongLength(length)) =>
+            text(language, Message::GuessWrongLength, &[&length_name(language, length)]),
+        Some(Reason::NonMatchingWord(_, ref too_many, ref too_few)) =>
+            text(language, Message::GuessNonMatching, &[too_many, too_few]),
+    };
+    format!("{}: {}\n", word, outcome)
+}
+
+/// The solutions as a table in a code block, with the words lined up in a column.
+fn solutions_attachment(language: Language,
+                        rows: &[(String, String)],
+                        salt: &Salt,
+                        stats: Option<&GuessStats>) -> Attachment {
+    let width = rows.iter().map(|&(ref word, _)| word.chars().count()).max().unwrap_or(0);
+    let table: Vec<String> = rows.iter()
+        .map(|&(ref word, ref solvers)| format!("{:<width$}  {}", word, solvers, width = width))
+        .collect();
+
+    let mut fields = vec![];
+    if let Some(stats) = stats {
+        for (title, value) in guess_stat_rows(language, stats) {
+            fields.push(Field { title: title, value: value, short: true });
+        }
+    }
+    if !salt.is_empty() {
+        fields.push(Field { title: text(language, Message::SaltField, &[]), value: salt.clone(), short: true });
+        fields.push(Field {
+            title: text(language, Message::VerifyField, &[]),
+            value: "!verifiera <hash>".to_string(),
+            short: true,
+        });
+    }
+    Attachment {
+        title: text(language, Message::SolutionsTitle, &[]),
+        text: if table.is_empty() { "".to_string() } else { format!("```{}```", table.join("\n")) },
+        fields: fields,
+    }
+}
+
+fn format_unsolutions(language: Language, &UnsolutionsMap(ref unsolutions): &UnsolutionsMap) -> String {
+    let mut s = text(language, Message::Unsolutions, &[]);
+    for (&Name(ref name), unsolutions) in unsolutions {
+        for unsolution in unsolutions {
+            s.push_str(&format!("*{}*: {}\n", name, unsolution));
+        }
+    }
+    s
+}
+
+impl SlackResponder {
+    /// Everything in the game channel is in the language of the game, so that everyone can read it.
+    /// Other responses are in the language the user has chosen, if any.
+    fn language_for(&self, channel: &Channel, user_language: Option<Language>) -> Language {
+        match user_language {
+            Some(language) if channel != &self.main_channel => language,
+            _ => self.language,
+        }
+    }
+
+    fn respond(&self, channel: &Channel, language: Language, message: Message, args: &[&fmt::Display]) -> SlackResponse {
+        SlackResponse(channel.clone(), puzzle_text(language, self.puzzle_length, message, args), None)
+    }
+}
+
+impl SlackResponse {
+    fn with_attachment(self, attachment: Attachment) -> SlackResponse {
+        SlackResponse(self.0, self.1, Some(attachment))
     }
 }
 
 impl Respond for SlackResponder {
-    fn serialize(&self, r: &Response) -> Vec<SlackResponse> {
+    fn serialize(&self, r: &Response, user_language: Option<Language>) -> Vec<SlackResponse> {
+        let language = |channel: &Channel| self.language_for(channel, user_language);
+        let main_language = self.language;
+        let length = self.puzzle_length;
+
         match *r {
+            Response::NoPuzzleToGenerate(ref channel, length) => vec![
+                self.respond(channel, language(channel), Message::NoPuzzleToGenerate,
+                             &[&length_name(language(channel), length)])
+            ],
+
+            Response::PuzzleQueued(ref channel, ref puzzle, position) => vec![
+                self.respond(channel, language(channel), Message::PuzzleQueued, &[&break_puzzle(puzzle), &position])
+            ],
+
+            Response::Queue(ref channel, ref queue) if queue.is_empty() => vec![
+                self.respond(channel, language(channel), Message::EmptyQueue, &[])
+            ],
+
+            Response::Queue(ref channel, ref queue) => vec![
+                self.respond(channel, language(channel), Message::Queue, &[&format_queue(queue)])
+            ],
+
+            Response::RemovedFromQueue(ref channel, ref puzzle) => vec![
+                self.respond(channel, language(channel), Message::RemovedFromQueue, &[&break_puzzle(puzzle)])
+            ],
+
+            Response::NoSuchQueueEntry(ref channel, ref entry) => vec![
+                self.respond(channel, language(channel), Message::NoSuchQueueEntry, &[entry])
+            ],
+
+            Response::QueueEmpty(ref channel) => vec![
+                self.respond(channel, language(channel), Message::QueueEmpty, &[])
+            ],
+
+            Response::PermissionDenied(ref channel, role) => {
+                let message = match role {
+                    Role::Admin => Message::OnlyAdmins,
+                    Role::Setter => Message::OnlySetters,
+                    Role::Player => Message::NotAllowed,
+                };
+                vec![self.respond(channel, language(channel), message, &[])]
+            },
+
+            Response::RoleSet(ref channel, Name(ref name), role) => vec![
+                self.respond(channel, language(channel), Message::RoleSet,
+                             &[name, &role_name(language(channel), role)])
+            ],
+
+            Response::UnknownRole(ref channel, ref role) => vec![
+                self.respond(channel, language(channel), Message::UnknownRole, &[role, &ROLE_NAMES.join(", ")])
+            ],
+
+            Response::ConfiguredAdmin(ref channel, Name(ref name)) => vec![
+                self.respond(channel, language(channel), Message::ConfiguredAdmin, &[name])
+            ],
+
+            Response::Roles(ref channel, default_role, ref roles) if roles.is_empty() => vec![
+                self.respond(channel, language(channel), Message::EveryoneHasRole,
+                             &[&role_name(language(channel), default_role)])
+            ],
+
+            Response::Roles(ref channel, default_role, ref roles) => {
+                let users: String = roles.iter()
+                    .map(|&(Name(ref name), role)| format!("{}: {}\n", name, role_name(language(channel), role)))
+                    .collect();
+                vec![
+                    self.respond(channel, language(channel), Message::Roles,
+                                 &[&users, &role_name(language(channel), default_role)])
+                ]
+            },
+
             Response::NoPuzzleSet(ref channel) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Nian är inte satt!"))
+                self.respond(channel, language(channel), Message::NoPuzzleSet, &[])
             ],
 
             Response::GetPuzzle(ref channel, ref puzzle, 1) => vec![
-                    SlackResponse(channel.clone(), format!("{}",
-                                  break_puzzle(puzzle)))
+                self.respond(channel, language(channel), Message::GetPuzzle, &[&break_puzzle(puzzle)])
+                    .with_attachment(puzzle_attachment(language(channel), length, Message::PuzzleTitle, puzzle, 1))
             ],
 
             Response::GetPuzzle(ref channel, ref puzzle, n) => vec![
-                    SlackResponse(channel.clone(),
-                        format!("{}.\nDet finns {} lösningar.",
-                                break_puzzle(puzzle), n))
+                self.respond(channel, language(channel), Message::GetPuzzleWithSolutions, &[&break_puzzle(puzzle), &n])
+                    .with_attachment(puzzle_attachment(language(channel), length, Message::PuzzleTitle, puzzle, n))
             ],
 
             Response::SetPuzzle(ref channel, ref puzzle, 1) => vec![
-                    SlackResponse(channel.clone(), format!("Dagens nia är satt till {}.",
-                                  break_puzzle(puzzle)))
+                self.respond(channel, language(channel), Message::SetPuzzle, &[&break_puzzle(puzzle)])
+                    .with_attachment(puzzle_attachment(language(channel), length, Message::NewPuzzleTitle, puzzle, 1))
             ],
 
             Response::SetPuzzle(ref channel, ref puzzle, n) => vec![
-                    SlackResponse(channel.clone(),
-                        format!("Dagens nia är satt till {}.\nDet finns {} lösningar.",
-                                break_puzzle(puzzle), n))
+                self.respond(channel, language(channel), Message::SetPuzzleWithSolutions, &[&break_puzzle(puzzle), &n])
+                    .with_attachment(puzzle_attachment(language(channel), length, Message::NewPuzzleTitle, puzzle, n))
             ],
 
-            Response::InvalidPuzzle(ref channel, Puzzle(ref puzzle), InvalidPuzzleReason::NotNineCharacters) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Ogiltig nian! {} är inte nio tecken långt.", puzzle))
+            Response::InvalidPuzzle(ref channel, Puzzle(ref puzzle), InvalidPuzzleReason::WrongLength(length)) => vec![
+                self.respond(channel, language(channel), Message::PuzzleWrongLength,
+                             &[puzzle, &length_name(language(channel), length)])
             ],
 
             Response::InvalidPuzzle(ref channel, Puzzle(ref puzzle), InvalidPuzzleReason::NotInDictionary) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Ogiltig nian! {} finns inte med i SAOL.", puzzle))
+                self.respond(channel, language(channel), Message::PuzzleNotInDictionary, &[puzzle])
             ],
 
-            Response::CorrectSolution(ref channel, Word(ref word)) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Ordet {} är korrekt!", word))
+            Response::ConfirmSetPuzzle(ref channel, ref puzzle, ref reason) => vec![
+                self.respond(channel, language(channel), Message::ConfirmSetPuzzle,
+                             &[&format_confirm_reason(language(channel), length, reason), &break_puzzle(puzzle)])
+            ],
+
+            Response::ConfirmGeneratePuzzle(ref channel, ref reason) => vec![
+                self.respond(channel, language(channel), Message::ConfirmGeneratePuzzle,
+                             &[&format_confirm_reason(language(channel), length, reason)])
+            ],
+
+            Response::ConfirmNextPuzzle(ref channel, ref reason) => vec![
+                self.respond(channel, language(channel), Message::ConfirmNextPuzzle,
+                             &[&format_confirm_reason(language(channel), length, reason)])
+            ],
+
+            Response::PuzzleRestored(ref channel, ref puzzle, 1) => vec![
+                self.respond(channel, language(channel), Message::PuzzleRestored, &[&break_puzzle(puzzle)])
+                    .with_attachment(puzzle_attachment(language(channel), length, Message::RestoredPuzzleTitle, puzzle, 1))
+            ],
+
+            Response::PuzzleRestored(ref channel, ref puzzle, n) => vec![
+                self.respond(channel, language(channel), Message::PuzzleRestoredWithSolutions,
+                             &[&break_puzzle(puzzle), &n])
+                    .with_attachment(puzzle_attachment(language(channel), length, Message::RestoredPuzzleTitle, puzzle, n))
+            ],
+
+            Response::NothingToUndo(ref channel) => vec![
+                self.respond(channel, language(channel), Message::NothingToUndo, &[])
             ],
 
-            Response::Notification(Name(ref name), ref hash) => vec![
-                SlackResponse(self.main_channel.clone(),
-                    format!("{} löste nian: {}", name, hash))
+            Response::CorrectSolution(ref channel, Word(ref word)) => vec![
+                self.respond(channel, language(channel), Message::CorrectSolution, &[word])
             ],
 
+            Response::Notification(Name(ref name), ref hash, solve_time, hints) => {
+                let after = match solve_time {
+                    Some(t) => text(main_language, Message::SolvedAfter, &[&format_duration(main_language, t)]),
+                    None => "".to_string(),
+                };
+                let with_hints = match hints {
+                    0 => "".to_string(),
+                    n => text(main_language, Message::SolvedWithHints, &[&format_hints(main_language, n)]),
+                };
+                // The hash is only interesting to those who want to verify it later, so it is kept
+                // out of the way in a field.
+                let attachment = Attachment {
+                    title: puzzle_text(main_language, length, Message::Solved, &[name, &after, &with_hints]),
+                    text: "".to_string(),
+                    fields: vec![
+                        Field { title: text(main_language, Message::HashField, &[]), value: hash.clone(), short: false },
+                    ],
+                };
+                vec![
+                    self.respond(&self.main_channel, main_language, Message::Notification,
+                                 &[name, &after, &with_hints, hash])
+                        .with_attachment(attachment)
+                ]
+            },
+
             Response::IncorrectSolution(ref channel, Word(ref w), Reason::NotInDictionary) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Ordet {} finns inte med i SAOL.", w))
+                self.respond(channel, language(channel), Message::NotInDictionary, &[w])
             ],
 
-            Response::IncorrectSolution(ref channel, Word(ref w), Reason::NotNineCharacters) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Ordet {} är inte nio tecken långt.", w))
+            Response::IncorrectSolution(ref channel, Word(ref w), Reason::NearMisses(ref words)) => {
+                let words: Vec<&str> = words.iter().map(|x| x.0.as_ref()).collect();
+                let words = words.join(&text(language(channel), Message::Or, &[]));
+                vec![self.respond(channel, language(channel), Message::NearMisses, &[w, &words])]
+            },
+
+            Response::IncorrectSolution(ref channel, Word(ref w), Reason::WrongLength(length)) => vec![
+                self.respond(channel, language(channel), Message::WrongLength,
+                             &[w, &length_name(language(channel), length)])
             ],
 
             Response::IncorrectSolution(ref channel, Word(ref w),
                                          Reason::NonMatchingWord(Puzzle(ref puzzle), ref too_many, ref too_few)) => vec![
-                SlackResponse(channel.clone(),
-                    format!("Ordet {} matchar inte dagens nia {}. För många {}, för få {}.", w, puzzle, too_many, too_few))
+                self.respond(channel, language(channel), Message::NonMatchingWord, &[w, puzzle, too_many, too_few])
+            ],
+
+            Response::SpoilerWarning(ref channel, Channel(ref public), Word(ref word)) => vec![
+                self.respond(channel, language(channel), Message::SpoilerWarning, &[public, word])
+            ],
+
+            Response::SpoilerReport(ref channel, Name(ref name), Channel(ref public)) => vec![
+                self.respond(channel, language(channel), Message::SpoilerReport, &[name, public])
+            ],
+
+            Response::TooManyGuesses(ref channel, wait) => vec![
+                self.respond(channel, language(channel), Message::TooManyGuesses, &[&wait])
+            ],
+
+            Response::BruteForceReport(ref channel, Name(ref name)) => vec![
+                self.respond(channel, language(channel), Message::BruteForceReport, &[name])
+            ],
+
+            Response::SolutionsNotification(ref solutions, ref times, ref hints, ref salt, ref stats) => {
+                let rows = solution_rows(main_language, solutions, times, hints);
+                vec![
+                    SlackResponse(self.main_channel.clone(),
+                                  format_solutions(main_language, &rows, salt, stats.as_ref()),
+                                  Some(solutions_attachment(main_language, &rows, salt, stats.as_ref()))),
+                ]
+            },
+
+            Response::HashVerified(ref channel, ref puzzle, Name(ref name), Word(ref word)) => vec![
+                self.respond(channel, language(channel), Message::HashVerified, &[name, word, &break_puzzle(puzzle)])
+            ],
+
+            Response::HashNotYetVerifiable(ref channel) => vec![
+                self.respond(channel, language(channel), Message::HashNotYetVerifiable, &[])
+            ],
+
+            Response::UnknownHash(ref channel, ref hash) => vec![
+                self.respond(channel, language(channel), Message::UnknownHash, &[hash])
+            ],
+
+            Response::SetUnsolution(ref channel, ref unsolution) => vec![
+                self.respond(channel, language(channel), Message::SetUnsolution, &[unsolution])
+            ],
+
+            Response::GetUnsolutions(ref channel, ref unsolutions) if unsolutions.is_empty() => vec![
+                self.respond(channel, language(channel), Message::NoUnsolutions, &[])
+            ],
+
+            Response::GetUnsolutions(ref channel, ref unsolutions) => vec![
+                self.respond(channel, language(channel), Message::GetUnsolutions, &[&unsolutions.join("\n")])
+            ],
+
+            Response::UnsolutionsPrivateOnly(ref channel) => vec![
+                self.respond(channel, language(channel), Message::UnsolutionsPrivateOnly, &[])
+            ],
+
+            Response::Guesses(ref channel, ref guesses) if guesses.is_empty() => vec![
+                self.respond(channel, language(channel), Message::NoGuesses, &[])
             ],
 
-            Response::SolutionsNotification(ref solutions) => vec![
-                SlackResponse(self.main_channel.clone(), format!("{}", solutions)),
+            Response::Guesses(ref channel, ref guesses) => {
+                let guesses: String = guesses.iter().map(|g| format_guess(language(channel), g)).collect();
+                vec![self.respond(channel, language(channel), Message::Guesses, &[&guesses])]
+            },
+
+            Response::UnsolutionsNotification(ref unsolutions) => vec![
+                SlackResponse(self.main_channel.clone(), format_unsolutions(main_language, unsolutions), None),
             ],
 
-            Response::Help(ref channel) => vec![
-                SlackResponse(channel.clone(), format!("{}", HELP_TEXT))
+            Response::Leaderboard(ref channel, ref all_time, ref monthly) => vec![
+                self.respond(channel, language(channel), Message::Leaderboard,
+                             &[&format_leaderboard(language(channel), length, all_time),
+                               &format_leaderboard(language(channel), length, monthly)])
             ],
 
+            Response::UserStats(ref channel, Name(ref name), ref stats) => {
+                let average = match stats.average_solve_time {
+                    Some(t) => format_duration(language(channel), t),
+                    None => "-".to_string(),
+                };
+                vec![
+                    self.respond(channel, language(channel), Message::UserStats,
+                                 &[name, &stats.solved, &average, &stats.current_streak, &stats.longest_streak])
+                ]
+            },
+
+            Response::WeeklySummary(ref summary) => vec![
+                self.respond(&self.main_channel, main_language, Message::WeeklySummary,
+                             &[&summary.puzzles, &format_leaderboard(main_language, length, &summary.leaderboard)])
+            ],
+
+            Response::Hint(ref channel, ref revealed, hints) => vec![
+                self.respond(channel, language(channel), Message::Hint, &[&hints, revealed])
+            ],
+
+            Response::NoMoreHints(ref channel) => vec![
+                self.respond(channel, language(channel), Message::NoMoreHints, &[])
+            ],
+
+            Response::AlreadySolved(ref channel) => vec![
+                self.respond(channel, language(channel), Message::AlreadySolved, &[])
+            ],
+
+            Response::GameSelected(ref channel, ref game) => vec![
+                self.respond(channel, language(channel), Message::GameSelected, &[game])
+            ],
+
+            Response::UnknownGame(ref channel, ref game, ref games) => vec![
+                self.respond(channel, language(channel), Message::UnknownGame, &[game, &games.join(", ")])
+            ],
+
+            // The confirmation is in the chosen language, even if it was chosen in the game channel.
+            Response::LanguageSet(ref channel, chosen) => vec![
+                self.respond(channel, chosen, Message::LanguageSet, &[])
+            ],
+
+            Response::UnknownLanguage(ref channel, ref code) => vec![
+                self.respond(channel, language(channel), Message::UnknownLanguage,
+                             &[code, &LANGUAGE_CODES.join(", ")])
+            ],
+
+            Response::ReloadStarted(ref channel) => vec![
+                self.respond(channel, language(channel), Message::ReloadStarted, &[])
+            ],
+
+            Response::ReloadRefused(ref channel, ref reason) => {
+                let message = match *reason {
+                    RefusedReason::NotAdmin => Message::ReloadNotAdmin,
+                    RefusedReason::NotConfigured => Message::ReloadNotConfigured,
+                    RefusedReason::AlreadyRunning => Message::ReloadAlreadyRunning,
+                };
+                vec![self.respond(channel, language(channel), message, &[])]
+            },
+
+            Response::Reloaded(ref channel) => vec![
+                self.respond(channel, language(channel), Message::Reloaded, &[])
+            ],
+
+            Response::ReloadFailed(ref channel, ref reason) => vec![
+                self.respond(channel, language(channel), Message::ReloadFailed, &[reason])
+            ],
+
+            Response::PuzzleRevalidated(ref puzzle, n) => vec![
+                self.respond(&self.main_channel, main_language, Message::PuzzleRevalidated, &[&break_puzzle(puzzle), &n])
+            ],
+
+            Response::Reminder(ref puzzle, n) => vec![
+                self.respond(&self.main_channel, main_language, Message::Reminder, &[&break_puzzle(puzzle), &n])
+            ],
+
+            Response::WordAllowed(ref channel, ref word) => vec![
+                self.respond(channel, language(channel), Message::WordAllowed, &[&word.0])
+            ],
+
+            Response::WordDenied(ref channel, ref word) => vec![
+                self.respond(channel, language(channel), Message::WordDenied, &[&word.0])
+            ],
+
+            Response::EditRefused(ref channel, ref reason) => {
+                let message = match *reason {
+                    RefusedReason::NotAdmin => Message::EditNotAdmin,
+                    RefusedReason::NotConfigured => Message::EditNotConfigured,
+                    RefusedReason::AlreadyRunning => Message::EditWhileReloading,
+                };
+                vec![self.respond(channel, language(channel), message, &[])]
+            },
+
+            Response::EditFailed(ref channel, ref reason) => vec![
+                self.respond(channel, language(channel), Message::EditFailed, &[reason])
+            ],
+
+            Response::Help(ref channel) => {
+                let help = self.respond(channel, language(channel), Message::Help, &[]);
+                let attachment = Attachment {
+                    title: text(language(channel), Message::HelpTitle, &[]),
+                    text: help.1.trim().to_string(),
+                    fields: vec![],
+                };
+                vec![help.with_attachment(attachment)]
+            },
+
             Response::Dual(ref first, ref second) => {
-                let mut f = self.serialize(&first);
-                let mut s = self.serialize(&second);
+                let mut f = self.serialize(&first, user_language);
+                let mut s = self.serialize(&second, user_language);
 
                 f.append(&mut s);
                 f
             },
 
-            //Response::Triple(ref first, ref second, ref third) => {
-            //    let mut f = self.serialize(&first);
-            //    let mut s = self.serialize(&second);
-            //    let mut t = self.serialize(&third);
-            //
-            //    f.append(&mut s);
-            //    f.append(&mut t);
-            //    f
-            //},
+            Response::Multiple(ref responses) => {
+                responses.iter().flat_map(|r| self.serialize(r, user_language)).collect()
+            },
         }
     }
 
-    fn serialize_invalid_command(&self, &InvalidCommand(ref channel,
-                                                        ref text,
-                                                        ref reason): &InvalidCommand) -> Vec<SlackResponse> {
-        let reason_string = match *reason {
-            InvalidCommandReason::UnknownCommand => "okänt kommando!",
-            InvalidCommandReason::WrongNoOfParameters => "fel antal parametrar!"
+    fn serialize_invalid_command(&self,
+                                 &InvalidCommand(ref channel, ref command, ref reason): &InvalidCommand,
+                                 user_language: Option<Language>) -> Vec<SlackResponse> {
+        let language = self.language_for(channel, user_language);
+        let reason = match *reason {
+            InvalidCommandReason::UnknownCommand => Message::UnknownCommand,
+            InvalidCommandReason::WrongNoOfParameters => Message::WrongNoOfParameters,
         };
         vec![
-            SlackResponse(channel.clone(),
-                          format!("Ogiltigt kommando '{}'. Orsak: {}", text, reason_string))
+            self.respond(channel, language, Message::InvalidCommand, &[command, &text(language, reason, &[])])
         ]
     }
 }
 
-pub fn new_responder(main_channel: &Channel) -> Box<Respond> {
-    Box::new(SlackResponder { main_channel: main_channel.clone() })
+pub fn new_responder(main_channel: &Channel, language: Language, puzzle_length: usize) -> Box<Respond> {
+    Box::new(SlackResponder { main_channel: main_channel.clone(), language: language, puzzle_length: puzzle_length })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use types::*;
-    use std::collections::HashMap;
+    use messages::{Language, LANGUAGE_CODES};
+    use std::collections::{HashMap, HashSet};
     use std::iter::FromIterator;
 
     #[test]
-    #[should_panic]
-    fn test_break_puzzle_panics() {
-        break_puzzle(&Puzzle("ABC".into()));
+    fn format_duration_test() {
+        assert_eq!(format_duration(Language::Swedish, 30), "mindre än en minut");
+        assert_eq!(format_duration(Language::English, 30), "less than a minute");
+        assert_eq!(format_duration(Language::Swedish, 12 * 60 + 5), "12 min");
+        assert_eq!(format_duration(Language::Swedish, 2 * 60 * 60), "2 h");
+        assert_eq!(format_duration(Language::Swedish, 2 * 60 * 60 + 5 * 60), "2 h 5 min");
+    }
+
+    #[test]
+    fn solutions_ordered_by_time_test() {
+        let solutions = SolutionsMap(HashMap::from_iter(vec![
+            (Word("DATORSPEL".into()), vec!["foo".to_string(), "bar".to_string()]),
+            (Word("SPELDATOR".into()), vec!["baz".to_string()]),
+            (Word("PELDATORS".into()), vec![]),
+        ].into_iter()));
+        let times = HashMap::from_iter(vec![
+            (Name("foo".into()), 30 * 60),
+            (Name("bar".into()), 10 * 60),
+            (Name("baz".into()), 20 * 60),
+        ].into_iter());
+
+        let hints = HashMap::from_iter(vec![(Name("foo".into()), 2)].into_iter());
+
+        let rows = solution_rows(Language::Swedish, &solutions, &times, &hints);
+        assert_eq!(format_solutions(Language::Swedish, &rows, &String::new(), None),
+                   "*Gårdagens lösningar:*\n\
+                    *DATORSPEL*: bar (10 min), foo (30 min, 2 ledtrådar)\n\
+                    *SPELDATOR*: baz (20 min)\n\
+                    *PELDATORS*: \n");
+
+        let attachment = solutions_attachment(Language::Swedish, &rows, &"0123".to_string(), None);
+        assert_eq!(attachment.title, "Gårdagens lösningar");
+        assert_eq!(attachment.text,
+                   "```DATORSPEL  bar (10 min), foo (30 min, 2 ledtrådar)\n\
+                    SPELDATOR  baz (20 min)\n\
+                    PELDATORS  ```");
+        assert_eq!(attachment.fields[0], Field { title: "Salt".into(), value: "0123".into(), short: true });
+    }
+
+    #[test]
+    fn puzzle_grid_test() {
+        assert_eq!(puzzle_grid(&Puzzle("ABCDEFGHI".into())), "```A B C\nD E F\nG H I```");
+        assert_eq!(puzzle_grid(&Puzzle("ABCDEFGHIJ".into())), "```A B C\nD E F\nG H\nI J```");
+    }
+
+    #[test]
+    fn attachments_test() {
+        let main_channel = Channel("C0123".into());
+        let responder = new_responder(&main_channel, Language::Swedish, DEFAULT_PUZZLE_LENGTH);
+
+        let set = responder.serialize(&Response::SetPuzzle(main_channel.clone(), Puzzle("ABCDEFGHI".into()), 2), None);
+        assert_eq!(set[0].1, "Dagens nia är satt till ABC DEF GHI.\nDet finns 2 lösningar.");
+        assert_eq!(set[0].2, Some(Attachment {
+            title: "Ny nia".into(),
+            text: "```A B C\nD E F\nG H I```".into(),
+            fields: vec![Field { title: "Lösningar".into(), value: "2".into(), short: true }],
+        }));
+
+        let notification = responder.serialize(&Response::Notification(Name("foo".into()), "abcdef".into(), None, 0), None);
+        assert_eq!(notification[0].1, "foo löste nian: abcdef");
+        assert_eq!(notification[0].2, Some(Attachment {
+            title: "foo löste nian!".into(),
+            text: "".into(),
+            fields: vec![Field { title: "Hash".into(), value: "abcdef".into(), short: false }],
+        }));
+
+        let incorrect = responder.serialize(&Response::NoPuzzleSet(main_channel.clone()), None);
+        assert_eq!(incorrect[0].2, None);
     }
 
     #[test]
     fn test_break_puzzle() {
         assert_eq!(break_puzzle(&Puzzle("ABCDEFGHI".into())), "ABC DEF GHI".to_string());
         assert_eq!(break_puzzle(&Puzzle("ABÅÄÖCDEF".into())), "ABÅ ÄÖC DEF".to_string());
+        assert_eq!(break_puzzle(&Puzzle("ABCDEFGHIJ".into())), "ABC DEF GH IJ".to_string());
+        assert_eq!(break_puzzle(&Puzzle("ABCDEFG".into())), "ABC DE FG".to_string());
+        assert_eq!(break_puzzle(&Puzzle("ABCD".into())), "AB CD".to_string());
+        assert_eq!(break_puzzle(&Puzzle("ABC".into())), "ABC".to_string());
+        assert_eq!(break_puzzle(&Puzzle("".into())), "".to_string());
     }
 
     struct TestEvent {
@@ -253,7 +946,7 @@ mod tests {
         let tests = vec![
             ResponderTest {
                 description: "Solution notification response to main channel",
-                response: Response::Notification(Name("erike".into()), "abcdef".into()),
+                response: Response::Notification(Name("erike".into()), "abcdef".into(), None, 0),
                 expected: vec![
                     TestEvent {
                         channel: main_channel_id.clone(),
@@ -263,6 +956,132 @@ mod tests {
                 ],
             },
 
+            ResponderTest {
+                description: "Solution notification with solve time",
+                response: Response::Notification(Name("erike".into()), "abcdef".into(), Some(12 * 60 + 17), 0),
+                expected: vec![
+                    TestEvent {
+                        channel: main_channel_id.clone(),
+                        has_texts: vec!["erike löste nian efter 12 min", "abcdef"],
+                        has_not_texts: vec!["ledtråd"],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Solution notification with hints",
+                response: Response::Notification(Name("erike".into()), "abcdef".into(), Some(12 * 60), 2),
+                expected: vec![
+                    TestEvent {
+                        channel: main_channel_id.clone(),
+                        has_texts: vec!["erike löste nian efter 12 min med 2 ledtrådar", "abcdef"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Game selected",
+                response: Response::GameSelected(Channel("D0".into()), "konsulatet".into()),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["konsulatet"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Unknown game",
+                response: Response::UnknownGame(Channel("D0".into()), "foo".into(),
+                                                vec!["konsulatet".into(), "general".into()]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["foo", "konsulatet, general"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Word allowed",
+                response: Response::WordAllowed(Channel("D0".into()), Word("GALLTJUTA".into())),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["GALLTJUTA är nu tillåtet"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Dictionary edit refused",
+                response: Response::EditRefused(Channel("D0".into()), RefusedReason::NotAdmin),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["administratörer"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Confirm setting a puzzle",
+                response: Response::ConfirmSetPuzzle(Channel("C0".into()), Puzzle("TAGALLTJU".into()),
+                                                     ConfirmReason::SetRecently(12 * 60)),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("C0".into()),
+                        has_texts: vec!["för 12 min sedan", "!setnian TAG ALL TJU igen"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Roles",
+                response: Response::Roles(Channel("C0".into()), Role::Player, vec![
+                    (Name("alice".into()), Role::Admin),
+                    (Name("bob".into()), Role::Setter),
+                ]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("C0".into()),
+                        has_texts: vec!["alice: administratör\nbob: sättare\n", "Alla andra är spelare."],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Queue",
+                response: Response::Queue(Channel("D0".into()),
+                                          vec![Puzzle("TAGALLTJU".into()), Puzzle("PELDATORS".into())]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["1. TAG ALL TJU", "2. PEL DAT ORS"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
+            ResponderTest {
+                description: "Hint",
+                response: Response::Hint(Channel("D0".into()), "DAT".into(), 3),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["DAT", "Ledtråd 3"],
+                        has_not_texts: vec![],
+                    },
+                ],
+            },
+
             ResponderTest {
                 description: "Incorrect solution response to user",
                 response: Response::IncorrectSolution(Channel("D0".into()), Word("FOO".into()),
@@ -271,6 +1090,19 @@ mod tests {
                     TestEvent {
                         channel: Channel("D0".into()),
                         has_texts: vec!["FOO", "inte"],
+                        has_not_texts: vec!["Menade du"],
+                    }
+                ],
+            },
+
+            ResponderTest {
+                description: "Incorrect solution with near misses",
+                response: Response::IncorrectSolution(Channel("D0".into()), Word("DATORSPEX".into()),
+                    Reason::NearMisses(vec![Word("DATORSPEL".into()), Word("DATORSPAR".into())])),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["DATORSPEX", "inte", "Menade du DATORSPEL eller DATORSPAR?"],
                         has_not_texts: vec![],
                     }
                 ],
@@ -279,11 +1111,11 @@ mod tests {
             ResponderTest {
                 description: "Invalid puzzle",
                 response: Response::InvalidPuzzle(Channel("C0".into()), Puzzle("PUZZLE".into()),
-                                                  InvalidPuzzleReason::NotNineCharacters),
+                                                  InvalidPuzzleReason::WrongLength(9)),
                 expected: vec![
                     TestEvent {
                         channel: Channel("C0".into()),
-                        has_texts: vec!["PUZZLE"],
+                        has_texts: vec!["PUZZLE", "inte nio tecken"],
                         has_not_texts: vec![],
                     }
                 ],
@@ -365,7 +1197,7 @@ mod tests {
                 description: "Composite responses",
                 response: Response::Dual(
                     Box::new(Response::CorrectSolution(Channel("D0".into()), Word("FOO".into()))),
-                    Box::new(Response::Notification(Name("erike".into()), "abcdef".into()))),
+                    Box::new(Response::Notification(Name("erike".into()), "abcdef".into(), None, 0))),
                 expected: vec![
                     TestEvent {
                         channel: Channel("D0".into()),
@@ -380,30 +1212,30 @@ mod tests {
                 ]
             },
 
-            //ResponderTest {
-            //    description: "Triple responses",
-            //    response: Response::Triple(
-            //        Box::new(Response::CorrectSolution(Channel("D0".into()), Word("FOO".into()))),
-            //        Box::new(Response::Notification(Name("erike".into()), "abcdef".into())),
-            //        Box::new(Response::GetPuzzle(Channel("C0".into()), Puzzle("PUZZLEABC".into()), 1))),
-            //    expected: vec![
-            //        TestEvent {
-            //            channel: Channel("D0".into()),
-            //            has_texts: vec!["FOO"],
-            //            has_not_texts: vec![],
-            //        },
-            //        TestEvent {
-            //            channel: Channel("C0123".into()),
-            //            has_texts: vec!["erike", "abcdef"],
-            //            has_not_texts: vec![],
-            //        },
-            //        TestEvent {
-            //            channel: Channel("C0".into()),
-            //            has_texts: vec!["PUZ ZLE ABC"],
-            //            has_not_texts: vec!["1"],
-            //        },
-            //    ]
-            //},
+            ResponderTest {
+                description: "Multiple responses",
+                response: Response::Multiple(vec![
+                    Response::CorrectSolution(Channel("D0".into()), Word("FOO".into())),
+                    Response::Notification(Name("erike".into()), "abcdef".into(), None, 0),
+                    Response::GetPuzzle(Channel("C0".into()), Puzzle("PUZZLEABC".into()), 1)]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["FOO"],
+                        has_not_texts: vec![],
+                    },
+                    TestEvent {
+                        channel: Channel("C0123".into()),
+                        has_texts: vec!["erike", "abcdef"],
+                        has_not_texts: vec![],
+                    },
+                    TestEvent {
+                        channel: Channel("C0".into()),
+                        has_texts: vec!["PUZ ZLE ABC"],
+                        has_not_texts: vec!["1"],
+                    },
+                ]
+            },
 
             ResponderTest {
                 description: "Help command",
@@ -419,7 +1251,7 @@ mod tests {
 
             ResponderTest {
                 description: "Incorrect solution, because it's not nine characters",
-                response: Response::IncorrectSolution(Channel("D0".into()), Word("FOO".into()), Reason::NotNineCharacters),
+                response: Response::IncorrectSolution(Channel("D0".into()), Word("FOO".into()), Reason::WrongLength(9)),
                 expected: vec![
                     TestEvent {
                         channel: Channel("D0".into()),
@@ -429,6 +1261,18 @@ mod tests {
                 ]
             },
 
+            ResponderTest {
+                description: "Incorrect solution, because it's not ten characters",
+                response: Response::IncorrectSolution(Channel("D0".into()), Word("FOO".into()), Reason::WrongLength(10)),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["FOO", "inte tio tecken"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
             ResponderTest {
                 description: "Incorrect solution, because it doesn't match todays puzzle",
                 response: Response::IncorrectSolution(
@@ -444,17 +1288,173 @@ mod tests {
                 ]
             },
 
+            ResponderTest {
+                description: "Set an unsolution",
+                response: Response::SetUnsolution(Channel("D0".into()), "Hello".into()),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["Hello", "Olösning"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Get unsolutions",
+                response: Response::GetUnsolutions(Channel("D0".into()), vec!["Hello".into(), "world".into()]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["Hello", "world"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Notify main channel with unsolutions",
+                response: Response::UnsolutionsNotification(
+                    UnsolutionsMap(HashMap::from_iter(vec![
+                        (Name("foo".into()), vec!["FOO".to_string()]),
+                        (Name("bar".into()), vec!["BAR".to_string(), "BAZ".to_string()]),
+                        ].into_iter()))),
+                expected: vec![
+                    TestEvent {
+                        channel: main_channel_id.clone(),
+                        has_texts: vec!["foo", "FOO", "bar", "BAR", "BAZ"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Leaderboard",
+                response: Response::Leaderboard(Channel("C0".into()),
+                                                vec![(Name("foo".into()), 17), (Name("bar".into()), 3)],
+                                                vec![(Name("bar".into()), 2)]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("C0".into()),
+                        has_texts: vec!["1. foo: 17", "2. bar: 3", "1. bar: 2", "totalt", "månad"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "User statistics",
+                response: Response::UserStats(Channel("C0".into()), Name("foo".into()), UserStats {
+                    solved: 17,
+                    average_solve_time: Some(65 * 60),
+                    current_streak: 3,
+                    longest_streak: 7,
+                }),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("C0".into()),
+                        has_texts: vec!["foo", "17", "1 h 5 min", "svit: 3", "svit: 7"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Weekly summary to the main channel",
+                response: Response::WeeklySummary(WeeklySummary {
+                    puzzles: 6,
+                    leaderboard: vec![(Name("foo".into()), 5)],
+                }),
+                expected: vec![
+                    TestEvent {
+                        channel: main_channel_id.clone(),
+                        has_texts: vec!["6 nior", "1. foo: 5"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
             ResponderTest {
                 description: "Notify main channel with solutions",
                 response: Response::SolutionsNotification(
                     SolutionsMap(HashMap::from_iter(vec![
                         (Word("DATORSPEL".into()), vec!["foo".to_string(), "bar".to_string()]),
                         (Word("SPELDATOR".into()), vec![]),
-                        ].into_iter()))),
+                        ].into_iter())),
+                    HashMap::from_iter(vec![(Name("bar".into()), 12 * 60)].into_iter()),
+                    HashMap::from_iter(vec![(Name("foo".into()), 1)].into_iter()),
+                    "0123456789abcdef".into(),
+                    None),
+                expected: vec![
+                    TestEvent {
+                        channel: main_channel_id.clone(),
+                        has_texts: vec!["foo (1 ledtråd)", "bar (12 min)", "DATORSPEL", "SPELDATOR", "0123456789abcdef"],
+                        has_not_texts: vec!["gissningar"],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Solutions without a salt",
+                response: Response::SolutionsNotification(
+                    SolutionsMap(HashMap::from_iter(vec![
+                        (Word("DATORSPEL".into()), vec!["foo".to_string()]),
+                        ].into_iter())),
+                    HashMap::new(),
+                    HashMap::new(),
+                    "".into(),
+                    None),
+                expected: vec![
+                    TestEvent {
+                        channel: main_channel_id.clone(),
+                        has_texts: vec!["DATORSPEL"],
+                        has_not_texts: vec!["Saltet"],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Solutions with the stats of the guesses",
+                response: Response::SolutionsNotification(
+                    SolutionsMap(HashMap::from_iter(vec![
+                        (Word("DATORSPEL".into()), vec!["foo".to_string()]),
+                        ].into_iter())),
+                    HashMap::new(),
+                    HashMap::new(),
+                    "".into(),
+                    Some(GuessStats { guesses: 17, most_common_wrong: Some((Word("SPELDATOR".into()), 3)) })),
                 expected: vec![
                     TestEvent {
                         channel: main_channel_id.clone(),
-                        has_texts: vec!["foo", "bar", "DATORSPEL", "SPELDATOR"],
+                        has_texts: vec!["Antal gissningar: 17", "Vanligaste felgissningen: SPELDATOR (3 gånger)"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Guesses on the current puzzle",
+                response: Response::Guesses(Channel("D0".into()), vec![
+                    Guess { word: Word("DATORSPLE".into()), reason: Some(Reason::NotInDictionary), time: 0 },
+                    Guess { word: Word("DATORSPEL".into()), reason: None, time: 0 },
+                ]),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("D0".into()),
+                        has_texts: vec!["DATORSPLE: finns inte i SAOL", "DATORSPEL: rätt!"],
+                        has_not_texts: vec![],
+                    }
+                ]
+            },
+
+            ResponderTest {
+                description: "Verified hash",
+                response: Response::HashVerified(Channel("C0".into()), Puzzle("DATORSPEL".into()),
+                                                 Name("foo".into()), Word("SPELDATOR".into())),
+                expected: vec![
+                    TestEvent {
+                        channel: Channel("C0".into()),
+                        has_texts: vec!["foo", "SPELDATOR", "DAT ORS PEL"],
                         has_not_texts: vec![],
                     }
                 ]
@@ -462,8 +1462,8 @@ mod tests {
         ];
 
         for t in tests {
-            let responder = new_responder(&main_channel_id);
-            let slack_responses = responder.serialize(&t.response);
+            let responder = new_responder(&main_channel_id, Language::Swedish, DEFAULT_PUZZLE_LENGTH);
+            let slack_responses = responder.serialize(&t.response, None);
 
             assert_eq!(slack_responses.len(), t.expected.len(), "{}", t.description);
 
@@ -481,6 +1481,265 @@ mod tests {
         }
     }
 
+    /// The index of each kind of response. A new kind of response doesn't compile until it is listed
+    /// here, and `translations_test` fails until it has an example.
+    fn response_kind(r: &Response) -> usize {
+        match *r {
+            Response::GetPuzzle(..) => 0,
+            Response::NoPuzzleSet(..) => 1,
+            Response::SetPuzzle(..) => 2,
+            Response::InvalidPuzzle(..) => 3,
+            Response::ConfirmSetPuzzle(..) => 4,
+            Response::PuzzleRestored(..) => 5,
+            Response::NothingToUndo(..) => 6,
+            Response::NoPuzzleToGenerate(..) => 7,
+            Response::PuzzleQueued(..) => 8,
+            Response::Queue(..) => 9,
+            Response::RemovedFromQueue(..) => 10,
+            Response::NoSuchQueueEntry(..) => 11,
+            Response::QueueEmpty(..) => 12,
+            Response::PermissionDenied(..) => 13,
+            Response::RoleSet(..) => 14,
+            Response::UnknownRole(..) => 15,
+            Response::Roles(..) => 16,
+            Response::CorrectSolution(..) => 17,
+            Response::Notification(..) => 18,
+            Response::SolutionsNotification(..) => 19,
+            Response::HashVerified(..) => 20,
+            Response::HashNotYetVerifiable(..) => 21,
+            Response::UnknownHash(..) => 22,
+            Response::IncorrectSolution(..) => 23,
+            Response::SetUnsolution(..) => 24,
+            Response::GetUnsolutions(..) => 25,
+            Response::UnsolutionsNotification(..) => 26,
+            Response::Leaderboard(..) => 27,
+            Response::UserStats(..) => 28,
+            Response::WeeklySummary(..) => 29,
+            Response::Hint(..) => 30,
+            Response::NoMoreHints(..) => 31,
+            Response::GameSelected(..) => 32,
+            Response::LanguageSet(..) => 33,
+            Response::UnknownLanguage(..) => 34,
+            Response::ReloadStarted(..) => 35,
+            Response::ReloadRefused(..) => 36,
+            Response::Reloaded(..) => 37,
+            Response::ReloadFailed(..) => 38,
+            Response::WordAllowed(..) => 39,
+            Response::WordDenied(..) => 40,
+            Response::EditRefused(..) => 41,
+            Response::EditFailed(..) => 42,
+            Response::Reminder(..) => 43,
+            Response::PuzzleRevalidated(..) => 44,
+            Response::UnknownGame(..) => 45,
+            Response::Help(..) => 46,
+            Response::Dual(..) => 47,
+            Response::Multiple(..) => 48,
+            Response::SpoilerWarning(..) => 49,
+            Response::SpoilerReport(..) => 50,
+            Response::Guesses(..) => 51,
+            Response::TooManyGuesses(..) => 52,
+            Response::BruteForceReport(..) => 53,
+            Response::UnsolutionsPrivateOnly(..) => 54,
+            Response::AlreadySolved(..) => 55,
+            Response::ConfiguredAdmin(..) => 56,
+            Response::ConfirmGeneratePuzzle(..) => 57,
+            Response::ConfirmNextPuzzle(..) => 58,
+        }
+    }
+
+    const RESPONSE_KINDS: usize = 59;
+
+    /// Examples of every kind of response, with each case that has its own message.
+    fn example_responses() -> Vec<Response> {
+        let c = Channel("D0".into());
+        let p = Puzzle("DATORSPEL".into());
+        let foo = Name("foo".into());
+        let w = Word("SPELDATOR".into());
+        vec![
+            Response::GetPuzzle(c.clone(), p.clone(), 1),
+            Response::GetPuzzle(c.clone(), p.clone(), 2),
+            Response::NoPuzzleSet(c.clone()),
+            Response::SetPuzzle(c.clone(), p.clone(), 1),
+            Response::SetPuzzle(c.clone(), p.clone(), 2),
+            Response::InvalidPuzzle(c.clone(), p.clone(), InvalidPuzzleReason::NotInDictionary),
+            Response::InvalidPuzzle(c.clone(), p.clone(), InvalidPuzzleReason::WrongLength(9)),
+            Response::ConfirmSetPuzzle(c.clone(), p.clone(), ConfirmReason::SetRecently(30)),
+            Response::ConfirmSetPuzzle(c.clone(), p.clone(), ConfirmReason::NoSolvers),
+            Response::ConfirmGeneratePuzzle(c.clone(), ConfirmReason::SetRecently(30)),
+            Response::ConfirmNextPuzzle(c.clone(), ConfirmReason::NoSolvers),
+            Response::PuzzleRestored(c.clone(), p.clone(), 1),
+            Response::PuzzleRestored(c.clone(), p.clone(), 2),
+            Response::NothingToUndo(c.clone()),
+            Response::NoPuzzleToGenerate(c.clone(), 9),
+            Response::PuzzleQueued(c.clone(), p.clone(), 1),
+            Response::Queue(c.clone(), vec![]),
+            Response::Queue(c.clone(), vec![p.clone()]),
+            Response::RemovedFromQueue(c.clone(), p.clone()),
+            Response::NoSuchQueueEntry(c.clone(), "3".into()),
+            Response::QueueEmpty(c.clone()),
+            Response::PermissionDenied(c.clone(), Role::Player),
+            Response::PermissionDenied(c.clone(), Role::Setter),
+            Response::PermissionDenied(c.clone(), Role::Admin),
+            Response::RoleSet(c.clone(), foo.clone(), Role::Setter),
+            Response::UnknownRole(c.clone(), "king".into()),
+            Response::ConfiguredAdmin(c.clone(), foo.clone()),
+            Response::Roles(c.clone(), Role::Player, vec![]),
+            Response::Roles(c.clone(), Role::Player, vec![(foo.clone(), Role::Admin)]),
+            Response::CorrectSolution(c.clone(), w.clone()),
+            Response::Notification(foo.clone(), "abcdef".into(), Some(30), 1),
+            Response::SolutionsNotification(SolutionsMap(HashMap::from_iter(vec![
+                (w.clone(), vec!["foo".to_string()]),
+            ].into_iter())), HashMap::new(), HashMap::new(), "0123".into(), None),
+            Response::HashVerified(c.clone(), p.clone(), foo.clone(), w.clone()),
+            Response::HashNotYetVerifiable(c.clone()),
+            Response::UnknownHash(c.clone(), "abcdef".into()),
+            Response::SpoilerWarning(c.clone(), Channel("C0".into()), w.clone()),
+            Response::SpoilerReport(c.clone(), foo.clone(), Channel("C0".into())),
+            Response::TooManyGuesses(c.clone(), 30),
+            Response::BruteForceReport(c.clone(), foo.clone()),
+            Response::IncorrectSolution(c.clone(), w.clone(), Reason::NotInDictionary),
+            Response::IncorrectSolution(c.clone(), w.clone(), Reason::NearMisses(vec![w.clone(), w.clone()])),
+            Response::IncorrectSolution(c.clone(), w.clone(), Reason::WrongLength(9)),
+            Response::IncorrectSolution(c.clone(), w.clone(), Reason::NonMatchingWord(p.clone(), "A".into(), "B".into())),
+            Response::SetUnsolution(c.clone(), "BAZ".into()),
+            Response::GetUnsolutions(c.clone(), vec![]),
+            Response::GetUnsolutions(c.clone(), vec!["BAZ".into()]),
+            Response::UnsolutionsPrivateOnly(Channel("C0".into())),
+            Response::Guesses(c.clone(), vec![]),
+            Response::Guesses(c.clone(), vec![
+                Guess { word: w.clone(), reason: None, time: 0 },
+                Guess { word: w.clone(), reason: Some(Reason::NotInDictionary), time: 0 },
+                Guess { word: w.clone(), reason: Some(Reason::WrongLength(9)), time: 0 },
+                Guess { word: w.clone(), reason: Some(Reason::NonMatchingWord(p.clone(), "A".into(), "B".into())), time: 0 },
+            ]),
+            Response::UnsolutionsNotification(UnsolutionsMap(HashMap::from_iter(vec![
+                (foo.clone(), vec!["BAZ".to_string()]),
+            ].into_iter()))),
+            Response::Leaderboard(c.clone(), vec![], vec![]),
+            Response::UserStats(c.clone(), foo.clone(), UserStats {
+                solved: 1,
+                average_solve_time: Some(30),
+                current_streak: 1,
+                longest_streak: 1,
+            }),
+            Response::WeeklySummary(WeeklySummary { puzzles: 1, leaderboard: vec![] }),
+            Response::Hint(c.clone(), "S".into(), 1),
+            Response::NoMoreHints(c.clone()),
+            Response::AlreadySolved(c.clone()),
+            Response::GameSelected(c.clone(), "general".into()),
+            Response::LanguageSet(c.clone(), Language::English),
+            Response::UnknownLanguage(c.clone(), "fi".into()),
+            Response::ReloadStarted(c.clone()),
+            Response::ReloadRefused(c.clone(), RefusedReason::NotAdmin),
+            Response::ReloadRefused(c.clone(), RefusedReason::NotConfigured),
+            Response::ReloadRefused(c.clone(), RefusedReason::AlreadyRunning),
+            Response::Reloaded(c.clone()),
+            Response::ReloadFailed(c.clone(), "error".into()),
+            Response::WordAllowed(c.clone(), w.clone()),
+            Response::WordDenied(c.clone(), w.clone()),
+            Response::EditRefused(c.clone(), RefusedReason::NotAdmin),
+            Response::EditRefused(c.clone(), RefusedReason::NotConfigured),
+            Response::EditRefused(c.clone(), RefusedReason::AlreadyRunning),
+            Response::EditFailed(c.clone(), "error".into()),
+            Response::Reminder(p.clone(), 1),
+            Response::PuzzleRevalidated(p.clone(), 1),
+            Response::UnknownGame(c.clone(), "tian".into(), vec!["general".into()]),
+            Response::Help(c.clone()),
+            Response::Dual(Box::new(Response::NoPuzzleSet(c.clone())), Box::new(Response::NoMoreHints(c.clone()))),
+            Response::Multiple(vec![Response::NoPuzzleSet(c.clone())]),
+        ]
+    }
+
+    #[test]
+    fn translations_test() {
+        let main_channel = Channel("C0".into());
+        let responses = example_responses();
+        let kinds: HashSet<usize> = responses.iter().map(response_kind).collect();
+        assert_eq!(kinds.len(), RESPONSE_KINDS, "Every kind of response needs an example");
+
+        for response in &responses {
+            let mut texts = vec![];
+            for code in LANGUAGE_CODES {
+                let language = Language::from_code(code).unwrap();
+                let text: Vec<String> = new_responder(&main_channel, language, DEFAULT_PUZZLE_LENGTH)
+                    .serialize(response, None)
+                    .into_iter()
+                    .map(|SlackResponse(_, text, _)| text)
+                    .collect();
+                for t in &text {
+                    assert!(!t.trim().is_empty(), "{:?} has no text in {}", response, code);
+                    for i in 0..10 {
+                        let placeholder = format!("{{{}}}", i);
+                        assert!(!t.contains(&placeholder), "{:?} has {} in {}: {}", response, placeholder, code, t);
+                    }
+                    for placeholder in &["{nia}", "{nian}", "{Nian}", "{nior}", "{length}"] {
+                        assert!(!t.contains(placeholder), "{:?} has {} in {}: {}", response, placeholder, code, t);
+                    }
+                }
+                texts.push(text);
+            }
+
+            // A bare puzzle reads the same in all languages, and the confirmation of a chosen
+            // language is always in that language.
+            match *response {
+                Response::GetPuzzle(_, _, 1) | Response::LanguageSet(..) => {},
+                _ => assert!(texts[0] != texts[1], "{:?} is not translated: {:?}", response, texts[1]),
+            }
+        }
+    }
+
+    #[test]
+    fn user_language_test() {
+        let main_channel = Channel("C0".into());
+        let private = Channel("D0".into());
+        let responder = new_responder(&main_channel, Language::Swedish, DEFAULT_PUZZLE_LENGTH);
+
+        // Private messages are in the user's language, but the game channel is in the game's.
+        let response = Response::Dual(Box::new(Response::CorrectSolution(private.clone(), Word("DATORSPEL".into()))),
+                                      Box::new(Response::Notification(Name("foo".into()), "abcdef".into(), None, 0)));
+        let texts: Vec<(Channel, String)> = responder.serialize(&response, Some(Language::English)).into_iter()
+            .map(|SlackResponse(channel, text, _)| (channel, text))
+            .collect();
+        assert_eq!(texts, vec![
+            (private.clone(), "The word DATORSPEL is correct!".into()),
+            (main_channel.clone(), "foo löste nian: abcdef".into()),
+        ]);
+        assert_eq!(responder.serialize(&Response::NoPuzzleSet(main_channel.clone()), Some(Language::English)),
+                   vec![SlackResponse(main_channel.clone(), "Nian är inte satt!".into(), None)]);
+
+        let r = InvalidCommand(private.clone(), "!nosuchcommand".into(), InvalidCommandReason::UnknownCommand);
+        assert_eq!(responder.serialize_invalid_command(&r, Some(Language::English)),
+                   vec![SlackResponse(private.clone(), "Invalid command '!nosuchcommand'. Reason: unknown command!".into(), None)]);
+    }
+
+    #[test]
+    fn puzzle_length_test() {
+        let main_channel = Channel("C0".into());
+        let private = Channel("D0".into());
+        let responder = new_responder(&main_channel, Language::Swedish, 7);
+        let puzzle = Puzzle("ABCDEFG".into());
+
+        // The messages of a game name its puzzles by their length.
+        let texts = |response: &Response, language: Option<Language>| -> Vec<String> {
+            responder.serialize(response, language).into_iter().map(|SlackResponse(_, text, _)| text).collect()
+        };
+        assert_eq!(texts(&Response::SetPuzzle(main_channel.clone(), puzzle.clone(), 1), None),
+                   vec!["Dagens sjua är satt till ABC DE FG.".to_string()]);
+        assert_eq!(texts(&Response::Notification(Name("foo".into()), "abcdef".into(), None, 0), None),
+                   vec!["foo löste sjuan: abcdef".to_string()]);
+        assert_eq!(texts(&Response::Reminder(puzzle.clone(), 2), None),
+                   vec!["Ingen har löst sjuan ABC DE FG än! Den har 2 lösningar.".to_string()]);
+        assert!(texts(&Response::Help(private.clone()), None)[0].contains("Varje dag får man sju bokstäver"));
+        assert!(texts(&Response::Help(private.clone()), Some(Language::English))[0].contains("there are seven letters"));
+
+        let attachment = responder.serialize(&Response::GetPuzzle(main_channel.clone(), puzzle.clone(), 1), None)
+            .remove(0).2.unwrap();
+        assert_eq!(attachment.title, "Dagens sjua");
+        let attachment = responder.serialize(&Response::Notification(Name("foo".into()), "abcdef".into(), None, 0), None)
+            .remove(0).2.unwrap();
+        assert_eq!(attachment.title, "foo löste sjuan!");
+    }
+
     #[test]
     fn invalid_command_test() {
         let expected = vec![
@@ -492,10 +1751,10 @@ mod tests {
         ];
 
         let main_channel_id = Channel("C0123".into());
-        let responder = new_responder(&main_channel_id);
+        let responder = new_responder(&main_channel_id, Language::Swedish, DEFAULT_PUZZLE_LENGTH);
 
         let r = InvalidCommand(Channel("C0".into()), "!nosuchcommand".into(), InvalidCommandReason::UnknownCommand);
-        let slack_responses = responder.serialize_invalid_command(&r);
+        let slack_responses = responder.serialize_invalid_command(&r, None);
 
         assert_eq!(slack_responses.len(), expected.len(), "{}", "Unexpected response length for invalid command");
 
@@ -514,30 +1773,6 @@ mod tests {
 
 
 
-//
-//    ResponderTest {
-//        "Set an unsolution",
-//        SetUnsolutionResponse(Channel("D0"), utf8("Hello")),
-//        expected: vec![
-//            TestEvent {
-//                channel: Channel("D0"), "Hello", "Olösning")]),
-//
-//    ResponderTest {
-//        "Get an unsolution",
-//        GetUnsolutionsResponse(Channel("D0"), [utf8("Hello"), utf8("world")]),
-//        expected: vec![
-//            TestEvent {
-//                channel: Channel("D0"), "Hello", "world")]),
-//
-//    ResponderTest {
-//        "Notification response",
-//        UnsolutionNotificationResponse(Dict{UserId, UnsolutionList}(
-//            UserId("U0") => [utf8("FOO")],
-//            UserId("U1") => [utf8("BAR"), utf8("BAZ")])),
-//        expected: vec![
-//            TestEvent {
-//                channel: main_channel_id, "<@U0>", "FOO", "<@U1>", "BAR", "BAZ")]),
-//
 //    ResponderTest {
 //        "Previous solutions response",
 //        PreviousSolutionsResponse(Dict{Word, Vector{UserId}}(
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use types::Name;
+
+/// What a user may do. Each role may do everything the roles before it may.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash)]
+pub enum Role {
+    /// May solve puzzles.
+    Player,
+    /// May also set puzzles.
+    Setter,
+    /// May also run admin commands, like changing the roles and the dictionary.
+    Admin,
+}
+
+pub const ROLE_NAMES: &'static [&'static str] = &["player", "setter", "admin"];
+
+impl Role {
+    /// The role with the name used in the config and in `!roll`.
+    pub fn from_name(name: &str) -> Option<Role> {
+        match name {
+            "player" => Some(Role::Player),
+            "setter" => Some(Role::Setter),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Role::Player => "player",
+            Role::Setter => "setter",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// The roles of the users, as configured.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Roles {
+    /// The role of users that are not given one.
+    pub default_role: Role,
+    pub users: HashMap<Name, Role>,
+}
+
+impl Roles {
+    pub fn new(default_role: Role) -> Roles {
+        Roles { default_role: default_role, users: HashMap::new() }
+    }
+
+    pub fn set(&mut self, name: Name, role: Role) {
+        self.users.insert(name, role);
+    }
+
+    pub fn role(&self, name: &Name) -> Role {
+        self.users.get(name).cloned().unwrap_or(self.default_role)
+    }
+}
+
+/// Anyone may set puzzles, unless configured otherwise, as before there were roles.
+impl Default for Roles {
+    fn default() -> Roles {
+        Roles::new(Role::Setter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn role_names_test() {
+        for name in ROLE_NAMES {
+            assert_eq!(Role::from_name(name).map(|r| r.name()), Some(*name));
+        }
+        assert_eq!(Role::from_name("Admin"), None);
+    }
+
+    #[test]
+    fn roles_test() {
+        let mut roles = Roles::new(Role::Player);
+        roles.set(Name("alice".into()), Role::Admin);
+
+        assert_eq!(roles.role(&Name("alice".into())), Role::Admin);
+        assert_eq!(roles.role(&Name("bob".into())), Role::Player);
+        assert!(Role::Admin > Role::Setter && Role::Setter > Role::Player);
+    }
+}
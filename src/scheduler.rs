@@ -0,0 +1,124 @@
+use stats::SECONDS_PER_DAY;
+use types::Timestamp;
+
+/// The times of day, in seconds after midnight UTC, of the jobs the bot runs by itself.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Schedule {
+    /// Set a new puzzle, unless one has been set since then.
+    pub daily_puzzle: Option<Timestamp>,
+    /// Remind the channel of the puzzle, if nobody has solved it yet.
+    pub reminders: Vec<Timestamp>,
+    /// Publish the solutions of the puzzle, and end it.
+    pub publish_solutions: Option<Timestamp>,
+}
+
+/// A job, and the time it was due.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Job {
+    DailyPuzzle(Timestamp),
+    Reminder(Timestamp),
+    PublishSolutions(Timestamp),
+}
+
+impl Job {
+    pub fn due(&self) -> Timestamp {
+        match *self {
+            Job::DailyPuzzle(due) | Job::Reminder(due) | Job::PublishSolutions(due) => due,
+        }
+    }
+}
+
+/// The last time at or before `now` that a job at the given time of day was due.
+pub fn last_due(time_of_day: Timestamp, now: Timestamp) -> Timestamp {
+    let today = now - now % SECONDS_PER_DAY + time_of_day;
+    if today <= now { today } else { today - SECONDS_PER_DAY }
+}
+
+/// Decides which jobs to run, each time it is polled.
+pub struct Scheduler {
+    schedule: Schedule,
+    last_poll: Option<Timestamp>,
+}
+
+impl Scheduler {
+    pub fn new(schedule: Schedule) -> Scheduler {
+        Scheduler { schedule: schedule, last_poll: None }
+    }
+
+    /// The jobs that have become due since the last poll, in the order they should run. On the
+    /// first poll, the puzzle jobs that were due while the bot was not running are run, as they only
+    /// do something if they haven't been done already. Missed reminders are not.
+    pub fn poll(&mut self, now: Timestamp) -> Vec<Job> {
+        let last_poll = self.last_poll;
+        self.last_poll = Some(now);
+        let became_due = |time_of_day: Timestamp| {
+            let due = last_due(time_of_day, now);
+            match last_poll {
+                Some(last_poll) if due <= last_poll => None,
+                _ => Some(due),
+            }
+        };
+
+        let mut jobs = vec![];
+        if let Some(due) = self.schedule.publish_solutions.and_then(&became_due) {
+            jobs.push(Job::PublishSolutions(due));
+        }
+        if let Some(due) = self.schedule.daily_puzzle.and_then(&became_due) {
+            jobs.push(Job::DailyPuzzle(due));
+        }
+        if last_poll.is_some() {
+            for due in self.schedule.reminders.iter().filter_map(|&t| became_due(t)) {
+                jobs.push(Job::Reminder(due));
+            }
+        }
+
+        // Solutions are published before a new puzzle is set at the same time, so that they are
+        // published only once.
+        jobs.sort_by_key(|job| job.due());
+        jobs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stats::SECONDS_PER_DAY;
+
+    // 2016-09-19 12:00:00 UTC, a monday.
+    const NOW: i64 = 1474286400;
+    const MIDNIGHT: i64 = NOW - 12 * 60 * 60;
+    const HOUR: i64 = 60 * 60;
+
+    #[test]
+    fn last_due_test() {
+        assert_eq!(last_due(6 * HOUR, NOW), MIDNIGHT + 6 * HOUR);
+        assert_eq!(last_due(12 * HOUR, NOW), NOW);
+        assert_eq!(last_due(18 * HOUR, NOW), MIDNIGHT + 18 * HOUR - SECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn scheduler_test() {
+        let mut scheduler = Scheduler::new(Schedule {
+            daily_puzzle: Some(6 * HOUR),
+            reminders: vec![13 * HOUR, 18 * HOUR],
+            publish_solutions: Some(0),
+        });
+
+        // The puzzle jobs are caught up on, but reminders are not.
+        assert_eq!(scheduler.poll(NOW), vec![
+            Job::PublishSolutions(MIDNIGHT),
+            Job::DailyPuzzle(MIDNIGHT + 6 * HOUR),
+        ]);
+        assert_eq!(scheduler.poll(NOW + 60), vec![]);
+        assert_eq!(scheduler.poll(NOW + HOUR), vec![Job::Reminder(NOW + HOUR)]);
+        assert_eq!(scheduler.poll(NOW + HOUR + 60), vec![]);
+
+        // A poll late in the day runs each job once, even if several were due.
+        let tomorrow = MIDNIGHT + SECONDS_PER_DAY;
+        assert_eq!(scheduler.poll(tomorrow + 7 * HOUR), vec![
+            Job::Reminder(MIDNIGHT + 18 * HOUR),
+            Job::PublishSolutions(tomorrow),
+            Job::DailyPuzzle(tomorrow + 6 * HOUR),
+        ]);
+    }
+}
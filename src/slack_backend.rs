@@ -0,0 +1,234 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use rustc_serialize::json::{Json, ToJson};
+
+use slack;
+use slack::api;
+use slack::api::channels::ListResponse;
+use hyper;
+
+use backend::{ChatBackend, IncomingMessage};
+use response::{Attachment, Field};
+use types::{Channel, Name, User};
+use {ListChannels, NiancatHandler};
+
+/// Sends messages through a connected Slack client, and looks up users and channel members among
+/// those known to the `SlackEventHandler`.
+struct SlackBackend<'b> {
+    client: &'b mut slack::RtmClient,
+    users: &'b HashMap<String, slack::User>,
+    members: &'b HashMap<String, HashSet<String>>,
+}
+
+impl<'b> ChatBackend for SlackBackend<'b> {
+    fn user_name(&self, &User(ref user): &User) -> Option<Name> {
+        self.users.get(user).map(|u| Name(u.name.clone()))
+    }
+
+    fn is_member(&self, &User(ref user): &User, &Channel(ref channel): &Channel) -> bool {
+        self.members.get(channel).map(|m| m.contains(user)).unwrap_or(false)
+    }
+
+    fn find_user(&self, &Name(ref name): &Name) -> Option<User> {
+        self.users.values().find(|u| &u.name == name).map(|u| User(u.id.clone()))
+    }
+
+    fn own_user(&self) -> Option<User> {
+        self.client.get_id().map(User)
+    }
+
+    fn private_channel(&mut self, &User(ref user): &User) -> Option<Channel> {
+        match self.client.im_open(user) {
+            Ok(response) => Some(Channel(response.channel)),
+            Err(e) => {
+                println!("Could not open a private channel with {}: {:?}", user, e);
+                None
+            },
+        }
+    }
+
+    fn send_message(&mut self, channel: &Channel, text: &str) -> Result<(), String> {
+        self.client.send_message(channel.0.as_str(), text)
+            .map(|_| ())
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Attachments are posted through the web API, since the real time API only sends plain text.
+    fn send_attachment(&mut self, channel: &Channel, text: &str, attachment: &Attachment) -> Result<(), String> {
+        let attachments = attachments_json(text, attachment);
+        self.client.post_message(channel.0.as_str(), "", Some(&attachments))
+            .map(|_| ())
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl ToJson for Field {
+    fn to_json(&self) -> Json {
+        let mut o = BTreeMap::new();
+        o.insert("title".to_string(), self.title.to_json());
+        o.insert("value".to_string(), self.value.to_json());
+        o.insert("short".to_string(), self.short.to_json());
+        Json::Object(o)
+    }
+}
+
+/// The attachments of a message, as Slack wants them. The plain text of the message is the fallback,
+/// shown in notifications and by clients that can't show attachments.
+fn attachments_json(text: &str, attachment: &Attachment) -> String {
+    let mut o = BTreeMap::new();
+    o.insert("fallback".to_string(), text.to_json());
+    o.insert("title".to_string(), attachment.title.to_json());
+    o.insert("text".to_string(), attachment.text.to_json());
+    o.insert("fields".to_string(), attachment.fields.to_json());
+    o.insert("mrkdwn_in".to_string(), vec!["text".to_string(), "fields".to_string()].to_json());
+    Json::Array(vec![Json::Object(o)]).to_string()
+}
+
+/// Receives Slack events and delivers messages to a `NiancatHandler`.
+pub struct SlackEventHandler<'a> {
+    handler: NiancatHandler<'a>,
+    users: HashMap<String, slack::User>,
+    /// The user ids of the members of each channel.
+    members: HashMap<String, HashSet<String>>,
+}
+
+impl<'a> SlackEventHandler<'a> {
+    pub fn new(handler: NiancatHandler<'a>) -> SlackEventHandler<'a> {
+        SlackEventHandler {
+            handler: handler,
+            users: HashMap::new(),
+            members: HashMap::new(),
+        }
+    }
+
+    pub fn update_user(&mut self, user: &slack::User) {
+        println!("Updating user {:?}", user);
+        self.users.insert(user.id.clone(), user.clone());
+    }
+
+    pub fn update_channel(&mut self, channel: &slack::Channel) {
+        let members = channel.members.clone().unwrap_or(vec![]);
+        self.members.insert(channel.id.clone(), members.into_iter().collect());
+    }
+
+    fn handle_message(&mut self, client: &mut slack::RtmClient, message: &slack::Message) {
+        match *message {
+            slack::Message::Standard{ref channel, ref user, ref text, ..} => {
+                if channel.is_none() {
+                    println!("Message with no channel! text: {:?}", text);
+                    return;
+                }
+
+                if user.is_none() {
+                    println!("Message with no user! text: {:?}", user);
+                    return;
+                }
+
+                if text.is_none() {
+                    println!("Message with no text!");
+                    return;
+                }
+
+                let incoming = IncomingMessage {
+                    channel: Channel(channel.clone().unwrap()),
+                    user: User(user.clone().unwrap()),
+                    text: text.clone().unwrap(),
+                };
+
+                // Anyone writing in a channel is a member of it, even if they joined after the
+                // channels were listed.
+                if !incoming.channel.is_private() {
+                    self.members.entry(incoming.channel.0.clone()).or_insert(HashSet::new())
+                        .insert(incoming.user.0.clone());
+                }
+
+                let mut backend = SlackBackend { client: client, users: &self.users, members: &self.members };
+                self.handler.handle_message(&mut backend, &incoming);
+            },
+
+            _ => {},
+        }
+    }
+
+    /// Let the handler finish background work, like reloading the dictionary.
+    fn poll(&mut self, client: &mut slack::RtmClient) {
+        let mut backend = SlackBackend { client: client, users: &self.users, members: &self.members };
+        self.handler.poll(&mut backend);
+    }
+
+    fn handle_event(&mut self, client: &mut slack::RtmClient, event: &slack::Event) {
+        match *event {
+            slack::Event::Message(ref m) => self.handle_message(client, m),
+            slack::Event::UserChange {ref user} => self.update_user(user),
+            slack::Event::TeamJoin{ref user} => self.update_user(user),
+            _ => {},
+        }
+    }
+}
+
+impl<'a> slack::EventHandler for SlackEventHandler<'a> {
+    fn on_event(&mut self,
+                client: &mut slack::RtmClient,
+                event: Result<&slack::Event, slack::Error>,
+                raw_json: &str) {
+        match event {
+            Ok(ok_event) => {
+                println!("on_event(event: {:?}, raw_json: {:?}", ok_event, raw_json);
+                self.handle_event(client, ok_event);
+            },
+            Err(bad_event) => println!("on_event(bad event: {:?}, raw_json: {:?}", bad_event, raw_json)
+        }
+
+    }
+
+    fn on_ping(&mut self, client: &mut slack::RtmClient) {
+        println!("on_ping");
+        self.poll(client);
+    }
+
+    fn on_close(&mut self, _client: &mut slack::RtmClient) {
+        println!("on_close");
+    }
+
+    fn on_connect(&mut self, _client: &mut slack::RtmClient) {
+        println!("Connected!");
+    }
+}
+
+pub struct SlackListChannels {
+    pub token: String,
+}
+
+impl ListChannels for SlackListChannels {
+    fn list_channels(&self) -> Result<ListResponse, api::Error> {
+        let client = hyper::Client::new();
+        api::channels::list(&client, &self.token, Some(true))
+    }
+}
+
+impl SlackListChannels {
+    pub fn list_users(&self) -> Result<api::users::ListResponse, api::Error> {
+        let client = hyper::Client::new();
+        api::users::list(&client, &self.token, Some(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attachments_json_test() {
+        let attachment = Attachment {
+            title: "Dagens nia".into(),
+            text: "```A B C\nD E F\nG H I```".into(),
+            fields: vec![Field { title: "Lösningar".into(), value: "2".into(), short: true }],
+        };
+
+        let json = Json::from_str(&attachments_json("ABC DEF GHI", &attachment)).unwrap();
+        let o = json[0].as_object().unwrap();
+        assert_eq!(o["fallback"], Json::String("ABC DEF GHI".into()));
+        assert_eq!(o["text"], Json::String("```A B C\nD E F\nG H I```".into()));
+        assert_eq!(o["fields"][0]["short"], Json::Boolean(true));
+    }
+}
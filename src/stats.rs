@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+
+use response::Reason;
+use types::*;
+
+/// A correct solution, by a user at a given time.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Solve {
+    pub name: Name,
+    pub word: Word,
+    pub time: Timestamp,
+}
+
+/// A guess on the current puzzle, by a user at a given time. The reason is why the guess was wrong,
+/// or `None` if it was right.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Guess {
+    pub word: Word,
+    pub reason: Option<Reason>,
+    pub time: Timestamp,
+}
+
+/// Fun facts about the guesses on a puzzle, for the summary when the puzzle ends.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct GuessStats {
+    pub guesses: usize,
+    /// The most common wrong guess, and how many times it was guessed. Ties go to the word first in
+    /// alphabetical order.
+    pub most_common_wrong: Option<(Word, usize)>,
+}
+
+impl GuessStats {
+    pub fn new(guesses: &HashMap<Name, Vec<Guess>>) -> GuessStats {
+        let mut wrong: HashMap<&Word, usize> = HashMap::new();
+        for guess in guesses.values().flat_map(|g| g.iter()).filter(|g| g.reason.is_some()) {
+            *wrong.entry(&guess.word).or_insert(0) += 1;
+        }
+
+        let most_common_wrong = wrong.into_iter()
+            .max_by(|&(a, m), &(b, n)| m.cmp(&n).then_with(|| b.0.cmp(&a.0)))
+            .map(|(word, n)| (word.clone(), n));
+        GuessStats {
+            guesses: guesses.values().map(|g| g.len()).sum(),
+            most_common_wrong: most_common_wrong,
+        }
+    }
+}
+
+/// A word posted in a public channel that gives away the puzzle, by a user at a given time.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Spoiler {
+    pub name: Name,
+    pub channel: Channel,
+    pub word: Word,
+    pub time: Timestamp,
+}
+
+/// A puzzle, when it was set, and all correct solutions in the order they were found.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct PuzzleRecord {
+    pub puzzle: Puzzle,
+    pub set_time: Timestamp,
+    pub solves: Vec<Solve>,
+    /// The secret that the solution hashes are salted with, until the solutions are published.
+    /// Puzzles from before there were salts have an empty salt.
+    pub salt: String,
+}
+
+impl PuzzleRecord {
+    pub fn new(puzzle: &Puzzle, set_time: Timestamp) -> PuzzleRecord {
+        PuzzleRecord { puzzle: puzzle.clone(), set_time: set_time, solves: vec![], salt: String::new() }
+    }
+
+    /// The first solve by each user, in the order the users solved the puzzle.
+    pub fn first_solves(&self) -> Vec<&Solve> {
+        let mut seen = HashSet::new();
+        self.solves.iter().filter(|s| seen.insert(s.name.clone())).collect()
+    }
+
+    pub fn first_solve(&self, name: &Name) -> Option<&Solve> {
+        self.solves.iter().find(|s| &s.name == name)
+    }
+}
+
+/// The number of solved puzzles for each user, with the most solved puzzles first.
+pub type Leaderboard = Vec<(Name, usize)>;
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct UserStats {
+    pub solved: usize,
+    /// Average time, in seconds, from the puzzle being set until the user solved it.
+    pub average_solve_time: Option<i64>,
+    pub current_streak: usize,
+    pub longest_streak: usize,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct WeeklySummary {
+    pub puzzles: usize,
+    pub leaderboard: Leaderboard,
+}
+
+/// All puzzles that have been set, oldest first. The last puzzle is the current one.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct History(pub Vec<PuzzleRecord>);
+
+pub const SECONDS_PER_DAY: Timestamp = 24 * 60 * 60;
+
+/// The number of weeks since the epoch, with weeks starting on mondays.
+pub fn week(t: Timestamp) -> i64 {
+    // The epoch was a thursday.
+    (t / SECONDS_PER_DAY + 3) / 7
+}
+
+/// The year and month (1-12) of a timestamp, in UTC.
+pub fn month(t: Timestamp) -> (i64, u32) {
+    // Converts days since the epoch to a civil date. See
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = t / SECONDS_PER_DAY + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m as u32)
+}
+
+impl History {
+    pub fn current(&self) -> Option<&PuzzleRecord> {
+        self.0.last()
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut PuzzleRecord> {
+        self.0.last_mut()
+    }
+
+    /// Count the solved puzzles for each user, among the puzzles that are included.
+    pub fn leaderboard<F>(&self, include: F) -> Leaderboard
+        where F: Fn(&PuzzleRecord) -> bool {
+        let mut counts: HashMap<Name, usize> = HashMap::new();
+        for record in self.0.iter().filter(|r| include(r)) {
+            for solve in record.first_solves() {
+                *counts.entry(solve.name.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let mut leaderboard: Leaderboard = counts.into_iter().collect();
+        leaderboard.sort_by(|a, b| b.1.cmp(&a.1).then(a.0 .0.cmp(&b.0 .0)));
+        leaderboard
+    }
+
+    pub fn user_stats(&self, name: &Name) -> UserStats {
+        let solve_times: Vec<i64> = self.0.iter()
+            .filter_map(|r| r.first_solve(name).map(|s| s.time - r.set_time))
+            .collect();
+
+        let average_solve_time = if solve_times.is_empty() {
+            None
+        } else {
+            Some(solve_times.iter().sum::<i64>() / solve_times.len() as i64)
+        };
+
+        let mut longest_streak = 0;
+        let mut streak = 0;
+        for record in &self.0 {
+            if record.first_solve(name).is_some() {
+                streak += 1;
+                longest_streak = longest_streak.max(streak);
+            } else {
+                streak = 0;
+            }
+        }
+
+        // The current puzzle can still be solved, so not having solved it yet does not break the
+        // current streak.
+        let mut records = self.0.iter().rev().peekable();
+        if let Some(current) = records.peek().cloned() {
+            if current.first_solve(name).is_none() {
+                records.next();
+            }
+        }
+        let current_streak = records.take_while(|r| r.first_solve(name).is_some()).count();
+
+        UserStats {
+            solved: solve_times.len(),
+            average_solve_time: average_solve_time,
+            current_streak: current_streak,
+            longest_streak: longest_streak,
+        }
+    }
+
+    /// A summary of all puzzles set during the given week, if any.
+    pub fn weekly_summary(&self, w: i64) -> Option<WeeklySummary> {
+        let puzzles = self.0.iter().filter(|r| week(r.set_time) == w).count();
+        if puzzles == 0 {
+            return None;
+        }
+
+        Some(WeeklySummary {
+            puzzles: puzzles,
+            leaderboard: self.leaderboard(|r| week(r.set_time) == w),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter::FromIterator;
+
+    // 2016-09-19 12:00:00 UTC, a monday.
+    const MONDAY: Timestamp = 1474286400;
+    const HOUR: Timestamp = 60 * 60;
+    const DAY: Timestamp = 24 * HOUR;
+
+    fn record(set_time: Timestamp, solvers: &[(&str, Timestamp)]) -> PuzzleRecord {
+        PuzzleRecord {
+            puzzle: Puzzle("ABCDEFGHI".into()),
+            set_time: set_time,
+            solves: solvers.iter().map(|&(name, t)| Solve {
+                name: Name(name.into()),
+                word: Word("ABCDEFGHI".into()),
+                time: set_time + t,
+            }).collect(),
+            salt: String::new(),
+        }
+    }
+
+    #[test]
+    fn calendar_test() {
+        assert_eq!(month(0), (1970, 1));
+        assert_eq!(month(MONDAY), (2016, 9));
+        // 2000-02-29 and 2000-03-01
+        assert_eq!(month(951782400), (2000, 2));
+        assert_eq!(month(951782400 + DAY), (2000, 3));
+        // 2016-12-31 23:59:59 and 2017-01-01
+        assert_eq!(month(1483228799), (2016, 12));
+        assert_eq!(month(1483228800), (2017, 1));
+
+        assert_eq!(week(MONDAY), week(MONDAY + 6 * DAY));
+        assert_eq!(week(MONDAY) - 1, week(MONDAY - DAY));
+        assert_eq!(week(MONDAY) + 1, week(MONDAY + 7 * DAY));
+    }
+
+    #[test]
+    fn first_solves_test() {
+        let r = record(MONDAY, &[("foo", 10), ("bar", 20), ("foo", 30)]);
+        let names: Vec<Name> = r.first_solves().iter().map(|s| s.name.clone()).collect();
+        assert_eq!(names, vec![Name("foo".into()), Name("bar".into())]);
+        assert_eq!(r.first_solve(&Name("foo".into())).map(|s| s.time), Some(MONDAY + 10));
+        assert_eq!(r.first_solve(&Name("baz".into())), None);
+    }
+
+    #[test]
+    fn leaderboard_test() {
+        let history = History(vec![
+            record(MONDAY, &[("foo", 10), ("bar", 20), ("foo", 30)]),
+            record(MONDAY + DAY, &[("bar", 10)]),
+            record(MONDAY + 2 * DAY, &[("baz", 10), ("bar", 20)]),
+        ]);
+
+        assert_eq!(history.leaderboard(|_| true), vec![
+            (Name("bar".into()), 3),
+            (Name("baz".into()), 1),
+            (Name("foo".into()), 1),
+        ]);
+
+        assert_eq!(history.leaderboard(|r| r.set_time == MONDAY), vec![
+            (Name("bar".into()), 1),
+            (Name("foo".into()), 1),
+        ]);
+    }
+
+    #[test]
+    fn user_stats_test() {
+        let history = History(vec![
+            record(MONDAY, &[("foo", 1 * HOUR)]),
+            record(MONDAY + DAY, &[("foo", 3 * HOUR)]),
+            record(MONDAY + 2 * DAY, &[("bar", HOUR)]),
+            record(MONDAY + 3 * DAY, &[("foo", 2 * HOUR)]),
+            // The current puzzle is not solved yet by foo.
+            record(MONDAY + 4 * DAY, &[("bar", HOUR)]),
+        ]);
+
+        assert_eq!(history.user_stats(&Name("foo".into())), UserStats {
+            solved: 3,
+            average_solve_time: Some(2 * HOUR),
+            current_streak: 1,
+            longest_streak: 2,
+        });
+
+        assert_eq!(history.user_stats(&Name("bar".into())), UserStats {
+            solved: 2,
+            average_solve_time: Some(HOUR),
+            current_streak: 1,
+            longest_streak: 1,
+        });
+
+        assert_eq!(history.user_stats(&Name("baz".into())), UserStats {
+            solved: 0,
+            average_solve_time: None,
+            current_streak: 0,
+            longest_streak: 0,
+        });
+    }
+
+    #[test]
+    fn weekly_summary_test() {
+        let history = History(vec![
+            record(MONDAY - DAY, &[("foo", HOUR)]),
+            record(MONDAY, &[("foo", HOUR), ("bar", HOUR)]),
+            record(MONDAY + DAY, &[("bar", HOUR)]),
+        ]);
+
+        assert_eq!(history.weekly_summary(week(MONDAY)), Some(WeeklySummary {
+            puzzles: 2,
+            leaderboard: vec![(Name("bar".into()), 2), (Name("foo".into()), 1)],
+        }));
+        assert_eq!(history.weekly_summary(week(MONDAY) + 1), None);
+    }
+
+    #[test]
+    fn guess_stats_test() {
+        let guess = |word: &str, reason: Option<Reason>| Guess { word: Word(word.into()), reason: reason, time: 0 };
+        let guesses = HashMap::from_iter(vec![
+            (Name("foo".into()), vec![guess("SPELDATOR", Some(Reason::NotInDictionary)),
+                                      guess("DATORSPEL", None)]),
+            (Name("bar".into()), vec![guess("SPELDATOR", Some(Reason::NotInDictionary)),
+                                      guess("DATORSPEL", None),
+                                      guess("DATORSPEL", None)]),
+            (Name("baz".into()), vec![guess("ABC", Some(Reason::WrongLength(9)))]),
+        ].into_iter());
+
+        assert_eq!(GuessStats::new(&guesses), GuessStats {
+            guesses: 6,
+            most_common_wrong: Some((Word("SPELDATOR".into()), 2)),
+        });
+        assert_eq!(GuessStats::new(&HashMap::new()), GuessStats { guesses: 0, most_common_wrong: None });
+    }
+}
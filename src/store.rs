@@ -0,0 +1,662 @@
+use rustc_serialize::json::{Json, ToJson, Object};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use messages::Language;
+use roles::Role;
+use response::Reason;
+use stats::{Guess, History, PuzzleRecord, Solve, Spoiler};
+use types::{Channel, Name, Puzzle, Word};
+
+/// A snapshot of the game state. This is what is saved to, and restored from, a `StateStore`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct State {
+    pub puzzle: Option<String>,
+    pub solutions: HashMap<String, Vec<String>>,
+    pub unsolutions: HashMap<String, Vec<String>>,
+    pub history: History,
+    pub hints: HashMap<String, usize>,
+    /// Every guess on the current puzzle, by each user.
+    pub guesses: HashMap<String, Vec<Guess>>,
+    /// Puzzles queued with `!köa`, next first.
+    pub queue: Vec<String>,
+    /// Roles given with `!roll`, which take precedence over the configured roles.
+    pub roles: HashMap<String, Role>,
+    /// Spoilers of the current puzzle posted in the game channel, oldest first.
+    pub spoilers: Vec<Spoiler>,
+    /// The last week whose summary has been posted, in weeks since the epoch.
+    pub summarized_week: Option<i64>,
+    /// The puzzle replaced last, while it can still be restored and its solutions are unpublished.
+    pub replaced: Option<ReplacedState>,
+}
+
+/// A replaced puzzle, with its solutions, unsolutions, hints and guesses.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ReplacedState {
+    pub puzzle: String,
+    pub solutions: HashMap<String, Vec<String>>,
+    pub unsolutions: HashMap<String, Vec<String>>,
+    pub hints: HashMap<String, usize>,
+    pub guesses: HashMap<String, Vec<Guess>>,
+    /// The length of the history before the next puzzle was added to it.
+    pub history_length: usize,
+    pub replaced_at: i64,
+}
+
+pub trait StateStore {
+    /// Load the last saved state, or `None` if no state has been saved yet.
+    fn load(&self) -> Result<Option<State>, String>;
+    fn save(&mut self, state: &State) -> Result<(), String>;
+}
+
+/// What users have chosen for themselves, across all games. This is saved to, and restored from, a
+/// `SettingsStore`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct UserSettings {
+    /// The game each user has selected with `!spela`, by the name of the game.
+    pub selected_games: HashMap<String, String>,
+    /// The language each user has chosen with `!språk`, for private messages.
+    pub languages: HashMap<String, Language>,
+}
+
+pub trait SettingsStore {
+    /// Load the last saved settings, or `None` if no settings have been saved yet.
+    fn load(&self) -> Result<Option<UserSettings>, String>;
+    fn save(&mut self, settings: &UserSettings) -> Result<(), String>;
+}
+
+impl ToJson for State {
+    fn to_json(&self) -> Json {
+        let mut o = BTreeMap::new();
+        o.insert("puzzle".to_string(), self.puzzle.to_json());
+        o.insert("solutions".to_string(), self.solutions.to_json());
+        o.insert("unsolutions".to_string(), self.unsolutions.to_json());
+        o.insert("history".to_string(), self.history.to_json());
+        o.insert("hints".to_string(), counts_to_json(&self.hints));
+        o.insert("guesses".to_string(), guesses_to_json(&self.guesses));
+        o.insert("queue".to_string(), self.queue.to_json());
+        o.insert("roles".to_string(), self.roles.iter()
+                 .map(|(k, v)| (k.clone(), Json::String(v.name().to_string())))
+                 .collect::<BTreeMap<String, Json>>().to_json());
+        o.insert("spoilers".to_string(), Json::Array(self.spoilers.iter().map(|spoiler| {
+            let mut s = BTreeMap::new();
+            s.insert("name".to_string(), spoiler.name.0.to_json());
+            s.insert("channel".to_string(), spoiler.channel.0.to_json());
+            s.insert("word".to_string(), spoiler.word.0.to_json());
+            s.insert("time".to_string(), spoiler.time.to_json());
+            Json::Object(s)
+        }).collect()));
+        o.insert("summarized_week".to_string(), self.summarized_week.to_json());
+        o.insert("replaced".to_string(), match self.replaced {
+            None => Json::Null,
+            Some(ref replaced) => {
+                let mut r = BTreeMap::new();
+                r.insert("puzzle".to_string(), replaced.puzzle.to_json());
+                r.insert("solutions".to_string(), replaced.solutions.to_json());
+                r.insert("unsolutions".to_string(), replaced.unsolutions.to_json());
+                r.insert("hints".to_string(), counts_to_json(&replaced.hints));
+                r.insert("guesses".to_string(), guesses_to_json(&replaced.guesses));
+                r.insert("history_length".to_string(), replaced.history_length.to_json());
+                r.insert("replaced_at".to_string(), replaced.replaced_at.to_json());
+                Json::Object(r)
+            },
+        });
+        Json::Object(o)
+    }
+}
+
+fn counts_to_json(counts: &HashMap<String, usize>) -> Json {
+    counts.iter()
+        .map(|(k, &v)| (k.clone(), Json::U64(v as u64)))
+        .collect::<BTreeMap<String, Json>>().to_json()
+}
+
+fn guesses_to_json(guesses: &HashMap<String, Vec<Guess>>) -> Json {
+    guesses.iter()
+        .map(|(k, guesses)| (k.clone(), Json::Array(guesses.iter().map(guess_to_json).collect())))
+        .collect::<BTreeMap<String, Json>>().to_json()
+}
+
+impl ToJson for History {
+    fn to_json(&self) -> Json {
+        Json::Array(self.0.iter().map(|record| {
+            let mut o = BTreeMap::new();
+            o.insert("puzzle".to_string(), record.puzzle.0.to_json());
+            o.insert("set_time".to_string(), record.set_time.to_json());
+            o.insert("salt".to_string(), record.salt.to_json());
+            o.insert("solves".to_string(), Json::Array(record.solves.iter().map(|solve| {
+                let mut s = BTreeMap::new();
+                s.insert("name".to_string(), solve.name.0.to_json());
+                s.insert("word".to_string(), solve.word.0.to_json());
+                s.insert("time".to_string(), solve.time.to_json());
+                Json::Object(s)
+            }).collect()));
+            Json::Object(o)
+        }).collect())
+    }
+}
+
+/// A guess, with the reason it was wrong as an object with a kind, or null if it was right.
+fn guess_to_json(guess: &Guess) -> Json {
+    let mut o = BTreeMap::new();
+    o.insert("word".to_string(), guess.word.0.to_json());
+    o.insert("time".to_string(), guess.time.to_json());
+    o.insert("reason".to_string(), match guess.reason {
+        None => Json::Null,
+        Some(ref reason) => {
+            let mut r = BTreeMap::new();
+            let kind = match *reason {
+                Reason::NotInDictionary => "not_in_dictionary",
+                Reason::NearMisses(ref words) => {
+                    r.insert("words".to_string(), words.iter().map(|w| w.0.clone()).collect::<Vec<String>>().to_json());
+                    "near_misses"
+                },
+                Reason::WrongLength(length) => {
+                    r.insert("length".to_string(), length.to_json());
+                    "wrong_length"
+                },
+                Reason::NonMatchingWord(ref puzzle, ref too_many, ref too_few) => {
+                    r.insert("puzzle".to_string(), puzzle.0.to_json());
+                    r.insert("too_many".to_string(), too_many.to_json());
+                    r.insert("too_few".to_string(), too_few.to_json());
+                    "non_matching_word"
+                },
+            };
+            r.insert("kind".to_string(), kind.to_json());
+            Json::Object(r)
+        },
+    });
+    Json::Object(o)
+}
+
+fn string_list_from_json(json: &Json) -> Result<Vec<String>, String> {
+    let array = try!(json.as_array().ok_or(format!("Expected a list, found {}", json)));
+    array.iter()
+        .map(|x| x.as_string().map(|s| s.to_string()).ok_or(format!("Expected a string, found {}", x)))
+        .collect()
+}
+
+fn string_list_map_from_json(o: &Object, key: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut map = HashMap::new();
+    // Missing keys are treated as empty, so that state files from older versions can be read.
+    if let Some(json) = o.get(key) {
+        let entries = try!(json.as_object().ok_or(format!("Expected an object for {}", key)));
+        for (k, v) in entries {
+            map.insert(k.clone(), try!(string_list_from_json(v)));
+        }
+    }
+    Ok(map)
+}
+
+fn string_field(o: &Object, key: &str) -> Result<String, String> {
+    o.get(key).and_then(|x| x.as_string()).map(|x| x.to_string()).ok_or(format!("Expected a string {}", key))
+}
+
+fn i64_field(o: &Object, key: &str) -> Result<i64, String> {
+    o.get(key).and_then(|x| x.as_i64()).ok_or(format!("Expected an integer {}", key))
+}
+
+fn counts_from_json(o: &Object, key: &str) -> Result<HashMap<String, usize>, String> {
+    let mut map = HashMap::new();
+    if let Some(json) = o.get(key) {
+        let entries = try!(json.as_object().ok_or(format!("Expected an object for {}", key)));
+        for (k, v) in entries {
+            let count = try!(v.as_u64().ok_or(format!("Expected a count, found {}", v)));
+            map.insert(k.clone(), count as usize);
+        }
+    }
+    Ok(map)
+}
+
+fn roles_from_json(o: &Object) -> Result<HashMap<String, Role>, String> {
+    let mut map = HashMap::new();
+    if let Some(json) = o.get("roles") {
+        let entries = try!(json.as_object().ok_or("Expected an object for roles".to_string()));
+        for (k, v) in entries {
+            let role = try!(v.as_string().and_then(Role::from_name).ok_or(format!("Expected a role, found {}", v)));
+            map.insert(k.clone(), role);
+        }
+    }
+    Ok(map)
+}
+
+fn languages_from_json(o: &Object) -> Result<HashMap<String, Language>, String> {
+    let mut map = HashMap::new();
+    if let Some(json) = o.get("languages") {
+        let entries = try!(json.as_object().ok_or("Expected an object for languages".to_string()));
+        for (k, v) in entries {
+            let language = try!(v.as_string().and_then(Language::from_code)
+                                .ok_or(format!("Expected a language, found {}", v)));
+            map.insert(k.clone(), language);
+        }
+    }
+    Ok(map)
+}
+
+fn spoilers_from_json(o: &Object) -> Result<Vec<Spoiler>, String> {
+    let spoilers = match o.get("spoilers") {
+        None => return Ok(vec![]),
+        Some(json) => try!(json.as_array().ok_or("Expected the spoilers to be a list".to_string())),
+    };
+
+    spoilers.iter()
+        .map(|spoiler| {
+            let spoiler = try!(spoiler.as_object().ok_or(format!("Expected a spoiler, found {}", spoiler)));
+            Ok(Spoiler {
+                name: Name(try!(string_field(spoiler, "name"))),
+                channel: Channel(try!(string_field(spoiler, "channel"))),
+                word: Word(try!(string_field(spoiler, "word"))),
+                time: try!(i64_field(spoiler, "time")),
+            })
+        })
+        .collect()
+}
+
+fn reason_from_json(json: &Json) -> Result<Option<Reason>, String> {
+    if json.is_null() {
+        return Ok(None);
+    }
+
+    let r = try!(json.as_object().ok_or(format!("Expected a reason, found {}", json)));
+    let reason = match try!(string_field(r, "kind")).as_str() {
+        "not_in_dictionary" => Reason::NotInDictionary,
+        "near_misses" => {
+            let words = try!(r.get("words").ok_or("Expected a list of words".to_string()));
+            Reason::NearMisses(try!(string_list_from_json(words)).into_iter().map(Word).collect())
+        },
+        "wrong_length" => Reason::WrongLength(try!(i64_field(r, "length")) as usize),
+        "non_matching_word" => Reason::NonMatchingWord(Puzzle(try!(string_field(r, "puzzle"))),
+                                                       try!(string_field(r, "too_many")),
+                                                       try!(string_field(r, "too_few"))),
+        kind => return Err(format!("Unknown kind of reason {}", kind)),
+    };
+    Ok(Some(reason))
+}
+
+fn guesses_from_json(o: &Object) -> Result<HashMap<String, Vec<Guess>>, String> {
+    let mut map = HashMap::new();
+    if let Some(json) = o.get("guesses") {
+        let entries = try!(json.as_object().ok_or("Expected an object for guesses".to_string()));
+        for (k, v) in entries {
+            let guesses = try!(v.as_array().ok_or(format!("Expected a list of guesses, found {}", v)));
+            let guesses = try!(guesses.iter()
+                .map(|guess| {
+                    let guess = try!(guess.as_object().ok_or(format!("Expected a guess, found {}", guess)));
+                    Ok(Guess {
+                        word: Word(try!(string_field(guess, "word"))),
+                        reason: try!(reason_from_json(guess.get("reason").unwrap_or(&Json::Null))),
+                        time: try!(i64_field(guess, "time")),
+                    })
+                })
+                .collect::<Result<Vec<Guess>, String>>());
+            map.insert(k.clone(), guesses);
+        }
+    }
+    Ok(map)
+}
+
+fn replaced_from_json(o: &Object) -> Result<Option<ReplacedState>, String> {
+    let r = match o.get("replaced") {
+        None | Some(&Json::Null) => return Ok(None),
+        Some(json) => try!(json.as_object().ok_or(format!("Expected a replaced puzzle, found {}", json))),
+    };
+
+    Ok(Some(ReplacedState {
+        puzzle: try!(string_field(r, "puzzle")),
+        solutions: try!(string_list_map_from_json(r, "solutions")),
+        unsolutions: try!(string_list_map_from_json(r, "unsolutions")),
+        hints: try!(counts_from_json(r, "hints")),
+        guesses: try!(guesses_from_json(r)),
+        history_length: try!(i64_field(r, "history_length")) as usize,
+        replaced_at: try!(i64_field(r, "replaced_at")),
+    }))
+}
+
+fn history_from_json(o: &Object) -> Result<History, String> {
+    let mut history = History::default();
+    let records = match o.get("history") {
+        None => return Ok(history),
+        Some(json) => try!(json.as_array().ok_or("Expected the history to be a list".to_string())),
+    };
+
+    for record in records {
+        let record = try!(record.as_object().ok_or(format!("Expected a puzzle record, found {}", record)));
+        let solves = try!(record.get("solves").and_then(|x| x.as_array())
+                          .ok_or("Expected a list of solves".to_string()));
+
+        let mut r = PuzzleRecord::new(&Puzzle(try!(string_field(record, "puzzle"))),
+                                      try!(i64_field(record, "set_time")));
+        // Records from before there were salts have no salt.
+        if let Some(salt) = record.get("salt") {
+            r.salt = try!(salt.as_string().ok_or(format!("Expected the salt to be a string, found {}", salt))).to_string();
+        }
+        for solve in solves {
+            let solve = try!(solve.as_object().ok_or(format!("Expected a solve, found {}", solve)));
+            r.solves.push(Solve {
+                name: Name(try!(string_field(solve, "name"))),
+                word: Word(try!(string_field(solve, "word"))),
+                time: try!(i64_field(solve, "time")),
+            });
+        }
+        history.0.push(r);
+    }
+    Ok(history)
+}
+
+impl State {
+    pub fn from_json(json: &Json) -> Result<State, String> {
+        let o = try!(json.as_object().ok_or("Expected the state to be an object".to_string()));
+
+        let puzzle = match o.get("puzzle") {
+            None | Some(&Json::Null) => None,
+            Some(&Json::String(ref p)) => Some(p.clone()),
+            Some(x) => return Err(format!("Expected the puzzle to be a string, found {}", x)),
+        };
+
+        Ok(State {
+            puzzle: puzzle,
+            solutions: try!(string_list_map_from_json(o, "solutions")),
+            unsolutions: try!(string_list_map_from_json(o, "unsolutions")),
+            history: try!(history_from_json(o)),
+            hints: try!(counts_from_json(o, "hints")),
+            guesses: try!(guesses_from_json(o)),
+            queue: match o.get("queue") {
+                None => vec![],
+                Some(json) => try!(string_list_from_json(json)),
+            },
+            roles: try!(roles_from_json(o)),
+            spoilers: try!(spoilers_from_json(o)),
+            summarized_week: match o.get("summarized_week") {
+                None | Some(&Json::Null) => None,
+                Some(json) => Some(try!(json.as_i64().ok_or(format!("Expected a week, found {}", json)))),
+            },
+            replaced: try!(replaced_from_json(o)),
+        })
+    }
+}
+
+impl ToJson for UserSettings {
+    fn to_json(&self) -> Json {
+        let mut o = BTreeMap::new();
+        o.insert("selected_games".to_string(), self.selected_games.to_json());
+        o.insert("languages".to_string(), self.languages.iter()
+                 .map(|(k, v)| (k.clone(), Json::String(v.code().to_string())))
+                 .collect::<BTreeMap<String, Json>>().to_json());
+        Json::Object(o)
+    }
+}
+
+impl UserSettings {
+    pub fn from_json(json: &Json) -> Result<UserSettings, String> {
+        let o = try!(json.as_object().ok_or("Expected the settings to be an object".to_string()));
+
+        let mut selected_games = HashMap::new();
+        if let Some(json) = o.get("selected_games") {
+            let entries = try!(json.as_object().ok_or("Expected an object for selected_games".to_string()));
+            for (k, v) in entries {
+                let game = try!(v.as_string().ok_or(format!("Expected the name of a game, found {}", v)));
+                selected_games.insert(k.clone(), game.to_string());
+            }
+        }
+
+        Ok(UserSettings {
+            selected_games: selected_games,
+            languages: try!(languages_from_json(o)),
+        })
+    }
+}
+
+/// Read the JSON in a file, or `None` if there is no such file.
+fn read_json_file(path: &PathBuf) -> Result<Option<Json>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let mut contents = String::new();
+    let mut f = try!(fs::File::open(path).map_err(|e| format!("{}", e)));
+    try!(f.read_to_string(&mut contents).map_err(|e| format!("{}", e)));
+
+    Json::from_str(&contents).map(Some).map_err(|e| format!("{}", e))
+}
+
+fn write_json_file(path: &PathBuf, json: &Json) -> Result<(), String> {
+    // Write to a temporary file first, so a crash while writing never leaves a half written file
+    // behind.
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut f = try!(fs::File::create(&tmp_path).map_err(|e| format!("{}", e)));
+        try!(write!(f, "{}", json.pretty()).map_err(|e| format!("{}", e)));
+    }
+    fs::rename(&tmp_path, path).map_err(|e| format!("{}", e))
+}
+
+/// Stores the state as JSON in a file.
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileStateStore {
+        FileStateStore { path: path.into() }
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn load(&self) -> Result<Option<State>, String> {
+        match try!(read_json_file(&self.path)) {
+            None => Ok(None),
+            Some(json) => State::from_json(&json).map(Some),
+        }
+    }
+
+    fn save(&mut self, state: &State) -> Result<(), String> {
+        write_json_file(&self.path, &state.to_json())
+    }
+}
+
+/// Stores the user settings as JSON in a file.
+pub struct FileSettingsStore {
+    path: PathBuf,
+}
+
+impl FileSettingsStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileSettingsStore {
+        FileSettingsStore { path: path.into() }
+    }
+}
+
+impl SettingsStore for FileSettingsStore {
+    fn load(&self) -> Result<Option<UserSettings>, String> {
+        match try!(read_json_file(&self.path)) {
+            None => Ok(None),
+            Some(json) => UserSettings::from_json(&json).map(Some),
+        }
+    }
+
+    fn save(&mut self, settings: &UserSettings) -> Result<(), String> {
+        write_json_file(&self.path, &settings.to_json())
+    }
+}
+
+/// Keeps the state in memory only. Used in tests, or when no state file is wanted.
+#[derive(Default)]
+pub struct MemoryStateStore {
+    pub state: Option<State>,
+}
+
+impl StateStore for MemoryStateStore {
+    fn load(&self) -> Result<Option<State>, String> {
+        Ok(self.state.clone())
+    }
+
+    fn save(&mut self, state: &State) -> Result<(), String> {
+        self.state = Some(state.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_serialize::json::{Json, ToJson};
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::iter::FromIterator;
+    use messages::Language;
+    use roles::Role;
+    use stats::*;
+    use types::*;
+
+    fn example_state() -> State {
+        State {
+            puzzle: Some("DATORSPEL".into()),
+            solutions: HashMap::from_iter(vec![
+                ("DATORSPEL".to_string(), vec!["foo".to_string(), "bar".to_string()]),
+                ("SPELDATOR".to_string(), vec![]),
+            ].into_iter()),
+            unsolutions: HashMap::from_iter(vec![
+                ("foo".to_string(), vec!["FOO BAR".to_string()]),
+            ].into_iter()),
+            history: History(vec![
+                PuzzleRecord {
+                    puzzle: Puzzle("PELDATORS".into()),
+                    set_time: 1474286400,
+                    solves: vec![
+                        Solve { name: Name("foo".into()), word: Word("DATORSPEL".into()), time: 1474286410 },
+                        Solve { name: Name("bar".into()), word: Word("SPELDATOR".into()), time: 1474286420 },
+                    ],
+                    salt: "0123456789abcdef".into(),
+                },
+                PuzzleRecord::new(&Puzzle("DATORSPEL".into()), 1474372800),
+            ]),
+            hints: HashMap::from_iter(vec![("bar".to_string(), 2)].into_iter()),
+            guesses: HashMap::from_iter(vec![
+                ("foo".to_string(), vec![
+                    Guess { word: Word("DATORSPLE".into()), reason: Some(Reason::NotInDictionary), time: 1474372810 },
+                    Guess { word: Word("DATORSPLA".into()), time: 1474372820,
+                            reason: Some(Reason::NonMatchingWord(Puzzle("DATORSPEL".into()), "A".into(), "E".into())) },
+                    Guess { word: Word("DATORSPE".into()), reason: Some(Reason::WrongLength(9)), time: 1474372830 },
+                    Guess { word: Word("PELDATORS".into()), time: 1474372840,
+                            reason: Some(Reason::NearMisses(vec![Word("DATORSPEL".into())])) },
+                    Guess { word: Word("DATORSPEL".into()), reason: None, time: 1474372850 },
+                ]),
+            ].into_iter()),
+            queue: vec!["SPELDATOR".to_string(), "TAGALLTJU".to_string()],
+            roles: HashMap::from_iter(vec![("foo".to_string(), Role::Setter)].into_iter()),
+            spoilers: vec![
+                Spoiler { name: Name("baz".into()), channel: Channel("C0".into()), word: Word("SPELDATOR".into()),
+                          time: 1474372900 },
+            ],
+            summarized_week: Some(2437),
+            replaced: Some(ReplacedState {
+                puzzle: "PELDATORS".into(),
+                solutions: HashMap::from_iter(vec![
+                    ("DATORSPEL".to_string(), vec!["foo".to_string()]),
+                ].into_iter()),
+                unsolutions: HashMap::new(),
+                hints: HashMap::from_iter(vec![("foo".to_string(), 1)].into_iter()),
+                guesses: HashMap::from_iter(vec![
+                    ("foo".to_string(), vec![Guess { word: Word("DATORSPEL".into()), reason: None, time: 1474286410 }]),
+                ].into_iter()),
+                history_length: 1,
+                replaced_at: 1474372800,
+            }),
+        }
+    }
+
+    #[test]
+    fn json_roundtrip_test() {
+        let state = example_state();
+        assert_eq!(State::from_json(&state.to_json()), Ok(state));
+
+        let state = State::default();
+        assert_eq!(State::from_json(&state.to_json()), Ok(state));
+    }
+
+    #[test]
+    fn missing_fields_test() {
+        let json = Json::from_str(r#"{"puzzle": "DATORSPEL"}"#).unwrap();
+        let expected = State { puzzle: Some("DATORSPEL".into()), ..State::default() };
+        assert_eq!(State::from_json(&json), Ok(expected));
+    }
+
+    #[test]
+    fn invalid_json_test() {
+        let json = Json::from_str(r#"{"puzzle": 17}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"solutions": {"DATORSPEL": [1, 2]}}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"history": [{"puzzle": "DATORSPEL", "solves": []}]}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"summarized_week": "last"}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"queue": "DATORSPEL"}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"roles": {"foo": "king"}}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"spoilers": [{"name": "foo", "word": "DATORSPEL", "time": 1}]}"#).unwrap();
+        assert!(State::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"guesses": {"foo": [{"word": "DATORSPEL", "time": 1, "reason": {"kind": "close"}}]}}"#)
+            .unwrap();
+        assert!(State::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn file_store_test() {
+        let path = env::temp_dir().join("niancat_file_store_test.json");
+        let _ = fs::remove_file(&path);
+
+        let mut store = FileStateStore::new(path.clone());
+        assert_eq!(store.load(), Ok(None));
+
+        store.save(&example_state()).unwrap();
+        assert_eq!(store.load(), Ok(Some(example_state())));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn settings_test() {
+        let settings = UserSettings {
+            selected_games: HashMap::from_iter(vec![("foo".to_string(), "konsulatet".to_string())].into_iter()),
+            languages: HashMap::from_iter(vec![("bar".to_string(), Language::English)].into_iter()),
+        };
+        assert_eq!(UserSettings::from_json(&settings.to_json()), Ok(settings.clone()));
+
+        let json = Json::from_str(r#"{"selected_games": {"foo": 17}}"#).unwrap();
+        assert!(UserSettings::from_json(&json).is_err());
+
+        let json = Json::from_str(r#"{"languages": {"foo": "fi"}}"#).unwrap();
+        assert!(UserSettings::from_json(&json).is_err());
+
+        let path = env::temp_dir().join("niancat_settings_store_test.json");
+        let _ = fs::remove_file(&path);
+
+        let mut store = FileSettingsStore::new(path.clone());
+        assert_eq!(store.load(), Ok(None));
+
+        store.save(&settings).unwrap();
+        assert_eq!(store.load(), Ok(Some(settings)));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn memory_store_test() {
+        let mut store = MemoryStateStore::default();
+        assert_eq!(store.load(), Ok(None));
+
+        store.save(&example_state()).unwrap();
+        assert_eq!(store.load(), Ok(Some(example_state())));
+    }
+}
@@ -15,6 +15,12 @@ pub struct User(pub String);
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub struct Channel(pub String);
 
+/// Seconds since the Unix epoch.
+pub type Timestamp = i64;
+
+/// The length of puzzles, unless the game is configured otherwise.
+pub const DEFAULT_PUZZLE_LENGTH: usize = 9;
+
 use regex::Regex;
 
 fn normalize_string(s: &String) -> String {
@@ -37,6 +43,11 @@ impl Puzzle {
     pub fn new(s: &String) -> Puzzle {
         Puzzle(normalize_string(&s))
     }
+
+    /// The number of letters in the puzzle.
+    pub fn length(&self) -> usize {
+        self.0.chars().count()
+    }
 }
 
 impl fmt::Display for Puzzle {
@@ -51,8 +62,8 @@ impl Channel {
     }
 }
 
-pub fn is_right_length(w: &String) -> bool {
-    return w.chars().collect::<Vec<char>>().len() == 9
+pub fn is_right_length(w: &String, length: usize) -> bool {
+    return w.chars().count() == length
 }
 
 #[cfg(test)]
@@ -87,6 +98,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn length_test() {
+        assert_eq!(Puzzle("DATORSPEL".into()).length(), 9);
+        assert_eq!(Puzzle("ÅÄÖABCD".into()).length(), 7);
+        assert!(is_right_length(&"ÅÄÖABCDEF".to_string(), 9));
+        assert!(!is_right_length(&"ÅÄÖABCDEF".to_string(), 10));
+    }
+
     #[test]
     fn public_private_channels() {
         assert!(!Channel("C0123".into()).is_private());
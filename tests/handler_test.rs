@@ -0,0 +1,645 @@
+extern crate niancat;
+
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use niancat::{GuessLimit, Language, NiancatHandler, Schedule};
+use niancat::backend::ScriptedBackend;
+use niancat::roles::{Role, Roles};
+use niancat::dictionary::{Dictionary, DictionaryFiles, SharedDictionary};
+use niancat::store::{State, MemoryStateStore, FileSettingsStore, SettingsStore};
+use niancat::types::{Channel, Name, User};
+
+fn has_message(sent: &Vec<(Channel, String)>, channel: &Channel, texts: &[&str]) -> bool {
+    sent.iter().any(|&(ref c, ref msg)| c == channel && texts.iter().all(|t| msg.contains(t)))
+}
+
+/// Roles where alice is an admin, and anyone may set puzzles.
+fn admin_roles() -> Roles {
+    let mut roles = Roles::default();
+    roles.set(Name("alice".into()), Role::Admin);
+    roles
+}
+
+#[test]
+fn solve_puzzle_conversation_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+
+    backend.push_message(&main_channel, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&main_channel, &alice, "!nian");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert_eq!(sent.len(), 2, "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["TAG ALL TJU"]), "{:?}", sent);
+
+    backend.push_message(&alice_im, &alice, "ABCDEFGHI");
+    backend.push_message(&alice_im, &alice, "GALLTJUTA");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert_eq!(sent.len(), 3, "{:?}", sent);
+    assert!(has_message(&sent, &alice_im, &["ABCDEFGHI", "matchar inte"]), "{:?}", sent);
+    assert!(has_message(&sent, &alice_im, &["GALLTJUTA", "korrekt"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["alice löste nian"]), "{:?}", sent);
+
+    // The puzzle was set just now, so it is replaced only when confirmed.
+    backend.push_message(&alice_im, &alice, "!setnian ABCDEFGHI");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["!setnian ABC DEF GHI igen"]), "{:?}", sent);
+    assert!(!has_message(&sent, &main_channel, &["GALLTJUTA"]), "{:?}", sent);
+
+    backend.push_message(&alice_im, &alice, "!setnian ABCDEFGHI");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["ABC DEF GHI"]), "{:?}", sent);
+    assert!(!has_message(&sent, &main_channel, &["GALLTJUTA"]), "{:?}", sent);
+
+    // The solutions of the replaced puzzle are published to the main channel once it can no longer
+    // be restored.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    handler.poll_games(&mut backend, now + 16 * 60);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["GALLTJUTA", "alice"]), "{:?}", sent);
+
+    // Slack sends mentions as user ids.
+    backend.push_message(&main_channel, &alice, "!statsnian <@U0|alice>");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &main_channel, &["alice har löst 1 nior"]));
+}
+
+#[test]
+fn unknown_user_is_ignored_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+
+    let mut backend = ScriptedBackend::new();
+    backend.push_message(&main_channel, &User("U17".into()), "!nian");
+    backend.run(&mut handler);
+
+    assert!(backend.sent.is_empty(), "{:?}", backend.sent);
+}
+
+#[test]
+fn own_message_is_ignored_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+
+    let niancat = User("U1".into());
+    let alice = User("U0".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&niancat, &Name("niancat".into()));
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.set_own_user(&niancat);
+
+    backend.push_message(&main_channel, &niancat, "!nian");
+    backend.run(&mut handler);
+    assert!(backend.take_sent().is_empty());
+
+    // Users are answered whatever they write.
+    backend.push_message(&Channel("D0".into()), &alice, "GALLTJU är inte 9 tecken långt.");
+    backend.run(&mut handler);
+    assert_eq!(backend.take_sent().len(), 1);
+}
+
+#[test]
+fn unknown_command_in_private_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.push_message(&main_channel, &alice, "!nosuchcommand");
+    backend.push_message(&alice_im, &alice, "!nosuchcommand");
+    backend.run(&mut handler);
+
+    assert_eq!(backend.sent.len(), 1, "{:?}", backend.sent);
+    assert!(has_message(&backend.sent, &alice_im, &["okänt kommando"]), "{:?}", backend.sent);
+}
+
+#[test]
+fn unsolutions_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("DU0".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.push_message(&alice_im, &alice, "!unsolution GALL TJU TA");
+    backend.run(&mut handler);
+    backend.take_sent();
+
+    // Unsolutions asked for in public are listed in private, and nothing is said in the channel.
+    backend.push_message(&main_channel, &alice, "!unsolutions");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert_eq!(sent.len(), 1, "{:?}", sent);
+    assert!(has_message(&sent, &alice_im, &["GALL TJU TA"]), "{:?}", sent);
+}
+
+#[test]
+fn languages_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let general = Channel("C0123".into());
+    let konsulatet = Channel("C0456".into());
+    let settings_path = env::temp_dir().join("niancat_languages_test.json");
+    let _ = fs::remove_file(&settings_path);
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", general.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.add_game("konsulatet", konsulatet.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_language("konsulatet", Language::English);
+    handler.set_settings_store(Box::new(FileSettingsStore::new(settings_path.clone())), &Default::default());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.push_message(&general, &alice, "!nian");
+    backend.push_message(&konsulatet, &alice, "!nian");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &general, &["Nian är inte satt"]), "{:?}", sent);
+    assert!(has_message(&sent, &konsulatet, &["The puzzle is not set"]), "{:?}", sent);
+
+    // A user's language is used in private messages, but not in the game channel.
+    backend.push_message(&alice_im, &alice, "!språk en");
+    backend.push_message(&alice_im, &alice, "!nian");
+    backend.push_message(&general, &alice, "!nian");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["answer you in English"]), "{:?}", sent);
+    assert!(has_message(&sent, &alice_im, &["The puzzle is not set"]), "{:?}", sent);
+    assert!(has_message(&sent, &general, &["Nian är inte satt"]), "{:?}", sent);
+
+    // The language is the same in all games.
+    backend.push_message(&alice_im, &alice, "!spela konsulatet");
+    backend.push_message(&alice_im, &alice, "!språk sv");
+    backend.push_message(&alice_im, &alice, "!spela general");
+    backend.push_message(&alice_im, &alice, "!nian");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &alice_im, &["Nian är inte satt"]));
+
+    // The language is remembered after a restart.
+    let settings = FileSettingsStore::new(settings_path.clone()).load().unwrap().unwrap();
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", general.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.add_game("konsulatet", konsulatet.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_language("konsulatet", Language::English);
+    handler.set_settings_store(Box::new(FileSettingsStore::new(settings_path.clone())), &settings);
+    backend.push_message(&alice_im, &alice, "!spela konsulatet");
+    backend.push_message(&alice_im, &alice, "!nian");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &alice_im, &["Nian är inte satt"]));
+    fs::remove_file(&settings_path).unwrap();
+}
+
+#[test]
+fn spoilers_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let general = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", general.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_roles(&admin_roles());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("DU0".into());
+    let bob = User("U1".into());
+    let bob_im = Channel("DU1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+    backend.push_message(&general, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&general, &bob, "Dagens nia är TAGALLTJU, kul!");
+    backend.push_message(&general, &bob, "Är det galltjuta?");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &bob_im, &["GALLTJUTA"]), "{:?}", sent);
+    assert!(!has_message(&sent, &alice_im, &["bob"]), "{:?}", sent);
+    assert_eq!(sent.iter().filter(|&&(ref c, _)| c == &bob_im).count(), 1);
+
+    // Other public channels are not checked.
+    backend.push_message(&Channel("C0999".into()), &bob, "Är det galltjuta?");
+    backend.run(&mut handler);
+    assert!(backend.take_sent().is_empty());
+
+    // With reports, the admins are also asked to delete the message.
+    handler.set_report_spoilers(true);
+    backend.push_message(&general, &bob, "GALLTJUTA!");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &bob_im, &["GALLTJUTA"]), "{:?}", sent);
+    assert!(has_message(&sent, &alice_im, &["bob", "<#C0123>"]), "{:?}", sent);
+}
+
+#[test]
+fn guess_limit_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let general = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", general.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_roles(&admin_roles());
+    handler.set_guess_limit(GuessLimit { burst: 2, interval: 60 * 60 });
+
+    let alice = User("U0".into());
+    let alice_im = Channel("DU0".into());
+    let bob = User("U1".into());
+    let bob_im = Channel("D1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+    backend.push_message(&general, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&bob_im, &bob, "TAGALLTJU");
+    backend.push_message(&bob_im, &bob, "JUTAGALLT");
+    backend.push_message(&bob_im, &bob, "GALLTJUTA");
+    backend.run(&mut handler);
+
+    // The guess after the burst is refused, and not checked.
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &bob_im, &["Du gissar för ofta"]), "{:?}", sent);
+    assert!(!has_message(&sent, &general, &["bob löste nian"]), "{:?}", sent);
+
+    // Users who keep guessing are reported to the admins, once, and are no longer answered.
+    for _ in 0..10 {
+        backend.push_message(&bob_im, &bob, "GALLTJUTA");
+    }
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert_eq!(sent.iter().filter(|&&(ref c, _)| c == &alice_im).count(), 1);
+    assert_eq!(sent.iter().filter(|&&(ref c, _)| c == &bob_im).count(), 4);
+    assert!(has_message(&sent, &alice_im, &["bob", "skript"]), "{:?}", sent);
+
+    // Other commands are not limited.
+    backend.push_message(&bob_im, &bob, "!nian");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &bob_im, &["TAG ALL TJU"]));
+}
+
+#[test]
+fn multiple_games_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let general = Channel("C0123".into());
+    let konsulatet = Channel("C4567".into());
+    let settings_path = env::temp_dir().join("niancat_multiple_games_test.json");
+    let _ = fs::remove_file(&settings_path);
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", general.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.add_game("konsulatet", konsulatet.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_settings_store(Box::new(FileSettingsStore::new(settings_path.clone())), &Default::default());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let bob = User("U1".into());
+    let bob_im = Channel("D1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+    backend.add_member(&konsulatet, &bob);
+
+    // Each game has its own puzzle.
+    backend.push_message(&general, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&konsulatet, &bob, "!setnian ABCDEFGHI");
+    backend.run(&mut handler);
+    backend.take_sent();
+
+    // Private messages go to the game the user is a member of, or to the first game.
+    backend.push_message(&alice_im, &alice, "GALLTJUTA");
+    backend.push_message(&bob_im, &bob, "GALLTJUTA");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &general, &["alice löste nian"]), "{:?}", sent);
+    assert!(has_message(&sent, &bob_im, &["GALLTJUTA", "matchar inte"]), "{:?}", sent);
+    assert!(!has_message(&sent, &konsulatet, &["löste nian"]), "{:?}", sent);
+
+    // A user can select another game.
+    backend.push_message(&bob_im, &bob, "!spela general");
+    backend.push_message(&bob_im, &bob, "GALLTJUTA");
+    backend.push_message(&bob_im, &bob, "!spela nosuchgame");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &bob_im, &["general"]), "{:?}", sent);
+    assert!(has_message(&sent, &general, &["bob löste nian"]), "{:?}", sent);
+    assert!(has_message(&sent, &bob_im, &["nosuchgame", "general, konsulatet"]), "{:?}", sent);
+
+    // The selected game is remembered after a restart.
+    let settings = FileSettingsStore::new(settings_path.clone()).load().unwrap().unwrap();
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", general.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.add_game("konsulatet", konsulatet.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_settings_store(Box::new(FileSettingsStore::new(settings_path.clone())), &settings);
+    backend.push_message(&general, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&bob_im, &bob, "GALLTJUTA");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &general, &["bob löste nian"]), "{:?}", sent);
+    fs::remove_file(&settings_path).unwrap();
+}
+
+#[test]
+fn disabled_commands_are_ignored_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_enabled_commands(&["!nian".to_string()]);
+
+    let alice = User("U0".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.push_message(&main_channel, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&main_channel, &alice, "!nian");
+    backend.run(&mut handler);
+
+    assert_eq!(backend.sent.len(), 1, "{:?}", backend.sent);
+    assert!(has_message(&backend.sent, &main_channel, &["inte satt"]), "{:?}", backend.sent);
+}
+
+fn write_file(path: &Path, contents: &str) {
+    let mut f = fs::File::create(path).unwrap();
+    f.write_all(contents.as_bytes()).unwrap();
+}
+
+fn poll_until_sent(handler: &mut NiancatHandler, backend: &mut ScriptedBackend) {
+    for _ in 0..500 {
+        handler.poll(backend);
+        if !backend.sent.is_empty() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn reload_dictionary_test() {
+    let path = env::temp_dir().join("niancat_reload_test.txt");
+    write_file(&path, "GALLTJUTA\n");
+    let path_name = path.to_string_lossy().into_owned();
+
+    let dictionary = SharedDictionary::new(Dictionary::from_file(&path).unwrap());
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.enable_reload(dictionary.clone(), DictionaryFiles::new(vec![path_name.clone()]));
+    handler.set_roles(&admin_roles());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let bob = User("U1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+
+    backend.push_message(&main_channel, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&main_channel, &bob, "!laddaom");
+    backend.run(&mut handler);
+
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["administratörer"]), "{:?}", sent);
+
+    // The new dictionary has another solution to the current puzzle.
+    write_file(&path, "GALLTJUTA\nTAGALLTJU\n");
+    backend.push_message(&alice_im, &alice, "!laddaom");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &alice_im, &["Laddar om"]));
+
+    poll_until_sent(&mut handler, &mut backend);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["omladdad"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["TAG ALL TJU", "2 lösningar"]), "{:?}", sent);
+
+    // If the dictionary can't be loaded, the old one is kept. The result is in the language of the
+    // user who asked for the reload.
+    fs::remove_file(&path).unwrap();
+    backend.push_message(&alice_im, &alice, "!språk en");
+    backend.push_message(&alice_im, &alice, "!laddaom");
+    backend.run(&mut handler);
+    backend.take_sent();
+
+    poll_until_sent(&mut handler, &mut backend);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["could not be reloaded"]), "{:?}", sent);
+
+    backend.push_message(&alice_im, &alice, "TAGALLTJU");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &alice_im, &["correct"]));
+}
+
+fn read_file(path: &Path) -> String {
+    let mut contents = String::new();
+    fs::File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+    contents
+}
+
+#[test]
+fn edit_dictionary_test() {
+    let base_path = env::temp_dir().join("niancat_edit_test_base.txt");
+    let allow_path = env::temp_dir().join("niancat_edit_test_allowed.txt");
+    let deny_path = env::temp_dir().join("niancat_edit_test_denied.txt");
+    write_file(&base_path, "GALLTJUTA\nDATORSPEL\n");
+    let _ = fs::remove_file(&allow_path);
+    let _ = fs::remove_file(&deny_path);
+
+    let files = DictionaryFiles {
+        base: vec![base_path.to_string_lossy().into_owned()],
+        allow: Some(allow_path.to_string_lossy().into_owned()),
+        deny: Some(deny_path.to_string_lossy().into_owned()),
+    };
+    let dictionary = SharedDictionary::new(files.load().unwrap());
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.enable_reload(dictionary.clone(), files.clone());
+    handler.set_roles(&admin_roles());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let bob = User("U1".into());
+    let bob_im = Channel("D1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+
+    backend.push_message(&main_channel, &alice, "!setnian TAGALLTJU");
+    backend.push_message(&bob_im, &bob, "!tillåt tagalltju");
+    backend.run(&mut handler);
+    assert!(has_message(&backend.take_sent(), &bob_im, &["administratörer"]));
+
+    backend.push_message(&alice_im, &alice, "!tillåt tagalltju");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["TAGALLTJU är nu tillåtet"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["TAG ALL TJU", "2 lösningar"]), "{:?}", sent);
+    assert_eq!(read_file(&allow_path), "TAGALLTJU\n");
+
+    backend.push_message(&alice_im, &alice, "!förbjud galltjuta");
+    backend.push_message(&bob_im, &bob, "GALLTJUTA");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["TAG ALL TJU", "1 lösningar"]), "{:?}", sent);
+    assert!(!has_message(&sent, &bob_im, &["korrekt"]), "{:?}", sent);
+    assert_eq!(read_file(&deny_path), "GALLTJUTA\n");
+
+    // The overlays are read again when the dictionary is reloaded.
+    let reloaded = files.load().unwrap();
+    assert_eq!(reloaded.allowed_words(), dictionary.allowed_words());
+    assert_eq!(reloaded.denied_words(), dictionary.denied_words());
+
+    fs::remove_file(&base_path).unwrap();
+    fs::remove_file(&allow_path).unwrap();
+    fs::remove_file(&deny_path).unwrap();
+}
+
+#[test]
+fn schedule_test() {
+    // 2016-09-19 00:00:00 UTC, a monday.
+    const MIDNIGHT: i64 = 1474243200;
+    const HOUR: i64 = 60 * 60;
+
+    let dictionary = Dictionary::new(vec!["GALLTJUTA".to_string()].into_iter());
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_schedule(Schedule {
+        daily_puzzle: Some(6 * HOUR),
+        reminders: vec![12 * HOUR],
+        publish_solutions: Some(0),
+    });
+
+    let mut backend = ScriptedBackend::new();
+
+    handler.poll_schedule(&mut backend, MIDNIGHT + 7 * HOUR);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["Dagens nia är satt till"]), "{:?}", sent);
+
+    handler.poll_schedule(&mut backend, MIDNIGHT + 12 * HOUR);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["Ingen har löst nian"]), "{:?}", sent);
+
+    // Each job runs once a day.
+    handler.poll_schedule(&mut backend, MIDNIGHT + 12 * HOUR + 30);
+    assert!(backend.take_sent().is_empty());
+
+    // The solutions were published at midnight, and the next puzzle doesn't publish them again.
+    handler.poll_schedule(&mut backend, MIDNIGHT + 31 * HOUR);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["GALLTJUTA"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["Dagens nia är satt till"]), "{:?}", sent);
+    assert_eq!(sent.iter().filter(|&&(_, ref msg)| msg.contains("GALLTJUTA")).count(), 1, "{:?}", sent);
+}
+
+#[test]
+fn queue_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_roles(&admin_roles());
+
+    let alice = User("U0".into());
+    let alice_im = Channel("D0".into());
+    let bob = User("U1".into());
+    let bob_im = Channel("D1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+
+    backend.push_message(&bob_im, &bob, "!köa TAGALLTJU");
+    backend.push_message(&bob_im, &bob, "!kö");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert_eq!(sent.len(), 2, "{:?}", sent);
+    assert!(sent.iter().all(|&(_, ref msg)| msg.contains("administratörer")), "{:?}", sent);
+
+    backend.push_message(&alice_im, &alice, "!köa TAG ALL TJU");
+    backend.push_message(&alice_im, &alice, "!kö");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &alice_im, &["TAG ALL TJU", "nummer 1"]), "{:?}", sent);
+    assert!(has_message(&sent, &alice_im, &["1. TAG ALL TJU"]), "{:?}", sent);
+
+    // Anyone may set the next puzzle in the queue.
+    backend.push_message(&main_channel, &bob, "!nästanian");
+    backend.push_message(&main_channel, &bob, "!nästanian");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["Dagens nia är satt till TAG ALL TJU"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["inga köade nior"]), "{:?}", sent);
+}
+
+#[test]
+fn roles_test() {
+    let dictionary = Dictionary::from_file("tests/test_dictionary.txt").unwrap();
+    let main_channel = Channel("C0123".into());
+    let mut roles = Roles::new(Role::Player);
+    roles.set(Name("alice".into()), Role::Admin);
+    let mut handler = NiancatHandler::new(&dictionary);
+    handler.add_game("general", main_channel.clone(), Box::new(MemoryStateStore::default()), &State::default());
+    handler.set_roles(&roles);
+
+    let alice = User("U0".into());
+    let bob = User("U1".into());
+    let mut backend = ScriptedBackend::new();
+    backend.add_user(&alice, &Name("alice".into()));
+    backend.add_user(&bob, &Name("bob".into()));
+
+    backend.push_message(&main_channel, &bob, "!setnian TAGALLTJU");
+    backend.push_message(&main_channel, &bob, "!roll bob admin");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["Bara sättare och administratörer"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["Bara administratörer"]), "{:?}", sent);
+    assert!(!has_message(&sent, &main_channel, &["Dagens nia"]), "{:?}", sent);
+
+    backend.push_message(&main_channel, &alice, "!roll @bob setter");
+    backend.push_message(&main_channel, &bob, "!setnian TAGALLTJU");
+    backend.push_message(&main_channel, &bob, "!roller");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["bob är nu sättare"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["Dagens nia är satt till TAG ALL TJU"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["alice: administratör\nbob: sättare"]), "{:?}", sent);
+
+    // An admin by `!roll`, here given with a mention, is only an admin in the game, and can't reload
+    // the dictionary or demote the admins in the config.
+    backend.push_message(&main_channel, &alice, "!roll <@U1> admin");
+    backend.push_message(&main_channel, &bob, "!laddaom");
+    backend.push_message(&main_channel, &bob, "!roll alice player");
+    backend.run(&mut handler);
+    let sent = backend.take_sent();
+    assert!(has_message(&sent, &main_channel, &["bob är nu administratör"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["Bara administratörer kan ladda om"]), "{:?}", sent);
+    assert!(has_message(&sent, &main_channel, &["alice är administratör enligt konfigurationen"]), "{:?}", sent);
+}
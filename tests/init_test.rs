@@ -4,6 +4,7 @@ extern crate slack;
 use niancat::dictionary::{Dictionary, CheckWord};
 use niancat::types::Word;
 use niancat::types;
+use niancat::store::{State, MemoryStateStore};
 use slack::api::Error;
 use slack::api::channels::ListResponse;
 
@@ -17,8 +18,10 @@ fn init_dictionary_test() {
             assert!(d.is_solution(&Word("ABCDEFGHI".into())));
             assert!(d.is_solution(&Word("GALLTJUTA".into())));
             assert!(d.is_solution(&Word("UVWXYZÅÄÖ".into())));
-            assert!(!d.is_solution(&Word("ABC".into())));
-            assert!(!d.is_solution(&Word("ABCDEF".into())));
+            // Words of all lengths are in the dictionary.
+            assert!(d.is_solution(&Word("ABC".into())));
+            assert!(d.is_solution(&Word("ABCDEF".into())));
+            assert!(!d.is_solution(&Word("ABCD".into())));
         }
     }
 }
@@ -93,20 +96,26 @@ fn init_handler_test() {
     ];
 
     let fake_list_channels = FakeListChannels { v: Some(ListResponse { channels: chans }) };
-    let result = niancat::initialize(&fake_list_channels, &"tests/test_dictionary.txt".into(), &"konsulatet".into());
+    let result = niancat::initialize(&fake_list_channels, &"tests/test_dictionary.txt".into(), &"konsulatet".into(),
+                                     &MemoryStateStore::default());
 
     match result {
         Err(s) => assert!(false, "Initialization failed, because: {}", s),
-        Ok((d, channel_id)) => {
+        Ok((d, channel_id, state)) => {
             // Check that the channel "konsulatet" was found to have the id C0123.
             assert_eq!(channel_id, types::Channel("C0123".into()));
 
+            // No state was saved, so the initial state is empty.
+            assert_eq!(state, State::default());
+
             // Same tests as `init_dictionary_test` above.
             assert!(d.is_solution(&Word("ABCDEFGHI".into())));
             assert!(d.is_solution(&Word("GALLTJUTA".into())));
             assert!(d.is_solution(&Word("UVWXYZÅÄÖ".into())));
-            assert!(!d.is_solution(&Word("ABC".into())));
-            assert!(!d.is_solution(&Word("ABCDEF".into())));
+            // Words of all lengths are in the dictionary.
+            assert!(d.is_solution(&Word("ABC".into())));
+            assert!(d.is_solution(&Word("ABCDEF".into())));
+            assert!(!d.is_solution(&Word("ABCD".into())));
         }
     }
 }
@@ -114,11 +123,12 @@ fn init_handler_test() {
 #[test]
 fn failed_channel_list_init_test() {
     let fake_list_channels = FakeListChannels { v: None };
-    let result = niancat::initialize(&fake_list_channels, &"tests/test_dictionary.txt".into(), &"konsulatet".into());
+    let result = niancat::initialize(&fake_list_channels, &"tests/test_dictionary.txt".into(), &"konsulatet".into(),
+                                     &MemoryStateStore::default());
 
     match result {
         Err(_) => {},
-        Ok((_, channel_id)) => {
+        Ok((_, channel_id, _)) => {
             assert!(false, "Got channel id {:?}, but expected error", channel_id);
         }
     }
@@ -146,7 +156,8 @@ fn failed_dictionary_init_test() {
     ];
 
     let fake_list_channels = FakeListChannels { v: Some(ListResponse { channels: chans }) };
-    let result = niancat::initialize(&fake_list_channels, &"tests/no_such_dictionary.txt".into(), &"konsulatet".into());
+    let result = niancat::initialize(&fake_list_channels, &"tests/no_such_dictionary.txt".into(), &"konsulatet".into(),
+                                     &MemoryStateStore::default());
 
     match result {
         Err(_) => {},
@@ -154,4 +165,37 @@ fn failed_dictionary_init_test() {
             assert!(false, "Got an unexpected dictionary");
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn restore_state_init_test() {
+    let chans: Vec<slack::Channel> = vec![
+       slack::Channel {
+            id: "C0123".into(),
+            name: "konsulatet".into(),
+            is_channel: true,
+            created: 1,
+            creator: "user0".into(),
+            is_archived: false,
+            is_general: false,
+            members: None,
+            topic: None,
+            purpose: None,
+            is_member: true,
+            last_read: None,
+            unread_count: None,
+            unread_count_display: None,
+        },
+    ];
+
+    let saved_state = State { puzzle: Some("GALLTJUTA".into()), ..State::default() };
+    let store = MemoryStateStore { state: Some(saved_state.clone()) };
+    let fake_list_channels = FakeListChannels { v: Some(ListResponse { channels: chans }) };
+    let result = niancat::initialize(&fake_list_channels, &"tests/test_dictionary.txt".into(), &"konsulatet".into(),
+                                     &store);
+
+    match result {
+        Err(s) => assert!(false, "Initialization failed, because: {}", s),
+        Ok((_, _, state)) => assert_eq!(state, saved_state),
+    }
+}